@@ -1,6 +1,8 @@
 pub mod x402;
 
 pub use crate::x402::{
-    deploy, handle_facilitator, handle_test, handle_wallet, init, FacilitatorCommands,
-    TestCommands, WalletCommands,
+    deploy, doctor, handle_config, handle_facilitator, handle_project, handle_test, handle_wallet,
+    init, mock_server, prompt_init, version_check, ConfigCommands, ExistingFilePolicy,
+    FacilitatorCommands, InitOptions, MockServerOptions, OutputFormat, PackageManager,
+    ProjectCommands, TestCommands, WalletCommands, X402Error,
 };
@@ -1,6 +1,10 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use x402_cli::{handle_facilitator, handle_test, handle_wallet, init};
+use clap::{ColorChoice, CommandFactory, FromArgMatches, Parser, Subcommand};
+use colored::Colorize;
+use x402_cli::{
+    handle_config, handle_facilitator, handle_test, handle_wallet, init, prompt_init,
+    ExistingFilePolicy, InitOptions, OutputFormat, PackageManager, X402Error,
+};
 
 #[derive(Parser)]
 #[command(
@@ -9,6 +13,31 @@ use x402_cli::{handle_facilitator, handle_test, handle_wallet, init};
     about = "Developer CLI for x402 projects"
 )]
 struct Cli {
+    /// Directory for wallets, config, and facilitator PID/log files
+    /// (default: $X402_HOME, else ~/.x402)
+    #[arg(long, global = true, value_name = "DIR")]
+    keystore_dir: Option<std::path::PathBuf>,
+    /// Aptos fullnode REST URL for balance and gas queries (default:
+    /// $X402_FULLNODE_URL, else [blockchain].fullnode_url, else the network's
+    /// public fullnode)
+    #[arg(long, global = true, value_name = "URL")]
+    fullnode_url: Option<String>,
+    /// Chains file mapping chain names to networks, fullnode and faucet URLs
+    /// (default: ~/.x402/chains.toml, merged over the built-in chains)
+    #[arg(long, global = true, value_name = "PATH")]
+    chain_config: Option<std::path::PathBuf>,
+    /// Output style: colored `table`, machine-readable `json`, or
+    /// uncolored `plain`
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Table)]
+    format: OutputFormat,
+    /// Disable colored output (also honors NO_COLOR, CLICOLOR=0 and
+    /// CLICOLOR_FORCE; colors are off by default when stdout is not a terminal)
+    #[arg(long, global = true)]
+    no_color: bool,
+    /// Skip the automatic once-a-day check for a newer release (also
+    /// X402_NO_UPDATE_CHECK)
+    #[arg(long, global = true)]
+    no_update_check: bool,
     #[command(subcommand)]
     command: Commands,
 }
@@ -16,12 +45,37 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     Init {
+        /// Project name (prompted for when omitted in a terminal)
         #[arg(short, long)]
-        name: String,
+        name: Option<String>,
+        /// Blockchain, e.g. aptos (prompted for when omitted in a terminal)
         #[arg(short, long)]
-        chain: String,
+        chain: Option<String>,
+        /// Framework: next, react, or vanilla (prompted for when omitted in a terminal)
         #[arg(short, long)]
-        framework: String,
+        framework: Option<String>,
+        /// Prompt for every value, using any flags given as defaults, and
+        /// confirm before scaffolding
+        #[arg(short, long)]
+        interactive: bool,
+        /// Overwrite generated files that already exist
+        #[arg(long, conflicts_with = "backup")]
+        force: bool,
+        /// Back up existing generated files to `<file>.bak` before rewriting
+        #[arg(long)]
+        backup: bool,
+        /// Package manager used to initialize the project and in generated docs
+        #[arg(long, value_enum, default_value_t = PackageManager::Npm)]
+        package_manager: PackageManager,
+        /// Only scaffold files; skip dependency installation
+        #[arg(long)]
+        no_install: bool,
+        /// Parent directory to create the project in (default: current directory)
+        #[arg(long, value_name = "PATH")]
+        output_dir: Option<std::path::PathBuf>,
+        /// Remove the partially created project if any step fails
+        #[arg(long)]
+        clean: bool,
     },
     Wallet {
         #[command(subcommand)]
@@ -33,26 +87,193 @@ enum Commands {
     },
     Test {
         #[command(subcommand)]
-        command: x402_cli::TestCommands,
+        command: Box<x402_cli::TestCommands>,
     },
     Deploy {
         #[arg(short, long)]
         provider: String,
+        /// Print the commands the deploy would run without executing them
+        #[arg(long)]
+        dry_run: bool,
+        /// Dotenv-style file whose variables are passed to the provider CLI
+        #[arg(long, value_name = "PATH")]
+        env_file: Option<std::path::PathBuf>,
+        /// Stop the release build and fail if it takes longer than this
+        #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+        build_timeout: Option<u64>,
+        /// Skip `cargo build --release` and deploy the existing release binary
+        #[arg(long, conflicts_with = "build_timeout")]
+        no_build: bool,
+        /// Return once Vercel accepts the deployment instead of waiting for
+        /// it to be ready
+        #[arg(long)]
+        no_wait: bool,
+    },
+    Config {
+        #[command(subcommand)]
+        command: x402_cli::ConfigCommands,
+    },
+    Project {
+        #[command(subcommand)]
+        command: x402_cli::ProjectCommands,
+    },
+    /// Check required tools, the ~/.x402 directory, and network access
+    Doctor,
+    /// Check whether a newer release of the CLI is published
+    #[command(name = "version-check")]
+    VersionCheck,
+    /// Run a local stand-in x402 API to exercise `test payment` against
+    #[command(name = "mock-server")]
+    MockServer {
+        #[arg(short, long, default_value_t = x402_cli::x402::mock_server::DEFAULT_PORT)]
+        port: u16,
+        /// Address to bind
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+        /// Price in octas or with a unit (`1000octas`, `0.5APT`)
+        #[arg(long, default_value = "1000", value_parser = x402_cli::x402::test::parse_amount)]
+        amount: u64,
+        /// Asset to charge in
+        #[arg(long, default_value = "0x1::aptos_coin::AptosCoin")]
+        asset: String,
+        /// Recipient address (default: the default wallet)
+        #[arg(long, value_parser = x402_cli::x402::wallet::parse_address)]
+        pay_to: Option<String>,
+        /// Network named in the payment requirements
+        #[arg(short, long, default_value = "aptos:testnet")]
+        network: String,
     },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    env_logger::init();
-    let cli = Cli::parse();
+    // `--no-color` has to be known before parsing so clap's own help and
+    // errors honor it too.
+    let color = if std::env::args_os().any(|arg| arg == "--no-color") {
+        ColorChoice::Never
+    } else {
+        ColorChoice::Auto
+    };
+    let matches = Cli::command().color(color).get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    init_logger(&cli);
+    let json_flag = uses_json_flag(&matches);
+    x402_cli::x402::ui::set_format(if json_flag {
+        OutputFormat::Json
+    } else {
+        cli.format
+    });
+    x402_cli::x402::ui::set_color(cli.no_color);
+    if json_flag {
+        x402_cli::x402::ui::warn("--json is deprecated; use --format json");
+    }
+
+    if let Some(dir) = &cli.keystore_dir {
+        x402_cli::x402::config::set_x402_dir(dir.clone());
+    }
+
+    if let Some(url) = &cli.fullnode_url {
+        x402_cli::x402::config::set_fullnode_url(url.clone());
+    }
+
+    if let Some(path) = &cli.chain_config {
+        x402_cli::x402::chains::set_chain_config(path.clone());
+    }
+
+    let update_check = match cli.command {
+        Commands::VersionCheck => None,
+        _ => x402_cli::x402::update::start_daily_check(cli.no_update_check),
+    };
+
+    let result = match x402_cli::x402::chains::init() {
+        Ok(()) => run(cli).await,
+        Err(e) => Err(e),
+    };
+    if let (Ok(()), Some(update_check)) = (&result, update_check) {
+        update_check.notify().await;
+    }
+    if let Err(error) = result {
+        eprintln!("Error: {:?}", error);
+        if let Some(x402_error) = error.downcast_ref::<X402Error>() {
+            eprintln!("{}", format!("  Hint: {}", x402_error.hint()).yellow());
+        }
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
 
+/// Whether the subcommand was given its deprecated `--json` flag, which
+/// now just selects `--format json`.
+fn uses_json_flag(matches: &clap::ArgMatches) -> bool {
+    match matches.subcommand() {
+        Some((_, sub)) => {
+            sub.try_get_one::<bool>("json").ok().flatten() == Some(&true) || uses_json_flag(sub)
+        }
+        None => false,
+    }
+}
+
+/// Honors `RUST_LOG`, and turns on HTTP wire logging for `--debug-http`.
+fn init_logger(cli: &Cli) {
+    let mut builder = env_logger::Builder::from_default_env();
+
+    if let Commands::Test { command } = &cli.command {
+        if let x402_cli::TestCommands::Payment {
+            debug_http: true, ..
+        } = command.as_ref()
+        {
+            builder.filter_module(
+                x402_cli::x402::test::HTTP_LOG_TARGET,
+                log::LevelFilter::Debug,
+            );
+        }
+    }
+
+    if cli.no_color {
+        builder.write_style(env_logger::WriteStyle::Never);
+    }
+
+    builder.init();
+}
+
+async fn run(cli: Cli) -> Result<()> {
     match cli.command {
         Commands::Init {
             name,
             chain,
             framework,
+            interactive,
+            force,
+            backup,
+            package_manager,
+            no_install,
+            output_dir,
+            clean,
         } => {
-            init(name, chain, framework).await?;
+            let Some(answers) = prompt_init(name, chain, framework, interactive)? else {
+                println!("{}", "Aborted; nothing was created".yellow());
+                return Ok(());
+            };
+
+            let on_existing = if force {
+                ExistingFilePolicy::Overwrite
+            } else if backup {
+                ExistingFilePolicy::Backup
+            } else {
+                ExistingFilePolicy::Skip
+            };
+            init(InitOptions {
+                name: answers.name,
+                chain: answers.chain,
+                framework: answers.framework,
+                on_existing,
+                package_manager,
+                install: !no_install,
+                output_dir,
+                clean,
+            })
+            .await?;
         }
         Commands::Wallet { command } => {
             handle_wallet(command).await?;
@@ -61,10 +282,51 @@ async fn main() -> Result<()> {
             handle_facilitator(command).await?;
         }
         Commands::Test { command } => {
-            handle_test(command).await?;
+            handle_test(*command).await?;
+        }
+        Commands::Deploy {
+            provider,
+            dry_run,
+            env_file,
+            build_timeout,
+            no_build,
+            no_wait,
+        } => {
+            let build = x402_cli::x402::deploy::BuildOptions {
+                timeout: build_timeout.map(std::time::Duration::from_secs),
+                skip: no_build,
+            };
+            x402_cli::deploy(provider, dry_run, env_file, build, !no_wait).await?;
         }
-        Commands::Deploy { provider } => {
-            x402_cli::deploy(provider).await?;
+        Commands::Config { command } => {
+            handle_config(command).await?;
+        }
+        Commands::Project { command } => {
+            x402_cli::handle_project(command).await?;
+        }
+        Commands::Doctor => {
+            x402_cli::doctor().await?;
+        }
+        Commands::VersionCheck => {
+            x402_cli::version_check().await?;
+        }
+        Commands::MockServer {
+            port,
+            host,
+            amount,
+            asset,
+            pay_to,
+            network,
+        } => {
+            x402_cli::mock_server(x402_cli::MockServerOptions {
+                host,
+                port,
+                amount,
+                asset,
+                pay_to,
+                network,
+            })
+            .await?;
         }
     }
 
@@ -0,0 +1,288 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::x402::config;
+use crate::x402::error::X402Error;
+
+/// Chain assumed for bare network names (`testnet`), such as the ones
+/// saved wallets record.
+pub const DEFAULT_CHAIN: &str = "aptos";
+
+/// File in the x402 directory whose chains extend and override the
+/// built-in ones.
+pub const CHAINS_FILE_NAME: &str = "chains.toml";
+
+/// The chains this CLI knows without any configuration.
+const BUILTIN_CHAINS: &str = r#"
+[aptos.networks.mainnet]
+chain_id = 1
+fullnode_url = "https://fullnode.mainnet.aptoslabs.com/v1"
+
+[aptos.networks.testnet]
+chain_id = 2
+fullnode_url = "https://fullnode.testnet.aptoslabs.com/v1"
+faucet_url = "https://faucet.testnet.aptoslabs.com"
+
+[aptos.networks.devnet]
+fullnode_url = "https://fullnode.devnet.aptoslabs.com/v1"
+
+[aptos.networks.local]
+fullnode_url = "http://127.0.0.1:8080/v1"
+"#;
+
+static CHAIN_CONFIG_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+static REGISTRY: OnceLock<ChainRegistry> = OnceLock::new();
+
+/// One chain's settings, e.g. the `[aptos]` table of `chains.toml`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChainConfig {
+    #[serde(default)]
+    pub networks: BTreeMap<String, NetworkConfig>,
+}
+
+/// One network of a chain, e.g. `[aptos.networks.testnet]`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkConfig {
+    /// Numeric id that also names the network (`aptos:2`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fullnode_url: Option<String>,
+    /// Faucet wallets on this network are funded from; networks without one
+    /// are never funded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub faucet_url: Option<String>,
+}
+
+/// Chain name → its settings: the built-in chains merged with
+/// `chains.toml`.
+#[derive(Debug, Clone, Default)]
+pub struct ChainRegistry {
+    chains: BTreeMap<String, ChainConfig>,
+}
+
+impl ChainRegistry {
+    pub fn builtin() -> Self {
+        let chains = toml::from_str(BUILTIN_CHAINS).expect("built-in chains are valid TOML");
+        ChainRegistry { chains }
+    }
+
+    /// The built-in chains with `path`'s merged over them. Settings given in
+    /// the file win; anything it leaves out keeps its built-in value.
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read chains file: {}", path.display()))?;
+        Self::parse(&source)
+            .with_context(|| format!("Failed to parse chains file: {}", path.display()))
+    }
+
+    /// The built-in chains with the chains in `source` merged over them.
+    fn parse(source: &str) -> Result<Self> {
+        let chains: BTreeMap<String, ChainConfig> = toml::from_str(source)?;
+
+        let mut registry = Self::builtin();
+        for (name, chain) in chains {
+            registry.merge(name.to_lowercase(), chain);
+        }
+        Ok(registry)
+    }
+
+    fn merge(&mut self, name: String, chain: ChainConfig) {
+        let existing = self.chains.entry(name).or_default();
+        for (name, network) in chain.networks {
+            let entry = existing.networks.entry(name).or_default();
+            if network.chain_id.is_some() {
+                entry.chain_id = network.chain_id;
+            }
+            if network.fullnode_url.is_some() {
+                entry.fullnode_url = network.fullnode_url;
+            }
+            if network.faucet_url.is_some() {
+                entry.faucet_url = network.faucet_url;
+            }
+        }
+    }
+
+    pub fn chain_names(&self) -> Vec<&str> {
+        self.chains.keys().map(String::as_str).collect()
+    }
+
+    pub fn chain(&self, name: &str) -> Option<&ChainConfig> {
+        self.chains.get(&name.to_lowercase())
+    }
+
+    /// Looks up a network given as `<chain>:<network>`, `<chain>:<chain_id>`,
+    /// or a bare name on [`DEFAULT_CHAIN`]. Returns the chain name, the
+    /// network name, and its settings.
+    pub fn resolve(&self, network: &str) -> Option<(&str, &str, &NetworkConfig)> {
+        let (chain, network) = network.split_once(':').unwrap_or((DEFAULT_CHAIN, network));
+        let (chain_name, chain) = self.chains.get_key_value(&chain.to_lowercase())?;
+        let chain_id = network.parse::<u64>().ok();
+        chain
+            .networks
+            .iter()
+            .find(|(name, config)| {
+                name.eq_ignore_ascii_case(network)
+                    || (chain_id.is_some() && config.chain_id == chain_id)
+            })
+            .map(|(name, config)| (chain_name.as_str(), name.as_str(), config))
+    }
+
+    /// Like [`resolve`](Self::resolve), but fails with the known networks.
+    pub fn require(&self, network: &str) -> Result<(&str, &str, &NetworkConfig)> {
+        self.resolve(network)
+            .ok_or_else(|| X402Error::UnknownNetwork(network.to_string()).into())
+    }
+
+    /// The name saved wallets record for `network`: bare on
+    /// [`DEFAULT_CHAIN`] (`testnet`), `<chain>:<network>` elsewhere.
+    pub fn wallet_network(&self, network: &str) -> Result<String> {
+        let (chain, network, _) = self.require(network)?;
+        if chain == DEFAULT_CHAIN {
+            Ok(network.to_string())
+        } else {
+            Ok(format!("{}:{}", chain, network))
+        }
+    }
+
+    pub fn fullnode_url(&self, network: &str) -> Option<&str> {
+        self.resolve(network)?.2.fullnode_url.as_deref()
+    }
+
+    pub fn faucet_url(&self, network: &str) -> Option<&str> {
+        self.resolve(network)?.2.faucet_url.as_deref()
+    }
+
+    /// Every network as `<chain>:<network>`, for error messages.
+    pub fn network_ids(&self) -> Vec<String> {
+        self.chains
+            .iter()
+            .flat_map(|(chain, config)| {
+                config
+                    .networks
+                    .keys()
+                    .map(move |network| format!("{}:{}", chain, network))
+            })
+            .collect()
+    }
+}
+
+/// Reads chains from `path` instead of `~/.x402/chains.toml` for the rest of
+/// the process (`--chain-config`). Only the first call has an effect.
+pub fn set_chain_config(path: PathBuf) {
+    let _ = CHAIN_CONFIG_OVERRIDE.set(path);
+}
+
+/// The chains file in use: `--chain-config`, else `chains.toml` in the x402
+/// directory.
+pub fn chains_file() -> Result<PathBuf> {
+    match CHAIN_CONFIG_OVERRIDE.get() {
+        Some(path) => Ok(path.clone()),
+        None => Ok(config::x402_dir()?.join(CHAINS_FILE_NAME)),
+    }
+}
+
+/// Loads the registry once, at startup. A missing `~/.x402/chains.toml`
+/// means the built-in chains; a missing `--chain-config` file is an error.
+pub fn init() -> Result<()> {
+    let path = chains_file()?;
+    let registry = if CHAIN_CONFIG_OVERRIDE.get().is_some() || path.is_file() {
+        ChainRegistry::load(&path)?
+    } else {
+        ChainRegistry::builtin()
+    };
+    let _ = REGISTRY.set(registry);
+    Ok(())
+}
+
+/// The loaded registry, or the built-in chains if [`init`] wasn't called.
+pub fn registry() -> &'static ChainRegistry {
+    REGISTRY.get_or_init(ChainRegistry::builtin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_keep_unset_builtin_settings() {
+        let registry = ChainRegistry::parse(
+            r#"
+[aptos.networks.testnet]
+fullnode_url = "http://127.0.0.1:9000/v1"
+"#,
+        )
+        .unwrap();
+
+        let (chain, network, config) = registry.require("aptos:2").unwrap();
+        assert_eq!((chain, network), ("aptos", "testnet"));
+        assert_eq!(config.chain_id, Some(2));
+        assert_eq!(
+            config.fullnode_url.as_deref(),
+            Some("http://127.0.0.1:9000/v1")
+        );
+        assert_eq!(
+            config.faucet_url.as_deref(),
+            Some("https://faucet.testnet.aptoslabs.com")
+        );
+        assert_eq!(
+            registry.fullnode_url("mainnet"),
+            Some("https://fullnode.mainnet.aptoslabs.com/v1")
+        );
+    }
+
+    #[test]
+    fn adds_new_chains_and_networks() {
+        let registry = ChainRegistry::parse(
+            r#"
+[aptos.networks.staging]
+fullnode_url = "https://staging.example.com/v1"
+
+[Movement.networks.testnet]
+chain_id = 250
+fullnode_url = "https://movement.example.com/v1"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            registry.fullnode_url("staging"),
+            Some("https://staging.example.com/v1")
+        );
+        assert_eq!(registry.chain_names(), vec!["aptos", "movement"]);
+        let (chain, network, _) = registry.require("MOVEMENT:250").unwrap();
+        assert_eq!((chain, network), ("movement", "testnet"));
+        assert_eq!(
+            registry.wallet_network("movement:testnet").unwrap(),
+            "movement:testnet"
+        );
+        assert_eq!(registry.wallet_network("aptos:2").unwrap(), "testnet");
+        assert!(registry.faucet_url("movement:testnet").is_none());
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        let err = ChainRegistry::parse(
+            r#"
+[aptos.networks.testnet]
+coin_type = "0x1::aptos_coin::AptosCoin"
+"#,
+        )
+        .unwrap_err();
+        assert!(format!("{:#}", err).contains("coin_type"));
+    }
+
+    #[test]
+    fn unknown_networks_are_not_resolved() {
+        let registry = ChainRegistry::builtin();
+        assert!(registry.resolve("aptos:99").is_none());
+        assert!(registry.resolve("sui:testnet").is_none());
+        assert!(registry.require("nope").is_err());
+    }
+}
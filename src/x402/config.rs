@@ -0,0 +1,531 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+pub const DEFAULT_CONFIG_PATH: &str = "config/x402.toml";
+
+/// Environment variable that overrides the base directory, like `--keystore-dir`.
+pub const X402_HOME_ENV: &str = "X402_HOME";
+
+static X402_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Environment variable that overrides the fullnode, like `--fullnode-url`.
+pub const X402_FULLNODE_URL_ENV: &str = "X402_FULLNODE_URL";
+
+static FULLNODE_URL_OVERRIDE: OnceLock<String> = OnceLock::new();
+
+/// Points [`x402_dir`] at `dir` for the rest of the process
+/// (`--keystore-dir`). Only the first call has an effect.
+pub fn set_x402_dir(dir: PathBuf) {
+    let _ = X402_DIR_OVERRIDE.set(dir);
+}
+
+/// Base directory for CLI state (wallets, config, facilitator PID and log
+/// files, ...): `--keystore-dir`, else `$X402_HOME`, else `~/.x402`.
+pub fn x402_dir() -> Result<PathBuf> {
+    if let Some(dir) = X402_DIR_OVERRIDE.get() {
+        return Ok(dir.clone());
+    }
+
+    if let Some(dir) = std::env::var_os(X402_HOME_ENV).filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+
+    let mut dir = dirs::home_dir().context("Failed to determine home directory")?;
+    dir.push(".x402");
+    Ok(dir)
+}
+
+/// Points [`fullnode_url`] at `url` for every network for the rest of the
+/// process (`--fullnode-url`). Only the first call has an effect.
+pub fn set_fullnode_url(url: String) {
+    let _ = FULLNODE_URL_OVERRIDE.set(url.trim_end_matches('/').to_string());
+}
+
+/// The chain registry's fullnode REST endpoint for a network, given either
+/// as an x402 network id (`aptos:testnet`, `aptos:2`) or a bare name
+/// (`testnet`).
+pub fn default_fullnode_url(network: &str) -> Option<&'static str> {
+    crate::x402::chains::registry().fullnode_url(network)
+}
+
+/// Fullnode REST endpoint for `network`: `--fullnode-url`, else
+/// `$X402_FULLNODE_URL`, else `[blockchain].fullnode_url` in the current
+/// project's `config/x402.toml`, else the network's registry default.
+pub fn fullnode_url(network: &str) -> Option<String> {
+    if let Some(url) = FULLNODE_URL_OVERRIDE.get() {
+        return Some(url.clone());
+    }
+
+    if let Some(url) = std::env::var(X402_FULLNODE_URL_ENV)
+        .ok()
+        .filter(|url| !url.trim().is_empty())
+    {
+        return Some(url.trim_end_matches('/').to_string());
+    }
+
+    let project_url = Path::new(DEFAULT_CONFIG_PATH)
+        .is_file()
+        .then(|| Config::load(Path::new(DEFAULT_CONFIG_PATH)).ok())
+        .flatten()
+        .and_then(|config| config.blockchain.fullnode_url);
+    if let Some(url) = project_url {
+        return Some(url.trim_end_matches('/').to_string());
+    }
+
+    default_fullnode_url(network).map(str::to_string)
+}
+
+/// Per-user CLI settings stored in `~/.x402/config.toml`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_wallet: Option<String>,
+}
+
+impl UserConfig {
+    pub fn path() -> Result<PathBuf> {
+        Ok(x402_dir()?.join("config.toml"))
+    }
+
+    /// Loads the user config, or an empty one if the file doesn't exist yet.
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+
+        toml::from_str(&source)
+            .with_context(|| format!("Failed to parse config file: {}", path.display()))
+    }
+
+    pub fn save(&self) -> Result<PathBuf> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create x402 directory")?;
+        }
+
+        let source = toml::to_string(self).context("Failed to serialize config")?;
+        fs::write(&path, source)
+            .with_context(|| format!("Failed to write config file: {}", path.display()))?;
+
+        Ok(path)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub project_name: String,
+    pub chain: String,
+    pub framework: String,
+    pub version: String,
+    pub server: ServerConfig,
+    pub blockchain: BlockchainConfig,
+    pub facilitator: FacilitatorConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerConfig {
+    pub port: u16,
+    pub host: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockchainConfig {
+    pub network: String,
+    /// Fullnode REST endpoint for a private or self-hosted node; the
+    /// network's public fullnode when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fullnode_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FacilitatorConfig {
+    pub enabled: bool,
+    pub port: u16,
+    /// Address `facilitator start` binds (default `127.0.0.1`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wallet: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fullnode_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_body_size: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_connections: Option<usize>,
+}
+
+/// Defaults for `facilitator start`, read from `~/.x402/facilitator.toml`
+/// and the project's `[facilitator]` section. Command-line flags win.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FacilitatorSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// Address to bind, e.g. `0.0.0.0` to accept outside connections.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    /// Address or alias of a saved wallet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wallet: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fullnode_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_body_size: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_connections: Option<usize>,
+}
+
+impl FacilitatorSettings {
+    pub fn path() -> Result<PathBuf> {
+        Ok(x402_dir()?.join("facilitator.toml"))
+    }
+
+    /// Loads `~/.x402/facilitator.toml`, then lets the current project's
+    /// `[facilitator]` section override it field by field. Missing files
+    /// give empty settings.
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        let mut settings = if path.exists() {
+            let source = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+            toml::from_str(&source)
+                .with_context(|| format!("Failed to parse config file: {}", path.display()))?
+        } else {
+            Self::default()
+        };
+
+        let project_path = Path::new(DEFAULT_CONFIG_PATH);
+        if project_path.is_file() {
+            let project = Config::load(project_path)?.facilitator;
+            settings.merge(FacilitatorSettings {
+                port: Some(project.port),
+                host: project.host,
+                wallet: project.wallet,
+                network: project.network,
+                fullnode_url: project.fullnode_url,
+                max_body_size: project.max_body_size,
+                max_connections: project.max_connections,
+            });
+        }
+
+        Ok(settings)
+    }
+
+    /// Takes every field `other` sets.
+    fn merge(&mut self, other: FacilitatorSettings) {
+        self.port = other.port.or(self.port);
+        self.host = other.host.or(self.host.take());
+        self.wallet = other.wallet.or(self.wallet.take());
+        self.network = other.network.or(self.network.take());
+        self.fullnode_url = other.fullnode_url.or(self.fullnode_url.take());
+        self.max_body_size = other.max_body_size.or(self.max_body_size);
+        self.max_connections = other.max_connections.or(self.max_connections);
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigProblem {
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+
+        toml::from_str(&source)
+            .with_context(|| format!("Failed to parse config file: {}", path.display()))
+    }
+
+    /// The project's network in requirements form, e.g. `aptos:testnet`.
+    /// `init` writes the chain name as the network, which means testnet.
+    pub fn payment_network(&self) -> String {
+        let network = self.blockchain.network.as_str();
+        if crate::x402::chains::registry().chain(network).is_some() {
+            format!("{}:testnet", self.chain)
+        } else {
+            format!("{}:{}", self.chain, network)
+        }
+    }
+
+    /// Parses and checks `source`, returning every problem found. An empty
+    /// list means the config is valid.
+    pub fn validate_source(source: &str) -> Vec<ConfigProblem> {
+        match toml::from_str::<Config>(source) {
+            Ok(config) => config.validate(source),
+            Err(e) => vec![ConfigProblem {
+                line: e.span().map(|span| line_at_offset(source, span.start)),
+                message: e.message().to_string(),
+            }],
+        }
+    }
+
+    pub fn validate(&self, source: &str) -> Vec<ConfigProblem> {
+        let mut problems = Vec::new();
+        let mut problem = |section: Option<&str>, key: &str, message: String| {
+            problems.push(ConfigProblem {
+                line: find_key_line(source, section, key),
+                message,
+            });
+        };
+
+        for (key, value) in [
+            ("project_name", &self.project_name),
+            ("chain", &self.chain),
+            ("framework", &self.framework),
+            ("version", &self.version),
+        ] {
+            if value.trim().is_empty() {
+                problem(None, key, format!("`{}` must not be empty", key));
+            }
+        }
+
+        let registry = crate::x402::chains::registry();
+        let chain = registry.chain(&self.chain);
+        if !self.chain.trim().is_empty() && chain.is_none() {
+            problem(
+                None,
+                "chain",
+                format!(
+                    "Unknown chain '{}' (supported: {})",
+                    self.chain,
+                    registry.chain_names().join(", ")
+                ),
+            );
+        }
+
+        if self.server.host.trim().is_empty() {
+            problem(
+                Some("server"),
+                "host",
+                "`server.host` must not be empty".to_string(),
+            );
+        }
+
+        if self.server.port == 0 {
+            problem(
+                Some("server"),
+                "port",
+                "`server.port` must be between 1 and 65535".to_string(),
+            );
+        }
+
+        // `init` writes the chain name as the network, so accept chains here
+        // too. The chain's own networks are only known if the chain is.
+        let network = self.blockchain.network.as_str();
+        if let Some(chain) = chain {
+            if !chain.networks.contains_key(network) && registry.chain(network).is_none() {
+                let known: Vec<&str> = chain.networks.keys().map(String::as_str).collect();
+                problem(
+                    Some("blockchain"),
+                    "network",
+                    format!(
+                        "Unknown network '{}' (supported: {})",
+                        network,
+                        known.join(", ")
+                    ),
+                );
+            }
+        }
+
+        if let Some(url) = &self.blockchain.fullnode_url {
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                problem(
+                    Some("blockchain"),
+                    "fullnode_url",
+                    format!(
+                        "`blockchain.fullnode_url` must be an http(s) URL, got '{}'",
+                        url
+                    ),
+                );
+            }
+        }
+
+        if self.facilitator.port == 0 {
+            problem(
+                Some("facilitator"),
+                "port",
+                "`facilitator.port` must be between 1 and 65535".to_string(),
+            );
+        }
+
+        if let Some(host) = &self.facilitator.host {
+            if host.trim().is_empty() {
+                problem(
+                    Some("facilitator"),
+                    "host",
+                    "`facilitator.host` must not be empty".to_string(),
+                );
+            }
+        }
+
+        if let Some(url) = &self.facilitator.fullnode_url {
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                problem(
+                    Some("facilitator"),
+                    "fullnode_url",
+                    format!(
+                        "`facilitator.fullnode_url` must be an http(s) URL, got '{}'",
+                        url
+                    ),
+                );
+            }
+        }
+
+        if self.facilitator.enabled && self.facilitator.port == self.server.port {
+            problem(
+                Some("facilitator"),
+                "port",
+                format!(
+                    "`facilitator.port` conflicts with `server.port` ({})",
+                    self.server.port
+                ),
+            );
+        }
+
+        problems
+    }
+}
+
+fn line_at_offset(source: &str, offset: usize) -> usize {
+    source[..offset.min(source.len())].matches('\n').count() + 1
+}
+
+fn find_key_line(source: &str, section: Option<&str>, key: &str) -> Option<usize> {
+    let mut current_section: Option<&str> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let trimmed = line.trim();
+
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            current_section = Some(trimmed.trim_matches(|c| c == '[' || c == ']').trim());
+            continue;
+        }
+
+        if current_section != section {
+            continue;
+        }
+
+        if let Some((name, _)) = trimmed.split_once('=') {
+            if name.trim() == key {
+                return Some(index + 1);
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID: &str = r#"project_name = "demo"
+chain = "aptos"
+framework = "express"
+version = "1.0.0"
+
+[server]
+port = 3000
+host = "localhost"
+
+[blockchain]
+network = "testnet"
+
+[facilitator]
+enabled = true
+port = 3001
+"#;
+
+    fn problems(source: &str) -> Vec<(Option<usize>, String)> {
+        Config::validate_source(source)
+            .into_iter()
+            .map(|problem| (problem.line, problem.message))
+            .collect()
+    }
+
+    #[test]
+    fn accepts_a_valid_config() {
+        assert!(problems(VALID).is_empty());
+        // `init` writes the chain name as the network.
+        assert!(problems(&VALID.replace("\"testnet\"", "\"aptos\"")).is_empty());
+    }
+
+    #[test]
+    fn reports_syntax_errors_with_their_line() {
+        let source = VALID.replace("port = 3000", "port = ");
+        let problems = problems(&source);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].0, Some(7));
+    }
+
+    #[test]
+    fn reports_bad_values_at_their_key() {
+        let source = VALID
+            .replace("project_name = \"demo\"", "project_name = \" \"")
+            .replace("chain = \"aptos\"", "chain = \"solana\"")
+            .replace("port = 3000", "port = 0")
+            .replace(
+                "network = \"testnet\"",
+                "network = \"testnet\"\nfullnode_url = \"ftp://node\"",
+            );
+        assert_eq!(
+            problems(&source),
+            vec![
+                (Some(1), "`project_name` must not be empty".to_string()),
+                (
+                    Some(2),
+                    "Unknown chain 'solana' (supported: aptos)".to_string()
+                ),
+                (
+                    Some(7),
+                    "`server.port` must be between 1 and 65535".to_string()
+                ),
+                (
+                    Some(12),
+                    "`blockchain.fullnode_url` must be an http(s) URL, got 'ftp://node'"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_unknown_networks() {
+        let source = VALID.replace("\"testnet\"", "\"staging\"");
+        assert_eq!(
+            problems(&source),
+            vec![(
+                Some(11),
+                "Unknown network 'staging' (supported: devnet, local, mainnet, testnet)"
+                    .to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn reports_a_facilitator_port_conflict_only_when_enabled() {
+        let source = VALID.replace("port = 3001", "port = 3000");
+        assert_eq!(
+            problems(&source),
+            vec![(
+                Some(15),
+                "`facilitator.port` conflicts with `server.port` (3000)".to_string()
+            )]
+        );
+
+        let source = source.replace("enabled = true", "enabled = false");
+        assert!(problems(&source).is_empty());
+    }
+}
@@ -1,95 +1,854 @@
 use anyhow::{Context, Result};
-use colored::Colorize;
-use std::process::Command;
+use colored::{ColoredString, Colorize};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
 
-pub async fn deploy(provider: &str) -> Result<()> {
-    match provider.to_lowercase().as_str() {
-        "vercel" | "vercel.app" => deploy_to_vercel().await,
-        _ => {
-            println!(
-                "{}",
-                format!("  ⚠ Provider '{}' not yet supported", provider)
-                    .yellow()
-                    .dimmed()
-            );
-            println!("{}", "  Supported providers: vercel".dimmed());
-            Ok(())
+use crate::x402::error::X402Error;
+use crate::x402::ui;
+
+/// Environment variables passed to the deployment, in file order.
+pub type EnvVars = Vec<(String, String)>;
+
+const MASK: &str = "****";
+
+const VERCEL_API_URL: &str = "https://api.vercel.com";
+const VERCEL_DASHBOARD_URL: &str = "https://vercel.com/dashboard";
+/// Token the Vercel API calls authenticate with, as for the Vercel CLI.
+const VERCEL_TOKEN_VAR: &str = "VERCEL_TOKEN";
+const DEPLOY_POLL_INITIAL_SECS: u64 = 2;
+const DEPLOY_POLL_MAX_SECS: u64 = 30;
+/// How long to wait for a deployment to become ready before giving up.
+const DEPLOY_WAIT_TIMEOUT_SECS: u64 = 20 * 60;
+
+/// How the deploy's `cargo build --release` step runs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BuildOptions {
+    /// Kill the build and fail if it runs longer than this.
+    pub timeout: Option<Duration>,
+    /// Skip the build and deploy the existing release artifact.
+    pub skip: bool,
+}
+
+/// Where a deploy ended up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeployStatus {
+    /// `--dry-run`: the steps were printed and nothing ran.
+    DryRun,
+    /// The provider isn't one this CLI deploys to.
+    Unsupported,
+    /// The provider accepted the deployment; it may still be building.
+    Initiated,
+    /// The deployment finished building and is serving.
+    Ready,
+    /// The provider CLI exited with an error, so the deployment may not
+    /// exist.
+    MayHaveFailed,
+    /// The deployment's build failed or was canceled.
+    Failed,
+    /// Waiting for the deployment broke off before it finished.
+    Unknown,
+}
+
+impl DeployStatus {
+    fn label(self) -> ColoredString {
+        match self {
+            DeployStatus::DryRun => "dry run".dimmed(),
+            DeployStatus::Unsupported => "unsupported".yellow(),
+            DeployStatus::Initiated => "initiated".green(),
+            DeployStatus::Ready => "ready".green(),
+            DeployStatus::MayHaveFailed => "may have failed".yellow(),
+            DeployStatus::Failed => "failed".red(),
+            DeployStatus::Unknown => "unknown".yellow(),
         }
     }
 }
 
-async fn deploy_to_vercel() -> Result<()> {
-    println!("{}", "  Step 1: Building facilitator...".dimmed());
+/// What [`deploy`] did, for the caller to report.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeployResult {
+    /// `vercel` or `railway`, or the unsupported name as given.
+    pub provider: String,
+    pub status: DeployStatus,
+    /// The deployment's public URL, when the provider reported one.
+    pub url: Option<String>,
+    /// Why the deployment failed or couldn't be followed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Names of the environment variables passed to the deployment.
+    pub environment: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dashboard: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub build_logs: Option<String>,
+}
 
-    let build_result = Command::new("cargo")
-        .args(["build", "--release"])
-        .output()
-        .context("Failed to build project")?;
+impl DeployResult {
+    fn new(provider: &str, status: DeployStatus, env: &EnvVars) -> Self {
+        DeployResult {
+            provider: provider.to_string(),
+            status,
+            url: None,
+            error: None,
+            environment: env.iter().map(|(key, _)| key.clone()).collect(),
+            dashboard: None,
+            build_logs: None,
+        }
+    }
+
+    /// Prints the closing deployment summary.
+    pub fn print_summary(&self) {
+        let provider = match self.provider.as_str() {
+            "vercel" => "Vercel".to_string(),
+            "railway" => "Railway".to_string(),
+            other => other.to_string(),
+        };
+        let environment = if self.environment.is_empty() {
+            "none".to_string()
+        } else {
+            self.environment.join(", ")
+        };
+
+        let mut pairs = vec![
+            ("Provider", provider),
+            ("Status", self.status.label().to_string()),
+            ("Environment", environment),
+            (
+                "URL",
+                self.url
+                    .as_ref()
+                    .map_or_else(|| "unknown".to_string(), |url| url.cyan().to_string()),
+            ),
+        ];
+        if let Some(url) = &self.build_logs {
+            pairs.push(("Build logs", url.clone()));
+        }
+        if let Some(url) = &self.dashboard {
+            pairs.push(("Dashboard", url.cyan().to_string()));
+        }
+        ui::summary("Deployment Summary", &pairs);
+    }
 
-    if !build_result.status.success() {
-        let error = String::from_utf8_lossy(&build_result.stderr);
-        anyhow::bail!("Build failed: {}", error);
+    /// Fails when the deployment failed or couldn't be followed to the end.
+    pub fn check(&self) -> Result<()> {
+        let error = self.error.clone().unwrap_or_default();
+        match self.status {
+            DeployStatus::Failed => Err(X402Error::DeploymentFailed(error).into()),
+            DeployStatus::Unknown => Err(anyhow::anyhow!(error)),
+            _ => Ok(()),
+        }
     }
+}
 
-    println!("{}", "  ✓ Build successful".green().dimmed());
+fn is_not_found(error: &std::io::Error) -> bool {
+    error.kind() == std::io::ErrorKind::NotFound
+}
+
+/// A command a provider's deploy runs, as shown by `--dry-run`.
+struct DeployStep {
+    description: &'static str,
+    program: &'static str,
+    args: Vec<String>,
+}
+
+impl DeployStep {
+    fn new(description: &'static str, program: &'static str, args: &[&str]) -> Self {
+        DeployStep {
+            description,
+            program,
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+
+    fn command(&self) -> Command {
+        let mut command = Command::new(self.program);
+        command.args(&self.args);
+        command
+    }
+
+    /// The command line with environment variable values masked.
+    fn display(&self, env: &EnvVars) -> String {
+        format!(
+            "{} {}",
+            self.program,
+            mask_secrets(&self.args.join(" "), env)
+        )
+    }
+}
+
+fn build_step() -> DeployStep {
+    DeployStep::new("Build facilitator", "cargo", &["build", "--release"])
+}
+
+/// `KEY=VALUE` pairs for provider flags such as `--env`.
+fn env_assignments(env: &EnvVars) -> impl Iterator<Item = String> + '_ {
+    env.iter().map(|(key, value)| format!("{}={}", key, value))
+}
+
+fn vercel_steps(env: &EnvVars) -> Vec<DeployStep> {
+    let mut release = DeployStep::new("Deploy facilitator", "vercel", &["--prod"]);
+    for assignment in env_assignments(env) {
+        release.args.push("--env".to_string());
+        release.args.push(assignment);
+    }
+
+    vec![
+        build_step(),
+        DeployStep::new("Check for vercel installation", "vercel", &["--version"]),
+        release,
+    ]
+}
+
+fn railway_steps(env: &EnvVars) -> Vec<DeployStep> {
+    let mut steps = vec![
+        build_step(),
+        DeployStep::new("Check for railway installation", "railway", &["--version"]),
+    ];
+
+    if !env.is_empty() {
+        let mut variables = DeployStep::new("Set environment variables", "railway", &["variables"]);
+        for assignment in env_assignments(env) {
+            variables.args.push("--set".to_string());
+            variables.args.push(assignment);
+        }
+        steps.push(variables);
+    }
+
+    steps.push(DeployStep::new(
+        "Deploy facilitator",
+        "railway",
+        &["up", "--detach"],
+    ));
+    steps
+}
+
+/// Reads a dotenv-style file: `KEY=VALUE` per line, with blank lines,
+/// `#` comments, an optional `export ` prefix, and optionally quoted values.
+pub fn load_env_file(path: &Path) -> Result<EnvVars> {
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read env file: {}", path.display()))?;
+    parse_env_file(&source, path)
+}
+
+/// Parses the contents of the env file at `path`, which errors name.
+fn parse_env_file(source: &str, path: &Path) -> Result<EnvVars> {
+    let mut vars = EnvVars::new();
+    for (index, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line
+            .split_once('=')
+            .with_context(|| format!("{}:{}: expected KEY=VALUE", path.display(), index + 1))?;
+
+        let key = key.trim();
+        let valid_key = key
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_key {
+            anyhow::bail!(
+                "{}:{}: invalid variable name '{}'",
+                path.display(),
+                index + 1,
+                key
+            );
+        }
+
+        let value = value.trim();
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|quote| {
+                value
+                    .strip_prefix(*quote)
+                    .and_then(|rest| rest.strip_suffix(*quote))
+            })
+            .unwrap_or(value);
+
+        vars.push((key.to_string(), value.to_string()));
+    }
+
+    Ok(vars)
+}
+
+/// Replaces every non-empty environment variable value in `text` with a mask.
+fn mask_secrets(text: &str, env: &EnvVars) -> String {
+    env.iter()
+        .filter(|(_, value)| !value.is_empty())
+        .fold(text.to_string(), |text, (_, value)| {
+            text.replace(value, MASK)
+        })
+}
+
+/// Deploys to `provider`. With `wait`, a Vercel deploy polls the deployment
+/// until it is ready or fails; Railway's deploy is always detached. Progress
+/// is printed as it goes; the outcome is returned for the caller to report.
+pub async fn deploy(
+    provider: &str,
+    dry_run: bool,
+    env: EnvVars,
+    build: BuildOptions,
+    wait: bool,
+) -> Result<DeployResult> {
+    match provider.to_lowercase().as_str() {
+        "vercel" | "vercel.app" if dry_run => {
+            print_dry_run("vercel", &vercel_steps(&env), &env, build)
+        }
+        "vercel" | "vercel.app" => deploy_to_vercel(&env, build, wait).await,
+        "railway" | "railway.app" if dry_run => {
+            print_dry_run("railway", &railway_steps(&env), &env, build)
+        }
+        "railway" | "railway.app" => deploy_to_railway(&env, build).await,
+        _ => {
+            ui::warn(&format!("Provider '{}' not yet supported", provider));
+            ui::info("Supported providers: vercel, railway");
+            Ok(DeployResult::new(provider, DeployStatus::Unsupported, &env))
+        }
+    }
+}
+
+fn print_dry_run(
+    provider: &str,
+    steps: &[DeployStep],
+    env: &EnvVars,
+    build: BuildOptions,
+) -> Result<DeployResult> {
+    let current_dir = std::env::current_dir()
+        .map(|dir| dir.display().to_string())
+        .unwrap_or_else(|_| ".".to_string());
+    // With --no-build the build step is replaced by the artifact check.
+    let steps = if build.skip { &steps[1..] } else { steps };
+
+    if ui::is_json() {
+        let steps: Vec<_> = steps
+            .iter()
+            .map(|step| {
+                serde_json::json!({
+                    "description": step.description,
+                    "command": step.display(env),
+                })
+            })
+            .collect();
+        ui::print_json(&serde_json::json!({
+            "provider": provider,
+            "dry_run": true,
+            "working_directory": current_dir,
+            "steps": steps,
+        }))?;
+        return Ok(DeployResult::new(provider, DeployStatus::DryRun, env));
+    }
 
     println!(
         "{}",
-        "  Step 2: Checking for vercel installation...".dimmed()
+        format!("  Working directory: {}", current_dir).dimmed()
     );
 
-    let check_result = Command::new("vercel").arg("--version").output();
+    for (index, step) in steps.iter().enumerate() {
+        println!(
+            "{}",
+            format!("  Step {}: {}", index + 1, step.description).dimmed()
+        );
+        println!("    $ {}", step.display(env));
+    }
+
+    println!();
+    println!("{}", "  Nothing was executed (--dry-run)".dimmed());
+    Ok(DeployResult::new(provider, DeployStatus::DryRun, env))
+}
+
+fn print_env_vars(env: &EnvVars) {
+    if env.is_empty() {
+        return;
+    }
+
+    ui::info(&format!(
+        "Passing {} environment variable(s): {}",
+        env.len(),
+        env_keys(env)
+    ));
+}
+
+fn env_keys(env: &EnvVars) -> String {
+    let keys: Vec<&str> = env.iter().map(|(key, _)| key.as_str()).collect();
+    keys.join(", ")
+}
+
+/// Where `cargo build --release` puts the current directory's package
+/// binary, honoring `CARGO_TARGET_DIR`.
+fn release_artifact() -> Result<PathBuf> {
+    let manifest = std::fs::read_to_string("Cargo.toml")
+        .context("--no-build needs a Cargo.toml in the current directory")?;
+    let manifest: toml::Value = toml::from_str(&manifest).context("Failed to parse Cargo.toml")?;
+    let name = manifest
+        .get("package")
+        .and_then(|package| package.get("name"))
+        .and_then(|name| name.as_str())
+        .context("Cargo.toml has no [package] name")?;
+
+    let target_dir = std::env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("target"));
+    Ok(target_dir
+        .join("release")
+        .join(format!("{}{}", name, std::env::consts::EXE_SUFFIX)))
+}
+
+/// Runs the `cargo build --release` step, streaming cargo's output as it
+/// goes. With `options.skip`, checks for the existing artifact instead.
+async fn build_release(step: &DeployStep, options: BuildOptions) -> Result<()> {
+    if options.skip {
+        ui::step(1, "Checking for release build (--no-build)");
+        let artifact = release_artifact()?;
+        if !artifact.is_file() {
+            anyhow::bail!(
+                "--no-build was given, but {} doesn't exist; build it with `cargo build --release` or drop --no-build",
+                artifact.display()
+            );
+        }
+        ui::success(&format!("Using {}", artifact.display()));
+        return Ok(());
+    }
+
+    ui::step(1, "Building facilitator");
+
+    // Cargo reports progress on stderr; keep stdout clean for JSON output.
+    let stdout = if ui::is_json() {
+        Stdio::null()
+    } else {
+        Stdio::inherit()
+    };
+    let mut command = step.command();
+    // Its own process group lets a timeout stop cargo together with the
+    // rustc and build-script processes it started.
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    let mut child = match tokio::process::Command::from(command)
+        .stdout(stdout)
+        .stderr(Stdio::inherit())
+        .kill_on_drop(true)
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) if is_not_found(&e) => {
+            return Err(X402Error::ToolNotFound(step.program.to_string()).into());
+        }
+        Err(e) => return Err(e).context("Failed to build project"),
+    };
+
+    let deadline = async {
+        match options.timeout {
+            Some(limit) => tokio::time::sleep(limit).await,
+            None => std::future::pending().await,
+        }
+    };
+    // Outside the terminal's process group, Ctrl-C has to be passed on.
+    let status = tokio::select! {
+        status = child.wait() => status.context("Failed to wait for the build")?,
+        _ = deadline => {
+            stop_build(&mut child).await;
+            anyhow::bail!(
+                "Build timed out after {}s and was stopped; raise --build-timeout, or build first and deploy with --no-build",
+                options.timeout.unwrap_or_default().as_secs()
+            );
+        }
+        _ = tokio::signal::ctrl_c() => {
+            stop_build(&mut child).await;
+            anyhow::bail!("Build interrupted");
+        }
+    };
 
-    match check_result {
+    if !status.success() {
+        anyhow::bail!("Build failed ({}); see cargo's output above", status);
+    }
+
+    ui::success("Build successful");
+    Ok(())
+}
+
+/// Kills the build's whole process group (just cargo where process groups
+/// don't exist) and reaps cargo.
+async fn stop_build(child: &mut tokio::process::Child) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        let _ = Command::new("kill")
+            .args(["-KILL", "--", &format!("-{}", pid)])
+            .status();
+    }
+    let _ = child.kill().await;
+}
+
+/// Runs a provider CLI's `--version` step to make sure it is installed.
+fn check_provider_cli(check: &DeployStep, label: &str) -> Result<()> {
+    ui::step(2, &format!("Checking for {} installation", check.program));
+
+    match check.command().output() {
         Ok(output) if output.status.success() => {
-            println!("{}", "  ✓ Vercel CLI installed".green().dimmed());
+            ui::success(&format!("{} CLI installed", label));
+            Ok(())
         }
-        _ => {
-            println!(
-                "{}",
-                "  ℹ Vercel CLI not found. Installing...".yellow().dimmed()
+        Ok(output) => {
+            let error = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!(
+                "{} {} failed ({}): {}",
+                check.program,
+                check.args.join(" "),
+                output.status,
+                error.trim()
             );
-            println!("{}", "  Run: npm install -g vercel".dimmed());
-            return Ok(());
         }
+        Err(e) if is_not_found(&e) => {
+            Err(X402Error::ToolNotFound(check.program.to_string()).into())
+        }
+        Err(e) => Err(e).with_context(|| format!("Failed to run {}", check.program)),
     }
+}
 
-    println!("{}", "  Step 3: Deploying facilitator...".dimmed());
+async fn deploy_to_vercel(env: &EnvVars, build: BuildOptions, wait: bool) -> Result<DeployResult> {
+    let steps = vercel_steps(env);
+    build_release(&steps[0], build).await?;
+    check_provider_cli(&steps[1], "Vercel")?;
 
-    let deploy_result = Command::new("vercel")
-        .args(["--prod"])
+    ui::step(3, "Deploying facilitator");
+    print_env_vars(env);
+
+    let deploy_result = steps[2]
+        .command()
         .output()
         .context("Failed to execute vercel deploy")?;
 
-    let _output = String::from_utf8_lossy(&deploy_result.stdout);
+    let mut result = DeployResult::new("vercel", DeployStatus::Initiated, env);
+    result.dashboard = Some(VERCEL_DASHBOARD_URL.to_string());
+    if !deploy_result.status.success() {
+        let error = mask_secrets(&String::from_utf8_lossy(&deploy_result.stderr), env);
+        ui::warn(&format!("Deployment may have failed: {}", error));
+        result.status = DeployStatus::MayHaveFailed;
+        result.error = Some(error.trim().to_string());
+        return Ok(result);
+    }
 
-    if deploy_result.status.success() {
-        println!("{}", "  ✓ Deployment initiated".green().dimmed());
-        println!(
-            "{}",
-            "  Check Vercel dashboard for deployment status".dimmed()
-        );
-        println!("{}", "  Typically: https://vercel.com/dashboard".dimmed());
-    } else {
-        let error = String::from_utf8_lossy(&deploy_result.stderr);
-        println!(
-            "{}",
-            format!("  ⚠ Deployment may have failed: {}", error)
-                .yellow()
-                .dimmed()
-        );
+    ui::success("Deployment initiated");
+
+    // `vercel` prints the new deployment's URL on stdout; its hostname
+    // identifies the deployment to the API.
+    let output = String::from_utf8_lossy(&deploy_result.stdout);
+    let deployment = extract_urls(&output)
+        .into_iter()
+        .find(|url| url.ends_with(".vercel.app"));
+    let token = std::env::var(VERCEL_TOKEN_VAR)
+        .ok()
+        .filter(|token| !token.is_empty());
+
+    let outcome = match (&deployment, &token) {
+        (Some(deployment), Some(token)) if wait => {
+            let host = deployment.trim_start_matches("https://");
+            Some(wait_for_vercel_deployment(host, token).await)
+        }
+        (None, _) if wait => {
+            ui::info("The vercel output had no deployment URL to follow; check the dashboard");
+            None
+        }
+        (Some(_), None) if wait => {
+            ui::info(&format!(
+                "Set {} to wait for the deployment to finish",
+                VERCEL_TOKEN_VAR
+            ));
+            None
+        }
+        _ => None,
+    };
+
+    result.url = deployment;
+    match outcome {
+        Some(Ok(url)) => {
+            result.status = DeployStatus::Ready;
+            result.url = Some(url);
+        }
+        Some(Err(e)) => {
+            result.status = match e.downcast_ref::<X402Error>() {
+                Some(X402Error::DeploymentFailed(reason)) => {
+                    result.error = Some(reason.clone());
+                    DeployStatus::Failed
+                }
+                _ => {
+                    result.error = Some(format!("{:#}", e));
+                    DeployStatus::Unknown
+                }
+            };
+        }
+        None => {}
+    }
+    Ok(result)
+}
+
+/// Polls the Vercel API, backing off from `DEPLOY_POLL_INITIAL_SECS` to
+/// `DEPLOY_POLL_MAX_SECS`, until the deployment is `READY` (returning its
+/// production URL) or `ERROR`/`CANCELED` (returning the build error).
+async fn wait_for_vercel_deployment(deployment: &str, token: &str) -> Result<String> {
+    ui::step(4, "Waiting for the deployment to finish");
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let mut url = format!("{}/v13/deployments/{}", VERCEL_API_URL, deployment);
+    if let Some(team) = vercel_team_id() {
+        url = format!("{}?teamId={}", url, team);
     }
 
-    println!();
-    println!("{}", "Deployment Summary".cyan().bold());
-    println!(
-        "{}",
-        "  Follow the Vercel CLI prompts to complete deployment".dimmed()
-    );
-    println!(
-        "{}",
-        "  Your facilitator will be deployed with a public URL".dimmed()
+    let start = Instant::now();
+    let deadline = start + Duration::from_secs(DEPLOY_WAIT_TIMEOUT_SECS);
+    let mut delay = Duration::from_secs(DEPLOY_POLL_INITIAL_SECS);
+    let mut last_state = String::new();
+
+    loop {
+        match client.get(&url).bearer_auth(token).send().await {
+            Ok(response) if response.status().is_success() => {
+                let body: serde_json::Value = response
+                    .json()
+                    .await
+                    .context("Failed to parse the Vercel deployment status")?;
+                let state = body["readyState"]
+                    .as_str()
+                    .or_else(|| body["status"].as_str())
+                    .unwrap_or("UNKNOWN")
+                    .to_string();
+
+                if state != last_state {
+                    ui::info(&format!(
+                        "Deployment {} ({}s)",
+                        state,
+                        start.elapsed().as_secs()
+                    ));
+                    last_state = state;
+                }
+
+                match last_state.as_str() {
+                    "READY" => {
+                        let host = body["alias"][0]
+                            .as_str()
+                            .or_else(|| body["url"].as_str())
+                            .unwrap_or(deployment);
+                        ui::success("Deployment ready");
+                        return Ok(format!("https://{}", host));
+                    }
+                    "ERROR" | "CANCELED" => {
+                        let reason = body["errorMessage"]
+                            .as_str()
+                            .map(str::to_string)
+                            .unwrap_or_else(|| format!("deployment is {}", last_state));
+                        return Err(X402Error::DeploymentFailed(reason).into());
+                    }
+                    _ => {}
+                }
+            }
+            Ok(response) => {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                let message = serde_json::from_str::<serde_json::Value>(&body)
+                    .ok()
+                    .and_then(|body| body["error"]["message"].as_str().map(str::to_string))
+                    .unwrap_or(body);
+                anyhow::bail!(
+                    "Vercel API returned {} for deployment {}: {}",
+                    status,
+                    deployment,
+                    message.trim()
+                );
+            }
+            // A dropped poll is retried like a pending deployment.
+            Err(e) => ui::warn(&format!("Failed to check deployment status: {}", e)),
+        }
+
+        if Instant::now() + delay > deadline {
+            anyhow::bail!(
+                "Deployment {} still {} after {} minutes; check the Vercel dashboard",
+                deployment,
+                if last_state.is_empty() {
+                    "unknown"
+                } else {
+                    &last_state
+                },
+                DEPLOY_WAIT_TIMEOUT_SECS / 60
+            );
+        }
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(Duration::from_secs(DEPLOY_POLL_MAX_SECS));
+    }
+}
+
+/// The Vercel team owning the project, from `VERCEL_ORG_ID` or the
+/// `.vercel/project.json` written by `vercel link`. Personal accounts
+/// (`orgId`s without the `team_` prefix) need no team ID.
+fn vercel_team_id() -> Option<String> {
+    let org = std::env::var("VERCEL_ORG_ID").ok().or_else(|| {
+        let project = std::fs::read_to_string(".vercel/project.json").ok()?;
+        let project: serde_json::Value = serde_json::from_str(&project).ok()?;
+        project["orgId"].as_str().map(str::to_string)
+    })?;
+    org.starts_with("team_").then_some(org)
+}
+
+async fn deploy_to_railway(env: &EnvVars, build: BuildOptions) -> Result<DeployResult> {
+    let steps = railway_steps(env);
+    build_release(&steps[0], build).await?;
+    check_provider_cli(&steps[1], "Railway")?;
+
+    if steps.len() > 3 {
+        ui::step(3, "Setting environment variables");
+        print_env_vars(env);
+
+        let variables_result = steps[2]
+            .command()
+            .output()
+            .context("Failed to execute railway variables")?;
+
+        if !variables_result.status.success() {
+            let error = String::from_utf8_lossy(&variables_result.stderr);
+            anyhow::bail!(
+                "railway variables failed ({}): {}",
+                variables_result.status,
+                mask_secrets(error.trim(), env)
+            );
+        }
+
+        ui::success("Environment variables set");
+    }
+
+    ui::step(steps.len(), "Deploying facilitator");
+
+    let deploy_result = steps[steps.len() - 1]
+        .command()
+        .output()
+        .context("Failed to execute railway up")?;
+
+    let output = mask_secrets(
+        &format!(
+            "{}\n{}",
+            String::from_utf8_lossy(&deploy_result.stdout),
+            String::from_utf8_lossy(&deploy_result.stderr)
+        ),
+        env,
     );
 
-    Ok(())
+    let mut result = DeployResult::new("railway", DeployStatus::Initiated, env);
+    if !deploy_result.status.success() {
+        ui::warn(&format!("Deployment may have failed: {}", output.trim()));
+        ui::info("Run `railway link` first if this directory isn't linked to a project");
+        result.status = DeployStatus::MayHaveFailed;
+        result.error = Some(output.trim().to_string());
+        return Ok(result);
+    }
+
+    ui::success("Deployment initiated");
+
+    let urls = extract_urls(&output);
+    result.url = urls
+        .iter()
+        .find(|url| url.contains(".up.railway.app"))
+        .cloned();
+    result.build_logs = urls
+        .iter()
+        .find(|url| url.contains("railway.com") || url.contains("railway.app/project"))
+        .cloned();
+
+    if result.url.is_none() {
+        ui::info("Run `railway domain` to get (or generate) the service's public URL");
+    }
+
+    Ok(result)
+}
+
+/// Pulls every `http(s)://` URL out of CLI output.
+fn extract_urls(output: &str) -> Vec<String> {
+    output
+        .split_whitespace()
+        .filter(|word| word.starts_with("https://") || word.starts_with("http://"))
+        .map(|url| {
+            url.trim_end_matches(|c: char| !(c.is_alphanumeric() || c == '/'))
+                .to_string()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Result<EnvVars> {
+        parse_env_file(source, Path::new(".env"))
+    }
+
+    #[test]
+    fn parses_dotenv_lines() {
+        let vars = parse(
+            r#"
+# comment
+PLAIN=value
+export EXPORTED=1
+DOUBLE="quoted value"
+SINGLE='single'
+  SPACED = padded  
+URL=https://example.com/?a=b
+EMPTY=
+"#,
+        )
+        .unwrap();
+
+        let vars: Vec<(&str, &str)> = vars
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        assert_eq!(
+            vars,
+            vec![
+                ("PLAIN", "value"),
+                ("EXPORTED", "1"),
+                ("DOUBLE", "quoted value"),
+                ("SINGLE", "single"),
+                ("SPACED", "padded"),
+                ("URL", "https://example.com/?a=b"),
+                ("EMPTY", ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn mismatched_quotes_are_kept() {
+        let vars = parse("VALUE=\"open'\n").unwrap();
+        assert_eq!(vars[0].1, "\"open'");
+    }
+
+    #[test]
+    fn reports_the_line_of_a_missing_equals_sign() {
+        let err = parse("A=1\n\nNOT_AN_ASSIGNMENT\n").unwrap_err();
+        assert_eq!(err.to_string(), ".env:3: expected KEY=VALUE");
+    }
+
+    #[test]
+    fn rejects_invalid_variable_names() {
+        for key in ["1ABC", "MY-VAR", ""] {
+            let err = parse(&format!("{}=x", key)).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!(".env:1: invalid variable name '{}'", key)
+            );
+        }
+    }
+
+    #[test]
+    fn masks_secret_values() {
+        let env = vec![
+            ("TOKEN".to_string(), "s3cret".to_string()),
+            ("EMPTY".to_string(), String::new()),
+        ];
+        assert_eq!(
+            mask_secrets("token=s3cret", &env),
+            format!("token={}", MASK)
+        );
+    }
 }
@@ -0,0 +1,203 @@
+use anyhow::Result;
+use colored::Colorize;
+use serde::Serialize;
+use std::fs;
+use std::process::Command;
+use std::time::Duration;
+
+use crate::x402::error::X402Error;
+use crate::x402::ui;
+
+/// Tools the CLI shells out to for `init` and `deploy`.
+const REQUIRED_TOOLS: &[&str] = &["node", "npm", "git", "cargo"];
+/// Provider CLIs, only needed for `deploy`.
+const PROVIDER_TOOLS: &[&str] = &["vercel", "railway"];
+
+const NETWORK_TIMEOUT_SECS: u64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Serialize)]
+struct Check {
+    name: String,
+    status: Status,
+    detail: String,
+}
+
+impl Check {
+    fn print(&self) {
+        let mark = match self.status {
+            Status::Pass => "✓".green(),
+            Status::Warn => "⚠".yellow(),
+            Status::Fail => "✗".red(),
+        };
+        println!("  {} {} {}", mark, self.name, self.detail.dimmed());
+    }
+}
+
+/// Checks tools, the `~/.x402` directory, and network access, printing a
+/// report. Fails if any required check fails; missing provider CLIs only
+/// warn.
+pub async fn run() -> Result<()> {
+    ui::header("Checking environment...");
+
+    let mut checks = Vec::new();
+
+    print_section("Tools");
+    for tool in REQUIRED_TOOLS {
+        checks.push(check_tool(tool, Status::Fail));
+    }
+    for tool in PROVIDER_TOOLS {
+        checks.push(check_tool(tool, Status::Warn));
+    }
+    print_from(&checks, 0);
+
+    let printed = checks.len();
+    print_section("Files");
+    checks.push(check_x402_dir());
+    print_from(&checks, printed);
+
+    let printed = checks.len();
+    print_section("Network");
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(NETWORK_TIMEOUT_SECS))
+        .build()?;
+    if let Some(fullnode) = crate::x402::config::fullnode_url("aptos:testnet") {
+        checks.push(check_url(&client, "Fullnode", &fullnode).await);
+    }
+    if let Some(faucet) = crate::x402::chains::registry().faucet_url("aptos:testnet") {
+        checks.push(check_url(&client, "Testnet faucet", faucet).await);
+    }
+    print_from(&checks, printed);
+
+    let failures = checks.iter().filter(|c| c.status == Status::Fail).count();
+    let warnings = checks.iter().filter(|c| c.status == Status::Warn).count();
+
+    if ui::is_json() {
+        ui::print_json(&serde_json::json!({
+            "ready": failures == 0,
+            "failures": failures,
+            "warnings": warnings,
+            "checks": checks,
+        }))?;
+    }
+
+    ui::blank_line();
+    if failures > 0 {
+        anyhow::bail!("{} check(s) failed, {} warning(s)", failures, warnings);
+    }
+
+    if ui::is_json() {
+        return Ok(());
+    }
+
+    if warnings > 0 {
+        println!(
+            "{}",
+            format!("✓ Environment ready ({} warning(s))", warnings)
+                .green()
+                .bold()
+        );
+    } else {
+        println!("{}", "✓ Environment ready".green().bold());
+    }
+
+    Ok(())
+}
+
+fn print_section(title: &str) {
+    if !ui::is_json() {
+        println!();
+        println!("{}", title.bold());
+    }
+}
+
+fn print_from(checks: &[Check], start: usize) {
+    if ui::is_json() {
+        return;
+    }
+
+    for check in &checks[start..] {
+        check.print();
+    }
+}
+
+/// Runs `<tool> --version`. `missing` is the status reported when the tool
+/// isn't installed.
+fn check_tool(tool: &str, missing: Status) -> Check {
+    match Command::new(tool).arg("--version").output() {
+        Ok(output) if output.status.success() => {
+            let version = String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+            Check {
+                name: tool.to_string(),
+                status: Status::Pass,
+                detail: version,
+            }
+        }
+        Ok(output) => Check {
+            name: tool.to_string(),
+            status: missing,
+            detail: format!("`{} --version` failed ({})", tool, output.status),
+        },
+        Err(_) => Check {
+            name: tool.to_string(),
+            status: missing,
+            detail: format!(
+                "not found. {}",
+                X402Error::ToolNotFound(tool.to_string()).hint()
+            ),
+        },
+    }
+}
+
+fn check_x402_dir() -> Check {
+    let name = "x402 directory writable".to_string();
+
+    let result = crate::x402::config::x402_dir().and_then(|dir| {
+        fs::create_dir_all(&dir)?;
+        let probe = dir.join(".doctor-probe");
+        fs::write(&probe, b"ok")?;
+        fs::remove_file(&probe)?;
+        Ok(dir)
+    });
+
+    match result {
+        Ok(dir) => Check {
+            name,
+            status: Status::Pass,
+            detail: dir.display().to_string(),
+        },
+        Err(e) => Check {
+            name,
+            status: Status::Fail,
+            detail: e.to_string(),
+        },
+    }
+}
+
+/// Any HTTP response counts as reachable; only connection failures fail.
+async fn check_url(client: &reqwest::Client, name: &str, url: &str) -> Check {
+    match client.get(url).send().await {
+        Ok(response) => Check {
+            name: name.to_string(),
+            status: Status::Pass,
+            detail: format!("{} ({})", url, response.status()),
+        },
+        Err(e) => Check {
+            name: name.to_string(),
+            status: Status::Fail,
+            detail: format!("{} unreachable: {}", url, e),
+        },
+    }
+}
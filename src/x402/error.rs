@@ -0,0 +1,119 @@
+use std::fmt;
+
+/// Failures callers may want to match on, e.g. to print tailored guidance.
+/// They travel inside `anyhow::Error`; use `downcast_ref::<X402Error>()` to
+/// recover them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum X402Error {
+    /// An external program (npm, vercel, cargo, ...) is not on `PATH`.
+    ToolNotFound(String),
+    /// A server could not bind because the port is taken.
+    PortInUse(u16),
+    /// No saved wallet matches the given address or alias.
+    WalletNotFound(String),
+    /// A saved wallet file isn't valid wallet JSON (e.g. truncated or
+    /// hand-edited).
+    CorruptWalletFile { path: String, reason: String },
+    /// An address argument is not a well-formed Aptos address.
+    InvalidAddress(String),
+    /// The chain is not one this CLI supports.
+    InvalidChain(String),
+    /// The network is not in the chain registry.
+    UnknownNetwork(String),
+    /// The directory has no `config/x402.toml`.
+    NotAProject(String),
+    /// `test payment` completed but the payment flow did not succeed.
+    PaymentTestFailed(String),
+    /// The testnet faucet refused or failed the funding request, after any
+    /// retries.
+    FaucetUnavailable(String),
+    /// The provider reported that the deployment's build failed or was
+    /// canceled.
+    DeploymentFailed(String),
+}
+
+impl X402Error {
+    /// A suggestion for how the user can fix the problem.
+    pub fn hint(&self) -> String {
+        match self {
+            X402Error::ToolNotFound(tool) => match tool.as_str() {
+                "npm" | "node" => "Install Node.js (which includes npm) from https://nodejs.org".to_string(),
+                "pnpm" | "yarn" | "vercel" => format!("Install it with `npm install -g {}`", tool),
+                "bun" => "Install Bun from https://bun.sh".to_string(),
+                "railway" => "Install it with `npm install -g @railway/cli`".to_string(),
+                "cargo" => "Install the Rust toolchain from https://rustup.rs".to_string(),
+                _ => format!("Install `{}` and make sure it is on your PATH", tool),
+            },
+            X402Error::PortInUse(_) => {
+                "Pick another port with --port, or stop the existing process (e.g. `x402 facilitator stop`)"
+                    .to_string()
+            }
+            X402Error::WalletNotFound(_) => {
+                "Create one with `x402 wallet create`, or import one with `x402 wallet import`"
+                    .to_string()
+            }
+            X402Error::CorruptWalletFile { path, .. } => format!(
+                "Restore {} from a backup (or the `.json.old` copy `wallet rotate` leaves), or move it out of the wallets directory",
+                path
+            ),
+            X402Error::InvalidAddress(_) => {
+                "Aptos account addresses are 0x followed by 64 hex characters".to_string()
+            }
+            X402Error::InvalidChain(_) => format!(
+                "Supported chains: {}",
+                crate::x402::chains::registry().chain_names().join(", ")
+            ),
+            X402Error::UnknownNetwork(_) => format!(
+                "Known networks: {}; add more in {}",
+                crate::x402::chains::registry().network_ids().join(", "),
+                crate::x402::chains::chains_file()
+                    .map(|path| path.display().to_string())
+                    .unwrap_or_else(|_| crate::x402::chains::CHAINS_FILE_NAME.to_string())
+            ),
+            X402Error::NotAProject(_) => {
+                "Pass the directory created by `x402 init`, or create a project with `x402 init`"
+                    .to_string()
+            }
+            X402Error::PaymentTestFailed(_) => {
+                "Check the facilitator logs, or rerun with --verbose or --json for details"
+                    .to_string()
+            }
+            X402Error::FaucetUnavailable(_) => {
+                "Created wallets are saved even when funding fails; retry later with more --fund-retries, or fund them at https://aptos.dev/network/faucet"
+                    .to_string()
+            }
+            X402Error::DeploymentFailed(_) => {
+                "Check the build logs on the provider's dashboard, fix the error, and deploy again"
+                    .to_string()
+            }
+        }
+    }
+}
+
+impl fmt::Display for X402Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            X402Error::ToolNotFound(tool) => write!(f, "{} not found", tool),
+            X402Error::PortInUse(port) => write!(f, "Port {} is already in use", port),
+            X402Error::WalletNotFound(handle) => {
+                write!(f, "No wallet found with address or alias '{}'", handle)
+            }
+            X402Error::CorruptWalletFile { path, reason } => {
+                write!(f, "Wallet file {} is corrupt: {}", path, reason)
+            }
+            X402Error::InvalidAddress(address) => {
+                write!(f, "Invalid address format: '{}'", address)
+            }
+            X402Error::InvalidChain(chain) => write!(f, "Unsupported chain '{}'", chain),
+            X402Error::UnknownNetwork(network) => write!(f, "Unknown network '{}'", network),
+            X402Error::NotAProject(dir) => {
+                write!(f, "{} is not an x402 project (no config/x402.toml)", dir)
+            }
+            X402Error::PaymentTestFailed(reason) => write!(f, "Payment test failed: {}", reason),
+            X402Error::FaucetUnavailable(reason) => write!(f, "Faucet funding failed: {}", reason),
+            X402Error::DeploymentFailed(reason) => write!(f, "Deployment failed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for X402Error {}
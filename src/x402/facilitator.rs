@@ -1,163 +1,1943 @@
 use anyhow::{Context, Result};
+use base64::Engine;
 use colored::Colorize;
-use std::io::{BufRead, BufReader, Write};
-use std::net::{TcpListener, TcpStream};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::thread;
-use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::{Notify, Semaphore};
+
+use crate::x402::error::X402Error;
+use crate::x402::test::REQUEST_ID_HEADER;
+use crate::x402::tx;
+use crate::x402::ui;
+
+pub const DEFAULT_PORT: u16 = 3001;
+/// Address the facilitator binds unless configured otherwise.
+pub const DEFAULT_HOST: &str = "127.0.0.1";
+const READY_TIMEOUT_SECS: u64 = 5;
+/// How long `start --wait` polls `/health` by default.
+pub const DEFAULT_HEALTH_WAIT_SECS: u64 = 10;
+/// Longest pause between `/health` polls.
+const HEALTH_MAX_BACKOFF_MS: u64 = 1000;
+pub(crate) const KEEP_ALIVE_TIMEOUT_SECS: u64 = 5;
+const MAX_REQUESTS_PER_CONNECTION: usize = 100;
+const WRITE_CHUNK_SIZE: usize = 16 * 1024;
+pub(crate) const JSON_CONTENT_TYPE: &str = "application/json";
+const HTML_CONTENT_TYPE: &str = "text/html; charset=utf-8";
+/// Largest request body accepted by default; larger ones get a 413.
+pub const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+/// Largest request line plus headers, in bytes; larger ones get a 431.
+const MAX_HEADER_BYTES: usize = 16 * 1024;
+/// Most headers one request may carry; more get a 431.
+const MAX_HEADERS: usize = 100;
+
+/// Environment variable carrying the facilitator's private key to a
+/// launched facilitator process; `facilitator start` also reads it in place
+/// of `--private-key`.
+pub const PRIVATE_KEY_ENV: &str = "X402_FACILITATOR_PRIVATE_KEY";
+
+const PID_FILE_NAME: &str = "facilitator.pid";
+const LOG_FILE_NAME: &str = "facilitator.log";
+/// Request field a rejection names when the transaction itself is at fault.
+const TRANSACTION_FIELD: &str = "paymentPayload.payload.transaction";
+const SENDER_AUTHENTICATOR_FIELD: &str = "paymentPayload.payload.senderAuthenticator";
+const FEE_PAYER_FIELD: &str = "paymentPayload.payload.feePayerAddress";
+
+/// Recorded by `start` so `stop` and `restart` can find the running instance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PidFile {
+    pub pid: u32,
+    pub port: u16,
+    /// Bound address, kept so `restart` and `metrics` reach the same socket.
+    #[serde(default = "default_host")]
+    pub host: String,
+    pub wallet: String,
+    /// Kept so `restart` comes back with the same limit.
+    #[serde(default = "default_max_body_size")]
+    pub max_body_size: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_connections: Option<usize>,
+    /// Executable name of the facilitator process. `stop` only signals `pid`
+    /// while it still runs this program, so a reused PID is left alone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub program: Option<String>,
+    /// The `--facilitator-bin` it runs, so `restart` launches that again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub facilitator_bin: Option<PathBuf>,
+}
+
+impl PidFile {
+    /// Base URL the recorded facilitator serves on.
+    pub fn url(&self) -> String {
+        base_url(&self.host, self.port)
+    }
+}
+
+fn default_max_body_size() -> usize {
+    DEFAULT_MAX_BODY_SIZE
+}
+
+fn default_host() -> String {
+    DEFAULT_HOST.to_string()
+}
+
+/// File name of `path`, as recorded in [`PidFile::program`].
+fn program_name(path: &std::path::Path) -> Option<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+}
+
+/// Address to connect to for a facilitator bound to `host`: wildcard binds
+/// are reached over loopback.
+fn connect_host(host: &str) -> &str {
+    match host {
+        "0.0.0.0" => "127.0.0.1",
+        "::" => "::1",
+        host => host,
+    }
+}
+
+/// Base URL of a facilitator bound to `host`.
+fn base_url(host: &str, port: u16) -> String {
+    match connect_host(host) {
+        "127.0.0.1" | "localhost" => format!("http://localhost:{}", port),
+        host if host.contains(':') => format!("http://[{}]:{}", host, port),
+        host => format!("http://{}:{}", host, port),
+    }
+}
+
+/// A request declared a `Content-Length` over the facilitator's limit. The
+/// body is left unread, so the connection is answered with a 413 and closed.
+#[derive(Debug)]
+struct BodyTooLarge {
+    declared: usize,
+    limit: usize,
+}
+
+impl std::fmt::Display for BodyTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Request body of {} bytes exceeds the {} byte limit",
+            self.declared, self.limit
+        )
+    }
+}
+
+impl std::error::Error for BodyTooLarge {}
+
+/// A request's line and headers ran past [`MAX_HEADER_BYTES`] or
+/// [`MAX_HEADERS`]. The rest is left unread, so the connection is answered
+/// with a 431 and closed.
+#[derive(Debug)]
+struct HeadersTooLarge;
+
+impl std::fmt::Display for HeadersTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Request headers exceed {} bytes or {} fields",
+            MAX_HEADER_BYTES, MAX_HEADERS
+        )
+    }
+}
+
+impl std::error::Error for HeadersTooLarge {}
+
+/// Why `/verify` or `/settle` turned a payment down, and the request field
+/// at fault when there is one (`paymentRequirements.payTo`).
+struct Rejection {
+    reason: String,
+    field: Option<String>,
+}
+
+/// A payment that passed [`Facilitator::check_payment`].
+struct Payment {
+    raw_txn: tx::RawTransaction,
+    fee_payer: Option<tx::AccountAddress>,
+    sender: tx::AccountAuthenticator,
+}
+
+impl Payment {
+    /// The chain runs one transaction per sender and sequence number, so
+    /// that pair identifies the payment however its bytes or signature are
+    /// varied.
+    fn replay_key(&self) -> (tx::AccountAddress, u64) {
+        (self.raw_txn.sender, self.raw_txn.sequence_number)
+    }
+}
+
+/// Payments already settled, by sender and sequence number, each kept until
+/// its transaction's expiration. An expired transaction can't be replayed
+/// because the facilitator refuses it outright, so entries are dropped then
+/// and the set stays as small as the payments still in flight.
+#[derive(Debug, Default)]
+pub struct SettledPayments {
+    expirations: HashMap<(tx::AccountAddress, u64), u64>,
+}
+
+impl SettledPayments {
+    /// Whether `key` has been settled, as of `now` (Unix seconds).
+    fn contains(&mut self, key: &(tx::AccountAddress, u64), now: u64) -> bool {
+        self.evict_expired(now);
+        self.expirations.contains_key(key)
+    }
+
+    /// Records `key` as settled until `expiration`, returning `false` if it
+    /// already was.
+    fn insert(&mut self, key: (tx::AccountAddress, u64), expiration: u64, now: u64) -> bool {
+        self.evict_expired(now);
+        match self.expirations.entry(key) {
+            std::collections::hash_map::Entry::Occupied(_) => false,
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                entry.insert(expiration);
+                true
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &(tx::AccountAddress, u64)) {
+        self.expirations.remove(key);
+    }
+
+    fn evict_expired(&mut self, now: u64) {
+        self.expirations.retain(|_, expiration| *expiration > now);
+    }
+}
+
+/// Current Unix time in seconds, which transaction expirations are
+/// compared against.
+fn unix_now() -> u64 {
+    chrono::Utc::now().timestamp().max(0) as u64
+}
 
 pub struct Facilitator {
     pub port: u16,
+    pub host: String,
     pub wallet: crate::x402::wallet::Wallet,
     pub url: String,
     pub running: Arc<AtomicBool>,
+    /// Wakes the accept loop so it stops taking new connections.
+    pub shutdown_signal: Arc<Notify>,
+    /// Connections currently being served, so shutdown can drain them.
+    pub active_connections: Arc<AtomicUsize>,
+    pub metrics: Arc<FacilitatorMetrics>,
+    /// Payments already settled, shared by every connection
+    /// task so a payment cannot be replayed.
+    pub settled: Arc<Mutex<SettledPayments>>,
+    /// Largest request body, in bytes, the facilitator will read.
+    pub max_body_size: usize,
+    /// Most connections served at once; more are answered 503 and closed.
+    /// `None` is unlimited.
+    pub max_connections: Option<usize>,
+    /// Don't log requests, e.g. when embedded in another command's output.
+    pub quiet: bool,
+}
+
+/// Request counters shared with the connection tasks and served at
+/// `GET /metrics`.
+pub struct FacilitatorMetrics {
+    started_at: Instant,
+    /// The facilitator's open connection count, shared with its accept loop.
+    active_connections: Arc<AtomicUsize>,
+    /// Connections turned away with a 503 by `--max-connections`.
+    rejected_connections: AtomicU64,
+    requests: AtomicU64,
+    verify_calls: AtomicU64,
+    settle_calls: AtomicU64,
+    successes: AtomicU64,
+    failures: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    pub uptime_secs: u64,
+    pub requests: u64,
+    pub verify_calls: u64,
+    pub settle_calls: u64,
+    pub successes: u64,
+    pub failures: u64,
+    /// Connections open right now.
+    #[serde(default)]
+    pub active_connections: usize,
+    #[serde(default)]
+    pub rejected_connections: u64,
+}
+
+impl FacilitatorMetrics {
+    fn new(active_connections: Arc<AtomicUsize>) -> Self {
+        FacilitatorMetrics {
+            started_at: Instant::now(),
+            active_connections,
+            rejected_connections: AtomicU64::new(0),
+            requests: AtomicU64::new(0),
+            verify_calls: AtomicU64::new(0),
+            settle_calls: AtomicU64::new(0),
+            successes: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+        }
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            uptime_secs: self.started_at.elapsed().as_secs(),
+            requests: self.requests.load(Ordering::Relaxed),
+            verify_calls: self.verify_calls.load(Ordering::Relaxed),
+            settle_calls: self.settle_calls.load(Ordering::Relaxed),
+            successes: self.successes.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
+            active_connections: self.active_connections.load(Ordering::SeqCst),
+            rejected_connections: self.rejected_connections.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// What each connection task needs from the facilitator.
+struct ConnectionState {
+    url: String,
+    wallet: crate::x402::wallet::Wallet,
+    running: Arc<AtomicBool>,
+    metrics: Arc<FacilitatorMetrics>,
+    settled: Arc<Mutex<SettledPayments>>,
+    max_body_size: usize,
+    quiet: bool,
+}
+
+/// Decrements the active connection count when a connection task ends.
+struct ConnectionGuard(Arc<AtomicUsize>);
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
 }
 
 impl Facilitator {
-    pub fn start(port: u16, wallet: crate::x402::wallet::Wallet) -> Result<Self> {
-        println!("{}", "Starting facilitator...".cyan());
+    pub async fn start(
+        port: u16,
+        host: &str,
+        wallet: crate::x402::wallet::Wallet,
+        max_body_size: usize,
+        max_connections: Option<usize>,
+    ) -> Result<Self> {
+        ui::header("Starting facilitator...");
+
+        let facilitator =
+            Self::serve(port, host, wallet, max_body_size, max_connections, false).await?;
+        ui::success(&format!("Listening on {}", facilitator.url));
 
-        let url = format!("http://localhost:{}", port);
-        let running = Arc::new(AtomicBool::new(true));
+        Self::write_pid_file(&PidFile {
+            pid: std::process::id(),
+            port,
+            host: host.to_string(),
+            wallet: facilitator.wallet.address.clone(),
+            max_body_size,
+            max_connections,
+            program: std::env::current_exe()
+                .ok()
+                .as_deref()
+                .and_then(program_name),
+            facilitator_bin: None,
+        })?;
+
+        if let Some(limit) = max_connections {
+            ui::info(&format!("Serving at most {} connection(s) at once", limit));
+        }
+
+        Ok(facilitator)
+    }
 
-        let (sender, receiver) = std::sync::mpsc::channel::<bool>();
-        let sender_clone = sender.clone();
+    /// Binds `host:port` and serves connections in the background. Port 0
+    /// picks a free port; `port` and `url` report the one bound.
+    async fn serve(
+        port: u16,
+        host: &str,
+        wallet: crate::x402::wallet::Wallet,
+        max_body_size: usize,
+        max_connections: Option<usize>,
+        quiet: bool,
+    ) -> Result<Self> {
+        if wallet.address == crate::x402::wallet::Wallet::default().address {
+            anyhow::bail!(
+                "Refusing to start facilitator with the placeholder all-zeros wallet. Pass --wallet <address> or --private-key"
+            );
+        }
 
-        let port_clone = port;
-        let running_clone = running.clone();
-        let url_clone = url.clone();
+        let listener = match tokio::net::TcpListener::bind((host, port)).await {
+            Ok(listener) => listener,
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
+                return Err(X402Error::PortInUse(port).into());
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to bind to {}:{}", host, port));
+            }
+        };
+        let port = listener.local_addr()?.port();
+        let active_connections = Arc::new(AtomicUsize::new(0));
 
         let facilitator = Facilitator {
             port,
+            host: host.to_string(),
             wallet,
-            url: url.clone(),
-            running,
+            url: base_url(host, port),
+            running: Arc::new(AtomicBool::new(true)),
+            shutdown_signal: Arc::new(Notify::new()),
+            active_connections: active_connections.clone(),
+            metrics: Arc::new(FacilitatorMetrics::new(active_connections)),
+            settled: Arc::new(Mutex::new(SettledPayments::default())),
+            max_body_size,
+            max_connections,
+            quiet,
         };
 
-        thread::spawn(move || {
-            let listener = match TcpListener::bind(format!("127.0.0.1:{}", port_clone)) {
-                Ok(l) => {
-                    sender_clone.send(true).ok();
-                    l
-                }
-                Err(e) => {
-                    let _ = sender_clone.send(false);
-                    eprintln!(
-                        "{}",
-                        format!("✗ Failed to bind to port {}: {}", port_clone, e)
-                            .red()
-                            .bold()
-                    );
-                    if e.raw_os_error() == Some(61) {
-                        eprintln!(
-                            "{}",
-                            "  Error 61: Port already in use. Try a different port or stop the existing facilitator."
-                                .yellow()
-                                .dimmed()
-                        );
-                    }
-                    return;
-                }
-            };
-
-            println!("{}", "  Facilitator ready to receive requests".dimmed());
+        tokio::spawn(Self::accept_loop(
+            listener,
+            facilitator.connection_state(),
+            facilitator.shutdown_signal.clone(),
+            facilitator.active_connections.clone(),
+            max_connections.map(|limit| Arc::new(Semaphore::new(limit))),
+        ));
 
-            for stream in listener.incoming() {
-                if !running_clone.load(Ordering::Relaxed) {
-                    break;
-                }
+        Ok(facilitator)
+    }
 
-                match stream {
-                    Ok(stream) => {
-                        if let Err(e) = Self::handle_connection(stream, &url_clone) {
-                            eprintln!("Error handling connection: {}", e);
-                        }
-                    }
+    /// Accepts connections until [`shutdown`](Self::shutdown) signals, serving
+    /// each one on its own task so idle keep-alive connections cost no thread.
+    /// With a `limit`, each connection holds one of its permits; when none is
+    /// left, the connection is answered 503 and closed.
+    async fn accept_loop(
+        listener: tokio::net::TcpListener,
+        state: Arc<ConnectionState>,
+        shutdown_signal: Arc<Notify>,
+        active_connections: Arc<AtomicUsize>,
+        limit: Option<Arc<Semaphore>>,
+    ) {
+        loop {
+            let stream = tokio::select! {
+                _ = shutdown_signal.notified() => break,
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => stream,
                     Err(e) => {
                         eprintln!("Failed to accept connection: {}", e);
                         break;
                     }
+                },
+            };
+
+            let permit = match &limit {
+                Some(limit) => match limit.clone().try_acquire_owned() {
+                    Ok(permit) => Some(permit),
+                    Err(_) => {
+                        state
+                            .metrics
+                            .rejected_connections
+                            .fetch_add(1, Ordering::Relaxed);
+                        tokio::spawn(Self::reject_connection(stream, state.clone()));
+                        continue;
+                    }
+                },
+                None => None,
+            };
+
+            active_connections.fetch_add(1, Ordering::SeqCst);
+            let guard = ConnectionGuard(active_connections.clone());
+            let state = state.clone();
+            tokio::spawn(async move {
+                let _guard = guard;
+                let _permit = permit;
+                if let Err(e) = Self::handle_connection(stream, &state).await {
+                    eprintln!("Error handling connection: {}", e);
                 }
+            });
+        }
+    }
+
+    /// Answers a connection over `--max-connections` with a 503. The request
+    /// is read first (briefly) so the client gets the response rather than a
+    /// reset connection.
+    async fn reject_connection(stream: tokio::net::TcpStream, state: Arc<ConnectionState>) {
+        if !state.quiet {
+            ui::log(
+                "  Rejected connection: too many open connections"
+                    .yellow()
+                    .dimmed(),
+            );
+        }
+
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = tokio::io::BufReader::new(reader);
+        let _ = tokio::time::timeout(
+            Duration::from_secs(1),
+            Self::read_request(&mut reader, state.max_body_size),
+        )
+        .await;
+
+        let body = json!({ "error": "too many connections" }).to_string();
+        let _ = Self::write_response(
+            &mut writer,
+            "503 Service Unavailable",
+            JSON_CONTENT_TYPE,
+            body.as_bytes(),
+            false,
+            &[("Retry-After", "1")],
+        )
+        .await;
+    }
+
+    fn connection_state(&self) -> Arc<ConnectionState> {
+        Arc::new(ConnectionState {
+            url: self.url.clone(),
+            wallet: self.wallet.clone(),
+            running: self.running.clone(),
+            metrics: self.metrics.clone(),
+            settled: self.settled.clone(),
+            max_body_size: self.max_body_size,
+            quiet: self.quiet,
+        })
+    }
+
+    /// Runs the facilitator in the background as a detached copy of this
+    /// binary (`facilitator start --foreground`), so it outlives the command
+    /// that started it. A saved wallet is passed by address, any other by its
+    /// private key in [`PRIVATE_KEY_ENV`]. Output goes to
+    /// `~/.x402/facilitator.log`. Returns the child's PID, which is recorded
+    /// in the PID file so `stop` signals the right process.
+    pub async fn spawn_detached(
+        port: u16,
+        host: &str,
+        wallet: &crate::x402::wallet::Wallet,
+        max_body_size: usize,
+        max_connections: Option<usize>,
+    ) -> Result<PidFile> {
+        ui::header("Starting facilitator...");
+        Self::check_port_free(host, port).await?;
+
+        let exe = std::env::current_exe().context("Failed to locate the x402-cli executable")?;
+        let mut command = Command::new(&exe);
+        command
+            .args([
+                "facilitator",
+                "start",
+                "--foreground",
+                "--yes",
+                "--no-update-check",
+            ])
+            .arg("--port")
+            .arg(port.to_string())
+            .arg("--host")
+            .arg(host)
+            .arg("--network")
+            .arg(&wallet.network)
+            .arg("--max-body-size")
+            .arg(max_body_size.to_string())
+            .arg("--keystore-dir")
+            .arg(crate::x402::config::x402_dir()?);
+        if let Some(limit) = max_connections {
+            command.arg("--max-connections").arg(limit.to_string());
+        }
+        let chains_file = crate::x402::chains::chains_file()?;
+        if chains_file.is_file() {
+            command.arg("--chain-config").arg(chains_file);
+        }
+        if crate::x402::wallet::Wallet::load_from_address(&wallet.address).is_ok() {
+            command
+                .arg("--wallet")
+                .arg(&wallet.address)
+                .env_remove(PRIVATE_KEY_ENV);
+        } else {
+            command.env(PRIVATE_KEY_ENV, wallet.private_key());
+        }
+        if let Some(url) = crate::x402::config::fullnode_url(&wallet.network) {
+            command.env(crate::x402::config::X402_FULLNODE_URL_ENV, url);
+        }
+
+        Self::launch(
+            command,
+            PidFile {
+                pid: 0,
+                port,
+                host: host.to_string(),
+                wallet: wallet.address.clone(),
+                max_body_size,
+                max_connections,
+                program: program_name(&exe),
+                facilitator_bin: None,
+            },
+        )
+        .await
+    }
+
+    /// Launches `bin` as its own detached OS process instead of serving from
+    /// this one. The binary receives its settings through the
+    /// `X402_FACILITATOR_*` environment variables (and the resolved fullnode
+    /// as `X402_FULLNODE_URL`), must listen on `X402_FACILITATOR_HOST` and
+    /// `X402_FACILITATOR_PORT`, and writes its output to
+    /// `~/.x402/facilitator.log`. Returns the child's PID, which is recorded
+    /// in the PID file so `stop` signals the right process.
+    pub async fn spawn_external(
+        bin: &std::path::Path,
+        port: u16,
+        host: &str,
+        wallet: &crate::x402::wallet::Wallet,
+        max_body_size: usize,
+        max_connections: Option<usize>,
+    ) -> Result<PidFile> {
+        ui::header(&format!(
+            "Starting facilitator process {}...",
+            bin.display()
+        ));
+        Self::check_port_free(host, port).await?;
+
+        let mut command = Command::new(bin);
+        command
+            .env("X402_FACILITATOR_PORT", port.to_string())
+            .env("X402_FACILITATOR_HOST", host)
+            .env("X402_FACILITATOR_WALLET", &wallet.address)
+            .env("X402_FACILITATOR_NETWORK", &wallet.network)
+            .env(PRIVATE_KEY_ENV, wallet.private_key())
+            .env("X402_FACILITATOR_MAX_BODY_SIZE", max_body_size.to_string());
+        if let Some(limit) = max_connections {
+            command.env("X402_FACILITATOR_MAX_CONNECTIONS", limit.to_string());
+        }
+        if let Some(url) = crate::x402::config::fullnode_url(&wallet.network) {
+            command.env(crate::x402::config::X402_FULLNODE_URL_ENV, url);
+        }
+
+        Self::launch(
+            command,
+            PidFile {
+                pid: 0,
+                port,
+                host: host.to_string(),
+                wallet: wallet.address.clone(),
+                max_body_size,
+                max_connections,
+                program: program_name(bin),
+                facilitator_bin: Some(bin.to_path_buf()),
+            },
+        )
+        .await
+    }
+
+    async fn check_port_free(host: &str, port: u16) -> Result<()> {
+        match tokio::net::TcpListener::bind((host, port)).await {
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
+                Err(X402Error::PortInUse(port).into())
             }
-        });
+            _ => Ok(()),
+        }
+    }
+
+    /// Spawns `command` detached, with its output in the log file, waits for
+    /// it to listen on `record`'s address, and writes `record` with the
+    /// child's PID as the PID file.
+    async fn launch(mut command: Command, mut record: PidFile) -> Result<PidFile> {
+        let (host, port) = (record.host.clone(), record.port);
+        let program = record
+            .facilitator_bin
+            .as_ref()
+            .map_or_else(|| "x402-cli".to_string(), |bin| bin.display().to_string());
+
+        let log_path = Self::log_file_path()?;
+        if let Some(parent) = log_path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        let log = fs::File::create(&log_path)
+            .with_context(|| format!("Failed to create log file: {}", log_path.display()))?;
+        command
+            .stdin(std::process::Stdio::null())
+            .stdout(log.try_clone().context("Failed to open log file")?)
+            .stderr(log);
+
+        // A new process group keeps the facilitator alive when the terminal
+        // sends Ctrl-C to the CLI.
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(X402Error::ToolNotFound(program).into());
+            }
+            Err(e) => return Err(e).with_context(|| format!("Failed to launch {}", program)),
+        };
+        let pid = child.id();
+
+        let deadline = Instant::now() + Duration::from_secs(READY_TIMEOUT_SECS);
+        loop {
+            if let Some(status) = child
+                .try_wait()
+                .context("Failed to check facilitator process")?
+            {
+                anyhow::bail!(
+                    "Facilitator process exited ({}) before listening on port {}. See {}",
+                    status,
+                    port,
+                    log_path.display()
+                );
+            }
+            if tokio::net::TcpStream::connect((connect_host(&host), port))
+                .await
+                .is_ok()
+            {
+                break;
+            }
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                anyhow::bail!(
+                    "Facilitator process did not listen on port {} within {}s. See {}",
+                    port,
+                    READY_TIMEOUT_SECS,
+                    log_path.display()
+                );
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
 
-        thread::sleep(Duration::from_millis(200));
+        record.pid = pid;
+        Self::write_pid_file(&record)?;
+        ui::success(&format!("Process {} listening on {}", pid, record.url()));
 
-        if let Ok(bound) = receiver.try_recv() {
-            if !bound {
-                anyhow::bail!("Facilitator failed to start - could not bind to port");
+        Ok(record)
+    }
+
+    /// Stops accepting connections, waits for in-flight ones to finish (idle
+    /// keep-alive connections close within the keep-alive timeout), and removes
+    /// the PID file.
+    pub async fn shutdown(&self) -> Result<()> {
+        ui::header("Shutting down facilitator...");
+
+        self.running.store(false, Ordering::SeqCst);
+        // `notify_one` keeps the wakeup if the accept loop isn't waiting yet.
+        self.shutdown_signal.notify_one();
+
+        let deadline = Instant::now() + Duration::from_secs(KEEP_ALIVE_TIMEOUT_SECS + 1);
+        loop {
+            let active = self.active_connections.load(Ordering::SeqCst);
+            if active == 0 {
+                break;
+            }
+            if Instant::now() >= deadline {
+                ui::warn(&format!("Giving up on {} open connection(s)", active));
+                break;
             }
+            tokio::time::sleep(Duration::from_millis(50)).await;
         }
 
-        println!(
-            "{}",
-            format!("✓ Facilitator server started on {}", url.cyan()).bold()
-        );
-        println!(
-            "{}",
-            format!("  Using wallet: {}", facilitator.wallet.address.cyan()).dimmed()
-        );
-        println!("{}", "  Waiting for wallet connections...".dimmed());
+        Self::remove_pid_file()?;
 
-        Ok(facilitator)
+        ui::done("Facilitator stopped");
+        Ok(())
     }
 
-    pub fn stop() -> Result<bool> {
-        println!("{}", "Stopping facilitator...".yellow());
+    /// Blocks until Ctrl-C (or SIGTERM on Unix, as sent by systemd, Docker,
+    /// and most supervisors) and then shuts down cleanly: new connections are
+    /// refused, in-flight ones drain, and the PID file is removed.
+    pub async fn run_until_signal(&self) -> Result<()> {
+        #[cfg(unix)]
+        let signal = {
+            let mut terminate =
+                tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+                    .context("Failed to install SIGTERM handler")?;
+            tokio::select! {
+                result = tokio::signal::ctrl_c() => {
+                    result.context("Failed to listen for Ctrl-C")?;
+                    "Ctrl-C"
+                }
+                _ = terminate.recv() => "SIGTERM",
+            }
+        };
+        #[cfg(not(unix))]
+        let signal = {
+            tokio::signal::ctrl_c()
+                .await
+                .context("Failed to listen for Ctrl-C")?;
+            "Ctrl-C"
+        };
 
-        let output = std::process::Command::new("pkill")
-            .args(["-f", "x402-cli"])
-            .output()
-            .context("Failed to execute pkill command")?;
+        ui::blank_line();
+        ui::info(&format!("Received {}", signal));
+        self.shutdown().await
+    }
+
+    /// Signals the facilitator recorded in the PID file and removes the
+    /// file. Returns the PID it stopped, or `None` when no facilitator was
+    /// running (a stale PID file is removed all the same).
+    pub fn stop() -> Result<Option<u32>> {
+        ui::header("Stopping facilitator...");
+
+        let pid_file = match Self::read_pid_file()? {
+            Some(pid_file) => pid_file,
+            None => {
+                ui::warn("No facilitator processes found");
+                return Ok(None);
+            }
+        };
 
-        if output.status.success() {
-            println!("{}", "✓ Facilitator stopped".green().bold());
-            Ok(true)
+        let stopped = if !Self::is_process_alive(pid_file.pid) {
+            ui::info(&format!(
+                "Removing stale PID file (process {} not running)",
+                pid_file.pid
+            ));
+            false
+        } else if !Self::is_facilitator_process(&pid_file) {
+            ui::info(&format!(
+                "Removing stale PID file (process {} is no longer the facilitator)",
+                pid_file.pid
+            ));
+            false
         } else {
-            println!("{}", "  ⚠ No facilitator processes found".yellow().dimmed());
-            Ok(false)
+            let status = Command::new("kill")
+                .arg(pid_file.pid.to_string())
+                .status()
+                .context("Failed to execute kill command")?;
+            status.success()
+        };
+
+        Self::remove_pid_file()?;
+
+        if stopped {
+            ui::success(&format!("Stopped process {}", pid_file.pid));
         }
+
+        Ok(stopped.then_some(pid_file.pid))
     }
 
-    fn handle_connection(mut stream: TcpStream, url: &str) -> Result<()> {
-        stream
-            .set_read_timeout(Some(Duration::from_secs(5)))
-            .context("Failed to set read timeout")?;
+    /// Stops any running instance, waits for its port to free up, and starts a
+    /// new detached one (running the same `--facilitator-bin`, if any).
+    /// Without `port`, the previous instance's port is reused; without
+    /// `wallet`, its wallet. Returns the new process's PID file.
+    pub async fn restart(
+        port: Option<u16>,
+        wallet: Option<crate::x402::wallet::Wallet>,
+    ) -> Result<PidFile> {
+        let previous = Self::read_pid_file()?;
 
-        let mut reader = BufReader::new(&stream);
-        let mut request_line = String::new();
-        reader.read_line(&mut request_line)?;
+        // Everything the new process needs is settled before the running one
+        // is stopped, so a restart that can't go ahead leaves it serving.
+        let port = port
+            .or(previous.as_ref().map(|p| p.port))
+            .unwrap_or(DEFAULT_PORT);
+        let max_body_size = previous
+            .as_ref()
+            .map_or(DEFAULT_MAX_BODY_SIZE, |p| p.max_body_size);
+        let max_connections = previous.as_ref().and_then(|p| p.max_connections);
+        let host = previous
+            .as_ref()
+            .map_or_else(default_host, |p| p.host.clone());
+        let facilitator_bin = previous.as_ref().and_then(|p| p.facilitator_bin.clone());
+        if let Some(bin) = &facilitator_bin {
+            if !bin.is_file() {
+                anyhow::bail!(
+                    "Facilitator binary {} no longer exists; the running facilitator was left alone",
+                    bin.display()
+                );
+            }
+        }
+        let wallet = Self::restart_wallet(wallet, previous.as_ref())?;
+        if previous.as_ref().map(|p| (p.host.as_str(), p.port)) != Some((host.as_str(), port)) {
+            Self::check_port_free(&host, port).await?;
+        }
+
+        Self::stop()?;
+        Self::wait_for_port_free(&host, port, Duration::from_secs(5)).await?;
+
+        match facilitator_bin {
+            Some(bin) => {
+                Self::spawn_external(&bin, port, &host, &wallet, max_body_size, max_connections)
+                    .await?
+            }
+            None => {
+                Self::spawn_detached(port, &host, &wallet, max_body_size, max_connections).await?
+            }
+        };
+        Self::read_pid_file()?.context("Facilitator started but wrote no PID file")
+    }
 
-        let request_line = request_line.trim();
-        println!("{}", format!("  Request: {}", request_line).dimmed());
+    /// The wallet `restart` runs with: `wallet` if given, else the running
+    /// facilitator's saved wallet, else the default one. A facilitator
+    /// started with `--private-key` has no saved wallet to come back with,
+    /// which is an error rather than a silent switch to another account.
+    fn restart_wallet(
+        wallet: Option<crate::x402::wallet::Wallet>,
+        previous: Option<&PidFile>,
+    ) -> Result<crate::x402::wallet::Wallet> {
+        use crate::x402::wallet::Wallet;
 
-        let response = if request_line.contains("GET /health") {
+        match (wallet, previous) {
+            (Some(wallet), _) => Ok(wallet),
+            (None, Some(previous)) => {
+                Wallet::load_from_address(&previous.wallet).with_context(|| {
+                    format!(
+                        "The running facilitator's wallet {} isn't saved (was it started with \
+                     --private-key?); pass --wallet, or stop it and start it again. \
+                     The running facilitator was left alone",
+                        previous.wallet
+                    )
+                })
+            }
+            (None, None) => Wallet::find_default(),
+        }
+    }
+
+    /// Fetches `/metrics` from a running facilitator. Without `port`, the port
+    /// recorded in the PID file is used.
+    pub async fn fetch_metrics(port: Option<u16>) -> Result<MetricsSnapshot> {
+        let pid_file = Self::read_pid_file()?;
+        let host = pid_file
+            .as_ref()
+            .map_or_else(default_host, |pid_file| pid_file.host.clone());
+        let port = port
+            .or(pid_file.map(|pid_file| pid_file.port))
+            .unwrap_or(DEFAULT_PORT);
+        let metrics_url = format!("{}/metrics", base_url(&host, port));
+
+        let response = reqwest::get(&metrics_url).await.with_context(|| {
             format!(
-                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{}",
-                r#"{"status":"healthy","timestamp":"{timestamp}"}"#.replace(
-                    "{timestamp}",
-                    &chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string()
-                )
+                "Failed to reach facilitator at {}. Is it running?",
+                metrics_url
             )
-        } else if request_line.contains("POST") {
-            format!(
-                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{}",
+        })?;
+
+        response
+            .json()
+            .await
+            .context("Failed to parse facilitator metrics")
+    }
+
+    pub fn pid_file_path() -> Result<PathBuf> {
+        Ok(crate::x402::config::x402_dir()?.join(PID_FILE_NAME))
+    }
+
+    /// Where a detached facilitator's output goes.
+    pub fn log_file_path() -> Result<PathBuf> {
+        Ok(crate::x402::config::x402_dir()?.join(LOG_FILE_NAME))
+    }
+
+    pub fn read_pid_file() -> Result<Option<PidFile>> {
+        let path = Self::pid_file_path()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let data = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read PID file: {}", path.display()))?;
+        let pid_file = serde_json::from_str(&data)
+            .with_context(|| format!("Failed to parse PID file: {}", path.display()))?;
+
+        Ok(Some(pid_file))
+    }
+
+    fn write_pid_file(pid_file: &PidFile) -> Result<()> {
+        let path = Self::pid_file_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+
+        let data = serde_json::to_string(pid_file).context("Failed to serialize PID file")?;
+        fs::write(&path, data)
+            .with_context(|| format!("Failed to write PID file: {}", path.display()))
+    }
+
+    fn remove_pid_file() -> Result<()> {
+        let path = Self::pid_file_path()?;
+        if path.exists() {
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove PID file: {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Whether `pid_file.pid` still runs the recorded program. Older PID
+    /// files don't record one, so they never match.
+    fn is_facilitator_process(pid_file: &PidFile) -> bool {
+        let Some(program) = &pid_file.program else {
+            return false;
+        };
+        Command::new("ps")
+            .args(["-p", &pid_file.pid.to_string(), "-o", "command="])
+            .output()
+            .map(|output| {
+                output.status.success()
+                    && String::from_utf8_lossy(&output.stdout).contains(program.as_str())
+            })
+            .unwrap_or(false)
+    }
+
+    fn is_process_alive(pid: u32) -> bool {
+        Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    /// Polls `GET /health` on the facilitator at `host:port` until it answers
+    /// 200, backing off from 50ms up to 1s between attempts. Returns how long
+    /// that took; fails once `timeout` passes without a healthy answer.
+    pub async fn wait_until_healthy(host: &str, port: u16, timeout: Duration) -> Result<Duration> {
+        let health_url = format!("{}/health", base_url(host, port));
+        let client = reqwest::Client::new();
+        let started = Instant::now();
+        let deadline = started + timeout;
+        let mut backoff = Duration::from_millis(50);
+
+        loop {
+            let last = match client
+                .get(&health_url)
+                .timeout(Duration::from_secs(2))
+                .send()
+                .await
+            {
+                Ok(response) if response.status().is_success() => return Ok(started.elapsed()),
+                Ok(response) => format!("status {}", response.status()),
+                Err(e) => e.to_string(),
+            };
+
+            if Instant::now() + backoff >= deadline {
+                anyhow::bail!(
+                    "Facilitator did not answer GET {} with 200 within {}s (last attempt: {})",
+                    health_url,
+                    timeout.as_secs(),
+                    last
+                );
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(Duration::from_millis(HEALTH_MAX_BACKOFF_MS));
+        }
+    }
+
+    async fn wait_for_port_free(host: &str, port: u16, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+
+        while tokio::net::TcpListener::bind((host, port)).await.is_err() {
+            if Instant::now() >= deadline {
+                anyhow::bail!("Port {} is still in use after {}s", port, timeout.as_secs());
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        Ok(())
+    }
+
+    /// Serves requests on one connection until the client closes it, asks
+    /// for `Connection: close`, stays idle past the keep-alive timeout, or the
+    /// facilitator shuts down.
+    async fn handle_connection(
+        stream: tokio::net::TcpStream,
+        state: &ConnectionState,
+    ) -> Result<()> {
+        let metrics = &state.metrics;
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = tokio::io::BufReader::new(reader);
+
+        for served in 1.. {
+            let read = tokio::time::timeout(
+                Duration::from_secs(KEEP_ALIVE_TIMEOUT_SECS),
+                Self::read_request(&mut reader, state.max_body_size),
+            )
+            .await;
+            let request = match read {
+                // Idle past the keep-alive timeout.
+                Err(_) => break,
+                Ok(Ok(Some(request))) => request,
+                Ok(Ok(None)) => break,
+                Ok(Err(e)) if e.is::<BodyTooLarge>() || e.is::<HeadersTooLarge>() => {
+                    metrics.requests.fetch_add(1, Ordering::Relaxed);
+                    metrics.failures.fetch_add(1, Ordering::Relaxed);
+                    if !state.quiet {
+                        ui::log(format!("  Rejected: {}", e).yellow().dimmed());
+                    }
+
+                    let (status, body) = if e.is::<HeadersTooLarge>() {
+                        let body = json!({
+                            "error": "request headers too large",
+                            "limit": MAX_HEADER_BYTES,
+                            "maxHeaders": MAX_HEADERS,
+                        });
+                        ("431 Request Header Fields Too Large", body)
+                    } else {
+                        let body = json!({
+                            "error": "payload too large",
+                            "limit": state.max_body_size,
+                        });
+                        ("413 Payload Too Large", body)
+                    };
+                    let body = body.to_string();
+                    Self::write_response(
+                        &mut writer,
+                        status,
+                        JSON_CONTENT_TYPE,
+                        body.as_bytes(),
+                        false,
+                        &[],
+                    )
+                    .await?;
+                    break;
+                }
+                Ok(Err(e)) => {
+                    metrics.failures.fetch_add(1, Ordering::Relaxed);
+                    return Err(e);
+                }
+            };
+            metrics.requests.fetch_add(1, Ordering::Relaxed);
+
+            let request_id = request.request_id();
+            let traced = match request_id {
+                Some(id) => format!(" [{}]", id),
+                None => String::new(),
+            };
+            if !state.quiet {
+                ui::log(
+                    format!(
+                        "  Request: {} {} {}{}",
+                        request.method, request.path, request.version, traced
+                    )
+                    .dimmed(),
+                );
+            }
+
+            let keep_alive = request.wants_keep_alive()
+                && served < MAX_REQUESTS_PER_CONNECTION
+                && state.running.load(Ordering::Relaxed);
+            let (body, content_type) = Self::route(&request, state).await;
+
+            let echoed: Vec<(&str, &str)> = request_id
+                .map(|id| (REQUEST_ID_HEADER, id))
+                .into_iter()
+                .collect();
+            Self::write_response(
+                &mut writer,
+                "200 OK",
+                content_type,
+                body.as_bytes(),
+                keep_alive,
+                &echoed,
+            )
+            .await?;
+
+            if !keep_alive {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes a response with the given status line, content type, and any extra
+    /// `headers` (e.g. the echoed `X-Request-ID`). `Content-Length` is the body's byte length so
+    /// keep-alive clients know where it ends. The body goes out in
+    /// `WRITE_CHUNK_SIZE` pieces through a buffer of the same size, so small
+    /// responses still leave in a single write and large ones aren't copied
+    /// into one allocation with the headers.
+    pub(crate) async fn write_response(
+        stream: &mut (impl AsyncWrite + Unpin),
+        status: &str,
+        content_type: &str,
+        body: &[u8],
+        keep_alive: bool,
+        headers: &[(&str, &str)],
+    ) -> Result<()> {
+        let mut writer = tokio::io::BufWriter::with_capacity(WRITE_CHUNK_SIZE, stream);
+
+        let extra: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}: {}\r\n", name, value))
+            .collect();
+        let head = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: {}\r\n{}\r\n",
+            status,
+            content_type,
+            body.len(),
+            if keep_alive { "keep-alive" } else { "close" },
+            extra
+        );
+        writer.write_all(head.as_bytes()).await?;
+
+        for chunk in body.chunks(WRITE_CHUNK_SIZE) {
+            writer.write_all(chunk).await?;
+        }
+
+        writer.flush().await?;
+        Ok(())
+    }
+
+    /// The response body and its content type. `/health` and the root answer
+    /// browsers (`Accept: text/html`) with a status page instead of JSON.
+    async fn route(request: &HttpRequest, state: &ConnectionState) -> (String, &'static str) {
+        let ConnectionState {
+            url,
+            wallet,
+            metrics,
+            settled,
+            quiet,
+            ..
+        } = state;
+        let (body, succeeded) = match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/health") => {
+                let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+                if request.prefers_html() {
+                    let rows = [
+                        ("Status", "healthy".to_string()),
+                        ("Time", format!("{} UTC", timestamp)),
+                    ];
+                    return (status_page("Facilitator healthy", &rows), HTML_CONTENT_TYPE);
+                }
+                let body = r#"{"status":"healthy","timestamp":"{timestamp}"}"#
+                    .replace("{timestamp}", &timestamp);
+                return (body, JSON_CONTENT_TYPE);
+            }
+            ("GET", "/metrics") => {
+                let body =
+                    serde_json::to_string(&metrics.snapshot()).unwrap_or_else(|_| "{}".to_string());
+                return (body, JSON_CONTENT_TYPE);
+            }
+            ("POST", "/verify") => {
+                metrics.verify_calls.fetch_add(1, Ordering::Relaxed);
+                Self::verify(&request.body, wallet, settled, *quiet)
+            }
+            ("POST", "/settle") => {
+                metrics.settle_calls.fetch_add(1, Ordering::Relaxed);
+                Self::settle(&request.body, wallet, settled, *quiet).await
+            }
+            ("POST", _) => (
                 format!(
                     r#"{{"message":"Payment facilitated","status":"success","url":"{}"}}"#,
                     url
+                ),
+                true,
+            ),
+            ("GET", _) if request.prefers_html() => {
+                let rows = [
+                    ("URL", url.to_string()),
+                    ("Network", wallet.network.clone()),
+                    ("Health", "/health".to_string()),
+                    ("Metrics", "/metrics".to_string()),
+                ];
+                return (status_page("Facilitator running", &rows), HTML_CONTENT_TYPE);
+            }
+            _ => {
+                let body = format!(r#"{{"message":"Facilitator running","url":"{}"}}"#, url);
+                return (body, JSON_CONTENT_TYPE);
+            }
+        };
+
+        if succeeded {
+            metrics.successes.fetch_add(1, Ordering::Relaxed);
+        } else {
+            metrics.failures.fetch_add(1, Ordering::Relaxed);
+        }
+
+        (body, JSON_CONTENT_TYPE)
+    }
+
+    /// Answers `/verify`. A payment is valid when [`Self::check_payment`]
+    /// accepts it and its transaction hasn't been settled. Rejections name
+    /// the request field at fault, when there is one, as `invalidField`.
+    fn verify(
+        body: &[u8],
+        wallet: &crate::x402::wallet::Wallet,
+        settled: &Mutex<SettledPayments>,
+        quiet: bool,
+    ) -> (String, bool) {
+        let payment = match Self::check_payment(body, wallet) {
+            Ok(payment) => payment,
+            Err(rejection) => {
+                let mut body = json!({ "isValid": false, "invalidReason": rejection.reason });
+                if let Some(field) = rejection.field {
+                    body["invalidField"] = json!(field);
+                }
+                return (body.to_string(), false);
+            }
+        };
+
+        let (sender, sequence_number) = payment.replay_key();
+        let replayed = settled
+            .lock()
+            .map(|mut settled| settled.contains(&(sender, sequence_number), unix_now()))
+            .unwrap_or(true);
+
+        if replayed {
+            if !quiet {
+                ui::log(
+                    format!(
+                        "  Rejected replayed payment {} #{}",
+                        sender, sequence_number
+                    )
+                    .dimmed()
+                    .yellow(),
+                );
+            }
+            return (
+                json!({ "isValid": false, "invalidReason": "replay detected" }).to_string(),
+                false,
+            );
+        }
+
+        (json!({ "isValid": true }).to_string(), true)
+    }
+
+    /// Answers `/settle` by submitting the signed payment to the network's
+    /// fullnode and waiting for it to commit. Checking and recording the
+    /// payment happen under one lock, so two concurrent settlements of the
+    /// same payment cannot both be submitted; a failed submission releases
+    /// it for a retry.
+    async fn settle(
+        body: &[u8],
+        wallet: &crate::x402::wallet::Wallet,
+        settled: &Mutex<SettledPayments>,
+        quiet: bool,
+    ) -> (String, bool) {
+        let network = serde_json::from_slice::<Value>(body)
+            .ok()
+            .and_then(|request| {
+                request["paymentRequirements"]["network"]
+                    .as_str()
+                    .map(str::to_string)
+            })
+            .unwrap_or_else(|| wallet.network.clone());
+
+        let payment = match Self::check_payment(body, wallet) {
+            Ok(payment) => payment,
+            Err(rejection) => {
+                let body = json!({
+                    "success": false,
+                    "errorReason": rejection.reason,
+                    "network": network,
+                });
+                return (body.to_string(), false);
+            }
+        };
+        let key = payment.replay_key();
+        let expiration = payment.raw_txn.expiration_timestamp_secs;
+
+        let first_settlement = settled
+            .lock()
+            .map(|mut settled| settled.insert(key, expiration, unix_now()))
+            .unwrap_or(false);
+
+        if !first_settlement {
+            if !quiet {
+                ui::log(
+                    format!("  Rejected replayed payment {} #{}", key.0, key.1)
+                        .dimmed()
+                        .yellow(),
+                );
+            }
+            let body = json!({
+                "success": false,
+                "errorReason": "replay detected",
+                "network": network,
+            });
+            return (body.to_string(), false);
+        }
+
+        let hash = match Self::submit(payment, wallet, &network).await {
+            Ok(hash) => hash,
+            Err(e) => {
+                if let Ok(mut settled) = settled.lock() {
+                    settled.remove(&key);
+                }
+                if !quiet {
+                    ui::log(format!("  Settlement failed: {:#}", e).dimmed().yellow());
+                }
+                let body = json!({
+                    "success": false,
+                    "errorReason": format!("{:#}", e),
+                    "network": network,
+                });
+                return (body.to_string(), false);
+            }
+        };
+
+        let body = json!({
+            "success": true,
+            "transaction": hash,
+            "network": network,
+            "facilitator": wallet.address,
+        });
+        (body.to_string(), true)
+    }
+
+    /// Signs a sponsored payment as its fee payer, then submits it to
+    /// `network`'s fullnode and returns the committed transaction's hash.
+    async fn submit(
+        payment: Payment,
+        wallet: &crate::x402::wallet::Wallet,
+        network: &str,
+    ) -> Result<String> {
+        let fee_payer = match payment.fee_payer {
+            Some(fee_payer) => {
+                wallet.check_key_address()?;
+                let signer =
+                    tx::sign_payment(&payment.raw_txn, Some(fee_payer), &wallet.signing_key()?)?;
+                Some((fee_payer, signer))
+            }
+            None => None,
+        };
+        let signed = tx::SignedTransaction::payment(payment.raw_txn, payment.sender, fee_payer);
+
+        let fullnode = crate::x402::config::fullnode_url(network)
+            .with_context(|| format!("No fullnode known for network '{}'", network))?;
+        crate::x402::wallet::submit_transaction(&reqwest::Client::new(), &fullnode, &signed, true)
+            .await
+    }
+
+    /// Decodes and checks a `/verify` or `/settle` request's payment. The
+    /// `paymentPayload.payload.transaction` bytes must be a BCS
+    /// `RawTransaction` (the fee-payer form naming `feePayerAddress`, which
+    /// must be this facilitator's account, when the payload has one)
+    /// transferring what `paymentRequirements` asks for, and
+    /// `senderAuthenticator` must be the sender's signature over it.
+    fn check_payment(
+        body: &[u8],
+        wallet: &crate::x402::wallet::Wallet,
+    ) -> std::result::Result<Payment, Rejection> {
+        let request: Value = serde_json::from_slice(body).map_err(|_| Rejection {
+            reason: "invalid request body".to_string(),
+            field: None,
+        })?;
+        let in_field = |field: &str, reason: String| Rejection {
+            reason,
+            field: Some(field.to_string()),
+        };
+        let in_transaction = |reason: String| in_field(TRANSACTION_FIELD, reason);
+
+        let payload = &request["paymentPayload"]["payload"];
+        let transaction = payload["transaction"]
+            .as_str()
+            .ok_or_else(|| in_transaction("missing transaction".to_string()))?;
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(transaction)
+            .map_err(|_| in_transaction("invalid transaction encoding".to_string()))?;
+
+        let fee_payer = match payload["feePayerAddress"].as_str() {
+            Some(address) => {
+                let address: tx::AccountAddress = address
+                    .parse()
+                    .map_err(|e: anyhow::Error| in_field(FEE_PAYER_FIELD, e.to_string()))?;
+                if wallet.address.parse::<tx::AccountAddress>().ok() != Some(address) {
+                    return Err(in_field(
+                        FEE_PAYER_FIELD,
+                        format!(
+                            "fee payer {} is not this facilitator's account {}",
+                            address, wallet.address
+                        ),
+                    ));
+                }
+                Some(address)
+            }
+            None => None,
+        };
+        let raw_txn = tx::decode_payment(&bytes, fee_payer.is_some())
+            .map_err(|e| in_transaction(e.to_string()))?;
+        if fee_payer.is_some()
+            && tx::encode_payment(&raw_txn, fee_payer).ok().as_deref() != Some(&bytes[..])
+        {
+            return Err(in_transaction(
+                "transaction names a different fee payer than feePayerAddress".to_string(),
+            ));
+        }
+
+        if raw_txn.expiration_timestamp_secs <= unix_now() {
+            return Err(in_transaction(format!(
+                "transaction expired at {}",
+                raw_txn.expiration_timestamp_secs
+            )));
+        }
+
+        let transfer = raw_txn.payload.as_transfer().ok_or_else(|| {
+            in_transaction("transaction is not a coin or fungible asset transfer".to_string())
+        })?;
+        let requirements = &request["paymentRequirements"];
+        let field = |name: &str| requirements[name].as_str().unwrap_or_default().to_string();
+        transfer
+            .check(&field("asset"), &field("payTo"), &field("amount"))
+            .map_err(|mismatch| Rejection {
+                reason: mismatch.reason,
+                field: Some(format!("paymentRequirements.{}", mismatch.field)),
+            })?;
+
+        let sender = payload["senderAuthenticator"]
+            .as_str()
+            .and_then(|encoded| {
+                base64::engine::general_purpose::STANDARD
+                    .decode(encoded)
+                    .ok()
+            })
+            .and_then(|bytes| bcs::from_bytes::<tx::AccountAuthenticator>(&bytes).ok())
+            .ok_or_else(|| {
+                in_field(
+                    SENDER_AUTHENTICATOR_FIELD,
+                    "missing or invalid sender authenticator".to_string(),
                 )
-            )
+            })?;
+        tx::verify_sender(&raw_txn, fee_payer, &sender)
+            .map_err(|e| in_field(SENDER_AUTHENTICATOR_FIELD, e.to_string()))?;
+
+        Ok(Payment {
+            raw_txn,
+            fee_payer,
+            sender,
+        })
+    }
+
+    /// Reads one request from the connection, returning `None` once the
+    /// client has closed its side. The request line and headers may take up
+    /// to [`MAX_HEADER_BYTES`] and [`MAX_HEADERS`] fields, or the read fails
+    /// with [`HeadersTooLarge`]. Bodies are read only up to `max_body_size`;
+    /// a larger `Content-Length` fails with [`BodyTooLarge`] before any of
+    /// the body is read.
+    pub(crate) async fn read_request(
+        reader: &mut (impl AsyncBufRead + Unpin),
+        max_body_size: usize,
+    ) -> Result<Option<HttpRequest>> {
+        let mut budget = MAX_HEADER_BYTES;
+        let mut request_line = String::new();
+        if Self::read_head_line(reader, &mut request_line, &mut budget).await? == 0 {
+            return Ok(None);
+        }
+
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_string();
+        let path = parts.next().unwrap_or_default().to_string();
+        let version = parts.next().unwrap_or("HTTP/1.0").to_string();
+
+        let mut headers = Vec::new();
+        loop {
+            let mut line = String::new();
+            if Self::read_head_line(reader, &mut line, &mut budget).await? == 0 {
+                break;
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if headers.len() == MAX_HEADERS {
+                return Err(HeadersTooLarge.into());
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+            }
+        }
+
+        let content_length = headers
+            .iter()
+            .find(|(name, _)| name == "content-length")
+            .and_then(|(_, value)| value.parse::<usize>().ok())
+            .unwrap_or(0);
+
+        if content_length > max_body_size {
+            return Err(BodyTooLarge {
+                declared: content_length,
+                limit: max_body_size,
+            }
+            .into());
+        }
+
+        let mut body = vec![0u8; content_length];
+        reader
+            .read_exact(&mut body)
+            .await
+            .context("Failed to read request body")?;
+
+        Ok(Some(HttpRequest {
+            method,
+            path,
+            version,
+            headers,
+            body,
+        }))
+    }
+
+    /// Reads one line of a request's head into `line`, taking its length
+    /// out of `budget`. Returns 0 at end of input, and fails with
+    /// [`HeadersTooLarge`] once a line would run past what's left.
+    async fn read_head_line(
+        reader: &mut (impl AsyncBufRead + Unpin),
+        line: &mut String,
+        budget: &mut usize,
+    ) -> Result<usize> {
+        if *budget == 0 {
+            return Err(HeadersTooLarge.into());
+        }
+        let read = (&mut *reader).take(*budget as u64).read_line(line).await?;
+        if read == *budget && !line.ends_with('\n') {
+            return Err(HeadersTooLarge.into());
+        }
+        *budget -= read;
+        Ok(read)
+    }
+}
+
+/// A facilitator served from this process on a free local port for the
+/// length of one command, e.g. `test payment --facilitator embedded`. It
+/// logs nothing, writes no PID file, and stops taking connections when
+/// dropped.
+pub struct EmbeddedFacilitator(Facilitator);
+
+impl EmbeddedFacilitator {
+    pub async fn start(wallet: crate::x402::wallet::Wallet) -> Result<Self> {
+        let facilitator =
+            Facilitator::serve(0, DEFAULT_HOST, wallet, DEFAULT_MAX_BODY_SIZE, None, true).await?;
+        Ok(EmbeddedFacilitator(facilitator))
+    }
+
+    pub fn url(&self) -> &str {
+        &self.0.url
+    }
+
+    pub fn wallet(&self) -> &crate::x402::wallet::Wallet {
+        &self.0.wallet
+    }
+}
+
+impl Drop for EmbeddedFacilitator {
+    fn drop(&mut self) {
+        self.0.running.store(false, Ordering::SeqCst);
+        self.0.shutdown_signal.notify_one();
+    }
+}
+
+#[derive(Debug)]
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    pub version: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_lowercase();
+        self.headers
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The client's `X-Request-ID`, if it is a plausible ID: 1-128 visible
+    /// ASCII characters, so it is safe to log and to echo in a header.
+    pub fn request_id(&self) -> Option<&str> {
+        self.header(REQUEST_ID_HEADER).filter(|id| {
+            !id.is_empty() && id.len() <= 128 && id.chars().all(|c| c.is_ascii_graphic())
+        })
+    }
+
+    /// Whether the client would rather have HTML than JSON: `Accept` lists
+    /// `text/html` with a higher quality than `application/json`. Wildcards
+    /// count for neither, so `curl` and API clients keep getting JSON.
+    pub fn prefers_html(&self) -> bool {
+        let Some(accept) = self.header("accept") else {
+            return false;
+        };
+
+        let mut html = 0.0;
+        let mut json = 0.0;
+        for range in accept.split(',') {
+            let mut parts = range.split(';').map(str::trim);
+            let media = parts.next().unwrap_or_default().to_lowercase();
+            let quality = parts
+                .find_map(|param| param.strip_prefix("q="))
+                .and_then(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            match media.as_str() {
+                "text/html" => html = quality,
+                "application/json" => json = quality,
+                _ => {}
+            }
+        }
+        html > json
+    }
+
+    /// HTTP/1.1 connections persist unless the client sends `Connection: close`;
+    /// HTTP/1.0 connections only persist when the client asks for keep-alive.
+    pub fn wants_keep_alive(&self) -> bool {
+        let connection = self.header("connection").map(|v| v.to_lowercase());
+        if self.version == "HTTP/1.1" {
+            connection.as_deref() != Some("close")
         } else {
+            connection.as_deref() == Some("keep-alive")
+        }
+    }
+}
+
+/// A minimal HTML page: a heading and a table of `rows`, escaped.
+fn status_page(title: &str, rows: &[(&str, String)]) -> String {
+    let rows: String = rows
+        .iter()
+        .map(|(label, value)| {
             format!(
-                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{}",
-                format!(r#"{{"message":"Facilitator running","url":"{}"}}"#, url)
+                "<tr><th>{}</th><td>{}</td></tr>",
+                escape_html(label),
+                escape_html(value)
             )
+        })
+        .collect();
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{title}</title>\
+         <style>body{{font-family:sans-serif;margin:2em}}th{{text-align:left;padding-right:1em}}</style>\
+         </head><body><h1>{title}</h1><table>{rows}</table></body></html>\n",
+        title = escape_html(title),
+        rows = rows
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn read(request: &[u8], max_body_size: usize) -> Result<Option<HttpRequest>> {
+        let mut reader = tokio::io::BufReader::new(request);
+        Facilitator::read_request(&mut reader, max_body_size).await
+    }
+
+    #[tokio::test]
+    async fn reads_a_request_within_the_limits() {
+        let request = read(
+            b"POST /verify HTTP/1.1\r\nContent-Length: 2\r\nX-Request-ID: abc\r\n\r\n{}",
+            1024,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(
+            (request.method.as_str(), request.path.as_str()),
+            ("POST", "/verify")
+        );
+        assert_eq!(request.request_id(), Some("abc"));
+        assert_eq!(request.body, b"{}");
+        assert!(read(b"", 1024).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn oversized_bodies_are_refused_unread() {
+        let error = read(b"POST /settle HTTP/1.1\r\nContent-Length: 11\r\n\r\n", 10)
+            .await
+            .unwrap_err();
+        let too_large = error.downcast_ref::<BodyTooLarge>().unwrap();
+        assert_eq!((too_large.declared, too_large.limit), (11, 10));
+    }
+
+    #[tokio::test]
+    async fn oversized_heads_are_refused() {
+        let long_header = format!(
+            "GET / HTTP/1.1\r\nX-Padding: {}\r\n\r\n",
+            "a".repeat(MAX_HEADER_BYTES)
+        );
+        let long_line = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_HEADER_BYTES));
+        let many_headers = format!(
+            "GET / HTTP/1.1\r\n{}\r\n",
+            "X-A: b\r\n".repeat(MAX_HEADERS + 1)
+        );
+        for request in [long_header, long_line, many_headers] {
+            let error = read(request.as_bytes(), 1024).await.unwrap_err();
+            assert!(error.is::<HeadersTooLarge>(), "{}", error);
+        }
+
+        let most_headers = format!("GET / HTTP/1.1\r\n{}\r\n", "X-A: b\r\n".repeat(MAX_HEADERS));
+        let request = read(most_headers.as_bytes(), 1024).await.unwrap().unwrap();
+        assert_eq!(request.headers.len(), MAX_HEADERS);
+    }
+
+    #[test]
+    fn settled_payments_are_keyed_by_sender_and_sequence_number() {
+        let sender: tx::AccountAddress = "0xa11ce".parse().unwrap();
+        let mut settled = SettledPayments::default();
+
+        assert!(settled.insert((sender, 7), 200, 100));
+        assert!(settled.contains(&(sender, 7), 100));
+        // The same sequence number again, however the bytes differ.
+        assert!(!settled.insert((sender, 7), 300, 100));
+        // The sender's next transaction, or another sender's.
+        assert!(settled.insert((sender, 8), 200, 100));
+        assert!(settled.insert(("0xb0b".parse().unwrap(), 7), 200, 100));
+
+        settled.remove(&(sender, 7));
+        assert!(!settled.contains(&(sender, 7), 100));
+    }
+
+    #[test]
+    fn settled_payments_are_dropped_once_expired() {
+        let sender: tx::AccountAddress = "0xa11ce".parse().unwrap();
+        let mut settled = SettledPayments::default();
+        settled.insert((sender, 1), 150, 100);
+        settled.insert((sender, 2), 300, 100);
+
+        assert!(settled.contains(&(sender, 1), 149));
+        assert!(!settled.contains(&(sender, 1), 150));
+        assert_eq!(settled.expirations.len(), 1);
+
+        settled.insert((sender, 3), 400, 300);
+        assert_eq!(
+            settled.expirations.keys().collect::<Vec<_>>(),
+            vec![&(sender, 3)]
+        );
+    }
+
+    const ASSET: &str = "0x1::aptos_coin::AptosCoin";
+    const PAY_TO: &str = "0xb0b";
+
+    fn wallet(key_byte: &str) -> crate::x402::wallet::Wallet {
+        crate::x402::wallet::Wallet::import(&key_byte.repeat(32), "testnet").unwrap()
+    }
+
+    /// A `/verify` body paying 1000 octas to [`PAY_TO`] from `sender`,
+    /// sponsored by `fee_payer` when given.
+    fn payment_body(
+        sender: &crate::x402::wallet::Wallet,
+        sequence_number: u64,
+        fee_payer: Option<&crate::x402::wallet::Wallet>,
+    ) -> Vec<u8> {
+        let fee_payer = fee_payer.map(|wallet| wallet.address.parse().unwrap());
+        let raw_txn = tx::RawTransaction {
+            sender: sender.address.parse().unwrap(),
+            sequence_number,
+            payload: tx::TransactionPayload::transfer(ASSET, PAY_TO.parse().unwrap(), 1000)
+                .unwrap(),
+            max_gas_amount: 2000,
+            gas_unit_price: 100,
+            expiration_timestamp_secs: unix_now() + 600,
+            chain_id: 2,
         };
+        let authenticator =
+            tx::sign_payment(&raw_txn, fee_payer, &sender.signing_key().unwrap()).unwrap();
+
+        let encode = |bytes: Vec<u8>| base64::engine::general_purpose::STANDARD.encode(bytes);
+        let mut payload = json!({
+            "transaction": encode(tx::encode_payment(&raw_txn, fee_payer).unwrap()),
+            "senderAuthenticator": encode(bcs::to_bytes(&authenticator).unwrap()),
+        });
+        if let Some(fee_payer) = fee_payer {
+            payload["feePayerAddress"] = json!(fee_payer.to_string());
+        }
+        json!({
+            "paymentPayload": { "payload": payload },
+            "paymentRequirements": { "asset": ASSET, "payTo": PAY_TO, "amount": "1000" },
+        })
+        .to_string()
+        .into_bytes()
+    }
 
-        stream.write_all(response.as_bytes())?;
-        stream.flush()?;
+    fn verify(
+        body: &[u8],
+        wallet: &crate::x402::wallet::Wallet,
+        settled: &Mutex<SettledPayments>,
+    ) -> Value {
+        let (body, _) = Facilitator::verify(body, wallet, settled, true);
+        serde_json::from_str(&body).unwrap()
+    }
 
-        Ok(())
+    #[test]
+    fn settled_payments_are_rejected_as_replays() {
+        let facilitator = wallet("01");
+        let sender = wallet("02");
+        let settled = Mutex::new(SettledPayments::default());
+
+        for fee_payer in [None, Some(&facilitator)] {
+            let body = payment_body(&sender, 7, fee_payer);
+            assert_eq!(
+                verify(&body, &facilitator, &settled),
+                json!({ "isValid": true })
+            );
+        }
+
+        let payment = Facilitator::check_payment(&payment_body(&sender, 7, None), &facilitator)
+            .unwrap_or_else(|rejection| panic!("{}", rejection.reason));
+        settled
+            .lock()
+            .unwrap()
+            .insert(payment.replay_key(), unix_now() + 600, unix_now());
+
+        // Sponsoring the same transaction changes its bytes, not its key.
+        for fee_payer in [None, Some(&facilitator)] {
+            let body = payment_body(&sender, 7, fee_payer);
+            assert_eq!(
+                verify(&body, &facilitator, &settled),
+                json!({ "isValid": false, "invalidReason": "replay detected" })
+            );
+        }
+        assert_eq!(
+            verify(&payment_body(&sender, 8, None), &facilitator, &settled),
+            json!({ "isValid": true })
+        );
+    }
+
+    #[test]
+    fn payments_not_signed_by_the_sender_are_rejected() {
+        let facilitator = wallet("01");
+        let sender = wallet("02");
+        let settled = Mutex::new(SettledPayments::default());
+
+        let mut body: Value = serde_json::from_slice(&payment_body(&sender, 7, None)).unwrap();
+        let other = payment_body(&wallet("03"), 7, None);
+        let other: Value = serde_json::from_slice(&other).unwrap();
+        body["paymentPayload"]["payload"]["senderAuthenticator"] =
+            other["paymentPayload"]["payload"]["senderAuthenticator"].clone();
+
+        let response = verify(body.to_string().as_bytes(), &facilitator, &settled);
+        assert_eq!(response["isValid"], json!(false));
+        assert_eq!(response["invalidField"], json!(SENDER_AUTHENTICATOR_FIELD));
+
+        let response = verify(
+            &payment_body(&sender, 7, Some(&wallet("03"))),
+            &facilitator,
+            &settled,
+        );
+        assert_eq!(response["invalidField"], json!(FEE_PAYER_FIELD));
+    }
+
+    fn pid_file(wallet: &str) -> PidFile {
+        PidFile {
+            pid: 1,
+            port: DEFAULT_PORT,
+            host: default_host(),
+            wallet: wallet.to_string(),
+            max_body_size: default_max_body_size(),
+            max_connections: None,
+            program: None,
+            facilitator_bin: None,
+        }
+    }
+
+    #[test]
+    fn restart_prefers_the_given_wallet() {
+        let given = wallet("01");
+        let previous = pid_file(&wallet("02").address);
+
+        let wallet = Facilitator::restart_wallet(Some(given.clone()), Some(&previous)).unwrap();
+        assert_eq!(wallet.address, given.address);
+    }
+
+    #[test]
+    fn restart_refuses_an_unsaved_running_wallet() {
+        let previous = pid_file(&wallet("04").address);
+
+        let error = Facilitator::restart_wallet(None, Some(&previous))
+            .err()
+            .unwrap();
+        assert!(
+            format!("{:#}", error).contains("isn't saved"),
+            "{:#}",
+            error
+        );
     }
 }
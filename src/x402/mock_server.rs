@@ -0,0 +1,245 @@
+use anyhow::{Context, Result};
+use base64::Engine;
+use colored::Colorize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::x402::error::X402Error;
+use crate::x402::facilitator::{
+    Facilitator, HttpRequest, JSON_CONTENT_TYPE, KEEP_ALIVE_TIMEOUT_SECS,
+};
+use crate::x402::test::{
+    self, PaymentRequirements, PAYMENT_REQUIRED_HEADER, PAYMENT_SIGNATURE_HEADER,
+    REQUEST_ID_HEADER, SUPPORTED_X402_VERSIONS,
+};
+
+pub const DEFAULT_PORT: u16 = 3000;
+/// Largest request the mock server reads; it only needs headers.
+const MAX_BODY_SIZE: usize = 64 * 1024;
+
+/// A stand-in x402 API: every path answers 402 with `requirements` until the
+/// request carries a payment proof that matches them.
+pub struct MockServer {
+    pub host: String,
+    pub port: u16,
+    pub requirements: PaymentRequirements,
+}
+
+impl MockServer {
+    /// Serves until Ctrl-C. Each connection gets its own task and may be
+    /// kept alive, like the facilitator's.
+    pub async fn run(self) -> Result<()> {
+        self.requirements.validate()?;
+
+        let listener = match tokio::net::TcpListener::bind((self.host.as_str(), self.port)).await {
+            Ok(listener) => listener,
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
+                return Err(X402Error::PortInUse(self.port).into());
+            }
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to bind to {}:{}", self.host, self.port));
+            }
+        };
+
+        let url = format!("http://{}:{}", self.host, self.port);
+        let amount: u64 = self.requirements.amount.parse().unwrap_or_default();
+        println!(
+            "{}",
+            format!("✓ Mock x402 server listening on {}", url.cyan()).bold()
+        );
+        println!(
+            "  Price:   {} octas ({} APT) in {}",
+            amount,
+            test::format_apt(amount),
+            self.requirements.asset
+        );
+        println!("  Pay to:  {}", self.requirements.pay_to);
+        println!("  Network: {}", self.requirements.network);
+        println!(
+            "{}",
+            format!(
+                "  Try it (with `x402-cli facilitator start` running): x402-cli test payment --api {}/",
+                url
+            )
+            .dimmed()
+        );
+        println!("{}", "  Press Ctrl-C to stop".dimmed());
+
+        let server = Arc::new(self);
+        loop {
+            let stream = tokio::select! {
+                result = tokio::signal::ctrl_c() => {
+                    result.context("Failed to listen for Ctrl-C")?;
+                    break;
+                }
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        eprintln!("Failed to accept connection: {}", e);
+                        continue;
+                    }
+                },
+            };
+
+            let server = server.clone();
+            tokio::spawn(async move {
+                if let Err(e) = server.handle_connection(stream).await {
+                    eprintln!("Error handling connection: {}", e);
+                }
+            });
+        }
+
+        println!();
+        println!("{}", "✓ Mock server stopped".green().bold());
+        Ok(())
+    }
+
+    async fn handle_connection(&self, stream: tokio::net::TcpStream) -> Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = tokio::io::BufReader::new(reader);
+
+        loop {
+            let read = tokio::time::timeout(
+                Duration::from_secs(KEEP_ALIVE_TIMEOUT_SECS),
+                Facilitator::read_request(&mut reader, MAX_BODY_SIZE),
+            )
+            .await;
+            let request = match read {
+                Ok(Ok(Some(request))) => request,
+                // Idle, closed, or unreadable: drop the connection.
+                _ => break,
+            };
+
+            let keep_alive = request.wants_keep_alive();
+            let (status, body, requirements_header) = self.respond(&request);
+
+            let mut headers = Vec::new();
+            if let Some(encoded) = &requirements_header {
+                headers.push((PAYMENT_REQUIRED_HEADER, encoded.as_str()));
+            }
+            if let Some(id) = request.request_id() {
+                headers.push((REQUEST_ID_HEADER, id));
+            }
+            Facilitator::write_response(
+                &mut writer,
+                status,
+                JSON_CONTENT_TYPE,
+                body.to_string().as_bytes(),
+                keep_alive,
+                &headers,
+            )
+            .await?;
+
+            if !keep_alive {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The status line, JSON body, and (for 402s) the base64
+    /// `PAYMENT-REQUIRED` header value for one request. Every outcome is
+    /// logged.
+    fn respond(&self, request: &HttpRequest) -> (&'static str, Value, Option<String>) {
+        let traced = match request.request_id() {
+            Some(id) => format!(" [{}]", id),
+            None => String::new(),
+        };
+        let route = format!("{} {}", request.method, request.path);
+
+        let Some(signature) = request.header(PAYMENT_SIGNATURE_HEADER) else {
+            println!(
+                "{}",
+                format!("  {} → 402 payment required{}", route, traced).dimmed()
+            );
+            return (
+                "402 Payment Required",
+                json!({ "error": "Payment required" }),
+                Some(self.encoded_requirements()),
+            );
+        };
+
+        match self.check_payment(signature) {
+            Ok(transaction) => {
+                println!(
+                    "{}",
+                    format!("  {} → 200 paid ({}){}", route, transaction, traced).green()
+                );
+                (
+                    "200 OK",
+                    json!({
+                        "message": "Payment accepted",
+                        "path": request.path,
+                        "transaction": transaction,
+                        "mock": true,
+                    }),
+                    None,
+                )
+            }
+            Err(reason) => {
+                println!(
+                    "{}",
+                    format!("  {} → 402 rejected: {}{}", route, reason, traced).yellow()
+                );
+                (
+                    "402 Payment Required",
+                    json!({ "error": reason }),
+                    Some(self.encoded_requirements()),
+                )
+            }
+        }
+    }
+
+    fn encoded_requirements(&self) -> String {
+        let requirements = serde_json::to_vec(&self.requirements).unwrap_or_default();
+        base64::engine::general_purpose::STANDARD.encode(requirements)
+    }
+
+    /// Checks a `PAYMENT-SIGNATURE` value: a base64 payment payload with a
+    /// supported `x402Version`, `accepted` requirements matching this
+    /// server's, and a base64 transaction. Returns the transaction's SHA-256
+    /// hash, or why the proof was rejected.
+    fn check_payment(&self, signature: &str) -> std::result::Result<String, String> {
+        let bytes = test::decode_base64_header(signature)
+            .map_err(|e| format!("payment signature is {}", e))?;
+        let payload: Value = serde_json::from_slice(&bytes)
+            .map_err(|_| "payment signature is not a JSON payment payload".to_string())?;
+
+        let version = payload["x402Version"].as_u64().unwrap_or_default();
+        if !SUPPORTED_X402_VERSIONS.contains(&(version as u32)) {
+            return Err(format!("unsupported x402Version {}", version));
+        }
+
+        let accepted: PaymentRequirements = serde_json::from_value(payload["accepted"].clone())
+            .map_err(|_| "payment payload has no valid accepted requirements".to_string())?;
+        let ours = &self.requirements;
+        let fields = [
+            ("scheme", &accepted.scheme, &ours.scheme),
+            ("network", &accepted.network, &ours.network),
+            ("amount", &accepted.amount, &ours.amount),
+            ("asset", &accepted.asset, &ours.asset),
+            ("payTo", &accepted.pay_to, &ours.pay_to),
+        ];
+        for (field, paid, expected) in fields {
+            if paid != expected {
+                return Err(format!("{} is {}, expected {}", field, paid, expected));
+            }
+        }
+
+        let transaction = payload["payload"]["transaction"]
+            .as_str()
+            .ok_or_else(|| "payment payload has no transaction".to_string())?;
+        let transaction = base64::engine::general_purpose::STANDARD
+            .decode(transaction)
+            .map_err(|_| "transaction is not valid base64".to_string())?;
+        if transaction.is_empty() {
+            return Err("transaction is empty".to_string());
+        }
+
+        Ok(format!("0x{}", hex::encode(Sha256::digest(&transaction))))
+    }
+}
@@ -1,16 +1,30 @@
+pub mod chains;
+pub mod config;
 pub mod deploy;
+pub mod doctor;
+pub mod error;
 pub mod facilitator;
+pub mod mock_server;
 pub mod project;
 pub mod test;
+pub mod tx;
+pub mod ui;
+pub mod update;
 pub mod wallet;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
 use colored::Colorize;
+use std::io::Read;
+use std::path::PathBuf;
 
+pub use config::Config;
+pub use error::X402Error;
 pub use facilitator::Facilitator;
-pub use project::Project;
-pub use wallet::Wallet;
+pub use project::{prompt_init, ExistingFilePolicy, InitAnswers, PackageManager, Project};
+pub use test::{test_payment_flow, PaymentFlowReport, PaymentTestOptions};
+pub use ui::OutputFormat;
+pub use wallet::{FaucetOptions, FaucetOutcome, Wallet};
 
 #[derive(Parser)]
 pub enum WalletCommands {
@@ -18,135 +32,1769 @@ pub enum WalletCommands {
     Create {
         #[arg(short, long, default_value = "testnet")]
         network: String,
+        /// Deprecated: use `--format json`
+        #[arg(long, hide = true)]
+        json: bool,
+        /// Include the private key and seed phrase in JSON or --dry-run output
+        #[arg(long)]
+        reveal: bool,
+        /// Return as soon as the faucet accepts the request, without waiting
+        /// for the funds to appear on-chain
+        #[arg(long)]
+        no_wait: bool,
+        /// Skip faucet funding entirely; creation stays local
+        #[arg(long, conflicts_with = "no_wait")]
+        no_fund: bool,
+        /// Retry the faucet this many times (with backoff) when it is
+        /// unreachable, rate-limited, or returns a server error
+        #[arg(long, value_name = "N", default_value_t = wallet::DEFAULT_FAUCET_RETRIES,
+              conflicts_with = "no_fund")]
+        fund_retries: u32,
+        /// Fund the new wallet with an APT transfer from this saved wallet
+        /// (address or alias) instead of the faucet
+        #[arg(long, value_name = "WALLET", conflicts_with_all = ["no_fund", "fund_retries"])]
+        fund_from: Option<String>,
+        /// Octas --fund-from sends to each new wallet
+        #[arg(long, value_name = "OCTAS", default_value_t = wallet::FAUCET_AMOUNT,
+              requires = "fund_from")]
+        amount: u64,
+        /// Directory to write the wallet file to (default: ~/.x402/wallets)
+        #[arg(long, value_name = "DIR")]
+        save_path: Option<PathBuf>,
+        /// Create this many distinct wallets, e.g. for multi-payer tests
+        #[arg(long, value_name = "N", default_value_t = 1,
+              value_parser = clap::value_parser!(u32).range(1..))]
+        count: u32,
+        /// Write wallet files as single-line JSON instead of pretty-printed
+        #[arg(long)]
+        compact: bool,
+        /// Generate and print a throwaway wallet without saving or funding it
+        #[arg(long, conflicts_with_all = ["no_wait", "no_fund", "fund_retries", "fund_from", "save_path", "count", "compact"])]
+        dry_run: bool,
     },
     #[command(name = "import")]
+    #[command(group(
+        clap::ArgGroup::new("source")
+            .required(true)
+            .args(["private_key", "mnemonic", "mnemonic_file"])
+    ))]
     Import {
         #[arg(short, long)]
+        private_key: Option<String>,
+        /// Seed phrase to import; pass `-` to read it from stdin
+        #[arg(short, long)]
+        mnemonic: Option<String>,
+        /// Read the seed phrase from a file
+        #[arg(long, value_name = "PATH")]
+        mnemonic_file: Option<PathBuf>,
+        #[arg(short, long, default_value = "testnet")]
+        network: String,
+        /// Directory to write the wallet file to (default: ~/.x402/wallets)
+        #[arg(long, value_name = "DIR")]
+        save_path: Option<PathBuf>,
+        /// Write the wallet file as single-line JSON instead of pretty-printed
+        #[arg(long)]
+        compact: bool,
+    },
+    /// Import a wallet from a raw Ed25519 private key in hex. The wallet has
+    /// no seed phrase
+    #[command(name = "import-private-key")]
+    ImportPrivateKey {
+        /// Private key hex (with or without `0x`); pass `-` to read it from stdin
         private_key: String,
         #[arg(short, long, default_value = "testnet")]
         network: String,
     },
+    /// List saved wallets, optionally only those on one network or with funds
+    #[command(name = "list")]
+    List {
+        /// Only show wallets on this network (`testnet`, `aptos:testnet`)
+        #[arg(short, long)]
+        network: Option<String>,
+        /// Query each wallet's balance and only show wallets holding funds
+        #[arg(long)]
+        funded: bool,
+        /// Deprecated: use `--format json`
+        #[arg(long, hide = true)]
+        json: bool,
+    },
+    /// Give a saved wallet an alias to use in place of its address
+    #[command(name = "rename")]
+    Rename {
+        /// Address or current alias of a saved wallet
+        address: String,
+        /// New alias for the wallet
+        alias: String,
+    },
+    /// Replace a wallet's keys with a fresh keypair, keeping its alias and network
+    #[command(name = "rotate")]
+    Rotate {
+        /// Address or alias of a saved wallet
+        address: String,
+    },
+    #[command(name = "default")]
+    Default {
+        /// Address or alias of the wallet to use by default (omit to show the current default)
+        address: Option<String>,
+    },
+    #[command(name = "sign")]
+    Sign {
+        /// Address or alias of a saved wallet
+        address: String,
+        /// Message to sign (signed as raw UTF-8 bytes)
+        message: String,
+    },
+    /// Check an Ed25519 signature over a message against a wallet's public key
+    #[command(name = "verify")]
+    Verify {
+        /// Address or alias of a saved wallet, or a raw 32-byte hex public key
+        address: String,
+        /// Hex signature, as printed by `wallet sign`
+        signature: String,
+        /// Message that was signed (checked as raw UTF-8 bytes)
+        message: String,
+    },
 }
 
 #[derive(Parser)]
 pub enum FacilitatorCommands {
     #[command(name = "start")]
     Start {
-        #[arg(short, long, default_value = "3001")]
-        port: u16,
+        /// Port to listen on (default: 3001)
+        #[arg(short, long)]
+        port: Option<u16>,
+        /// Address to bind, e.g. 0.0.0.0 for outside connections
+        /// (default: 127.0.0.1)
+        #[arg(long)]
+        host: Option<String>,
+        /// Address or alias of a saved wallet to use as the facilitator's account
         #[arg(long)]
         wallet: Option<String>,
+        /// Raw private key to use as the facilitator's account (default:
+        /// $X402_FACILITATOR_PRIVATE_KEY)
         #[arg(long)]
         private_key: Option<String>,
-        #[arg(short, long, default_value = "testnet")]
-        network: String,
+        /// Network for --private-key (default: testnet)
+        #[arg(short, long)]
+        network: Option<String>,
+        /// Keep running in this terminal until Ctrl-C, then shut down cleanly
+        #[arg(long)]
+        foreground: bool,
+        /// Run this facilitator binary as a separate, detached process
+        #[arg(long, value_name = "PATH", conflicts_with = "foreground")]
+        facilitator_bin: Option<PathBuf>,
+        /// Largest request body to accept, in bytes; larger requests get 413
+        /// (default: 1048576)
+        #[arg(long, value_name = "BYTES")]
+        max_body_size: Option<usize>,
+        /// Serve at most this many connections at once; more are answered
+        /// 503 and closed (default: unlimited)
+        #[arg(long, value_name = "N")]
+        max_connections: Option<usize>,
+        /// Don't ask for confirmation when the wallet is on mainnet
+        #[arg(short, long)]
+        yes: bool,
+        /// Poll GET /health after starting and only report success once it
+        /// answers 200
+        #[arg(long)]
+        wait: bool,
+        /// Give up on --wait after this many seconds
+        #[arg(long, value_name = "SECS", requires = "wait",
+              default_value_t = facilitator::DEFAULT_HEALTH_WAIT_SECS)]
+        wait_timeout: u64,
     },
     #[command(name = "stop")]
     Stop,
+    #[command(name = "restart")]
+    Restart {
+        /// Port to restart on (default: the running instance's port)
+        #[arg(short, long)]
+        port: Option<u16>,
+        /// Address or alias of a saved wallet to restart with (default: the
+        /// running instance's wallet)
+        #[arg(long)]
+        wallet: Option<String>,
+        /// Poll the new process's GET /health and only report success once
+        /// it answers 200
+        #[arg(long)]
+        wait: bool,
+        /// Give up on --wait after this many seconds
+        #[arg(long, value_name = "SECS", requires = "wait",
+              default_value_t = facilitator::DEFAULT_HEALTH_WAIT_SECS)]
+        wait_timeout: u64,
+    },
+    #[command(name = "metrics")]
+    Metrics {
+        /// Port of the facilitator (default: the running instance's port)
+        #[arg(short, long)]
+        port: Option<u16>,
+        /// Deprecated: use `--format json`
+        #[arg(long, hide = true)]
+        json: bool,
+    },
 }
 
+// Parsed once per invocation, so boxing the large `Payment` variant buys nothing.
+#[allow(clippy::large_enum_variant)]
 #[derive(Parser)]
 pub enum TestCommands {
     #[command(name = "payment")]
     Payment {
         #[arg(short, long)]
         api: String,
-        #[arg(short, long, default_value = "1000")]
+        /// Amount you expect to pay, in octas or with a unit (`1000octas`,
+        /// `0.5APT`); checked against the server's requirements
+        #[arg(long, value_parser = test::parse_amount)]
+        amount: Option<u64>,
+        /// Fail instead of warning when --amount differs from the server's amount
+        #[arg(long)]
+        strict: bool,
+        /// Accept invalid TLS certificates (e.g. self-signed staging certs)
+        #[arg(long)]
+        insecure: bool,
+        /// Deprecated: use `--format json`
+        #[arg(long, hide = true)]
+        json: bool,
+        /// Response header carrying the base64 payment requirements
+        #[arg(long, default_value = test::PAYMENT_REQUIRED_HEADER)]
+        payment_required_header: String,
+        /// Request header used to send the payment proof on retry
+        #[arg(long, default_value = test::PAYMENT_SIGNATURE_HEADER)]
+        payment_signature_header: String,
+        /// Force the x402Version sent in the payment payload
+        #[arg(long)]
+        x402_version: Option<u32>,
+        /// Override the asset from the server's payment requirements
+        #[arg(long)]
+        asset: Option<String>,
+        /// Override the payTo address from the server's payment requirements
+        #[arg(long)]
+        pay_to: Option<String>,
+        /// Override the network from the server's payment requirements
+        #[arg(long)]
+        payment_network: Option<String>,
+        /// Facilitator to verify and settle with: its URL, or `embedded` to
+        /// run one inside this command (default: http://localhost:3001)
+        #[arg(long, value_name = "URL|embedded")]
+        facilitator: Option<String>,
+        /// Poll the facilitator's /health for up to this many seconds before verifying
+        #[arg(long, value_name = "SECS")]
+        wait_for_facilitator: Option<u64>,
+        /// Run the flow this many times and print aggregate stats
+        #[arg(long, default_value = "1", value_parser = clap::value_parser!(u32).range(1..))]
+        repeat: u32,
+        /// Number of runs to execute in parallel with --repeat
+        #[arg(long, default_value = "1", value_parser = clap::value_parser!(u32).range(1..))]
+        concurrency: u32,
+        /// Fail unless the paid request returns this HTTP status
+        #[arg(long, value_name = "CODE")]
+        expect_status: Option<u16>,
+        /// Fail unless the paid response body contains this substring
+        #[arg(long, value_name = "TEXT")]
+        expect_contains: Option<String>,
+        /// Print the paid response body
+        #[arg(short, long)]
+        verbose: bool,
+        /// Maximum gas units for the payment transaction
+        #[arg(long, value_name = "UNITS")]
+        max_gas: Option<u64>,
+        /// Gas unit price in octas (default: the fullnode's estimate)
+        #[arg(long, value_name = "OCTAS")]
+        gas_unit_price: Option<u64>,
+        /// Seconds until the payment transaction expires
+        #[arg(long, value_name = "SECS")]
+        expiration_secs: Option<u64>,
+        /// Retry the paid request immediately even if the 402 had Retry-After
+        #[arg(long)]
+        ignore_retry_after: bool,
+        /// Log HTTP requests and responses (secrets redacted) to stderr
+        #[arg(long)]
+        debug_http: bool,
+        /// Simulate the server and facilitator locally (no network calls)
+        #[arg(long, conflicts_with_all = ["repeat", "concurrency", "facilitator", "wait_for_facilitator", "insecure"])]
+        mock: bool,
+        /// Don't ask for confirmation before paying on mainnet
+        #[arg(short, long)]
+        yes: bool,
+        /// Copy this response header from the 402 onto the paid retry
+        /// (repeatable); cookies are always carried
+        #[arg(long = "carry-header", value_name = "NAME")]
+        carry_headers: Vec<String>,
+        /// Send this header on the initial request and the paid retry, e.g.
+        /// an API key (repeatable)
+        #[arg(long = "header", value_name = "KEY=VALUE", value_parser = test::parse_header)]
+        headers: Vec<(String, String)>,
+        /// HTTP method for the initial request and the paid retry (default:
+        /// GET, or POST with --body)
+        #[arg(short = 'X', long, value_name = "METHOD", value_parser = test::parse_method)]
+        method: Option<reqwest::Method>,
+        /// Request body for both requests: JSON or other text, or @<path> to
+        /// read it from a file
+        #[arg(long, value_name = "BODY")]
+        body: Option<String>,
+        /// Saved wallet (address or alias) paying; picks the server's
+        /// requirement on this wallet's network
+        #[arg(long, value_name = "WALLET")]
+        sign_with: Option<String>,
+        /// Pick the server's requirement on this network (e.g. aptos:testnet
+        /// or testnet), overriding --sign-with's network
+        #[arg(long, value_name = "NETWORK")]
+        prefer_network: Option<String>,
+        /// Write the payment payload and requirements sent to /verify as
+        /// pretty JSON to this file (`-` for stdout)
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["repeat", "concurrency", "mock"])]
+        dump_payload: Option<PathBuf>,
+        /// Also write the full JSON report to this file (e.g. a CI artifact)
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+    /// Run every scenario in a JSON or TOML file and report pass/fail
+    #[command(name = "batch")]
+    Batch {
+        /// Scenarios file (`.toml` is read as TOML, anything else as JSON)
+        file: PathBuf,
+        /// Accept invalid TLS certificates (e.g. self-signed staging certs)
+        #[arg(long)]
+        insecure: bool,
+        /// Deprecated: use `--format json`
+        #[arg(long, hide = true)]
+        json: bool,
+        /// Don't ask for confirmation before paying on mainnet
+        #[arg(short, long)]
+        yes: bool,
+        /// Stop at the first failing scenario instead of running them all
+        #[arg(long)]
+        fail_fast: bool,
+        /// Write a JUnit XML report of the scenarios to this path
+        #[arg(long, value_name = "PATH")]
+        junit: Option<PathBuf>,
+        /// Also write the per-scenario results, with each full flow report,
+        /// as JSON to this file
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Parser)]
+pub enum ConfigCommands {
+    #[command(name = "validate")]
+    Validate {
+        #[arg(default_value = config::DEFAULT_CONFIG_PATH)]
+        path: PathBuf,
+    },
+}
+
+#[derive(Parser)]
+pub enum ProjectCommands {
+    /// Summarize a scaffolded project from its config/x402.toml
+    #[command(name = "info")]
+    Info {
+        /// Project directory
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+        /// Deprecated: use `--format json`
+        #[arg(long, hide = true)]
+        json: bool,
+    },
+    /// Scaffold a new x402-gated route in an existing project
+    #[command(name = "add-endpoint")]
+    AddEndpoint {
+        /// Route path, e.g. /api/weather
+        #[arg(long)]
+        path: String,
+        /// Price in octas or with a unit (`1000octas`, `0.5APT`)
+        #[arg(long, value_parser = test::parse_amount)]
         amount: u64,
+        /// Asset to charge in
+        #[arg(long, default_value = "0x1::aptos_coin::AptosCoin")]
+        asset: String,
+        /// Recipient address (default: $X402_PAY_TO at runtime)
+        #[arg(long, value_parser = wallet::parse_address)]
+        pay_to: Option<String>,
+        /// Project directory
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+        /// Replace the route's handler if it already exists
+        #[arg(long)]
+        force: bool,
     },
 }
 
-pub async fn init(name: String, chain: String, framework: String) -> Result<()> {
-    println!(
-        "{}",
-        format!("Initializing x402 project: {}", name.cyan()).bold()
-    );
+pub struct InitOptions {
+    pub name: String,
+    pub chain: String,
+    pub framework: String,
+    pub on_existing: ExistingFilePolicy,
+    pub package_manager: PackageManager,
+    pub install: bool,
+    pub output_dir: Option<PathBuf>,
+    /// Remove whatever this run created if a step fails.
+    pub clean: bool,
+}
 
-    let project = Project::new(name.clone(), chain, framework);
+pub async fn init(options: InitOptions) -> Result<()> {
+    let InitOptions {
+        name,
+        chain,
+        framework,
+        on_existing,
+        package_manager,
+        install,
+        output_dir,
+        clean,
+    } = options;
 
-    println!("{}", "  Creating project structure...".dimmed());
-    project.create_directories()?;
+    if chains::registry().chain(&chain).is_none() {
+        return Err(X402Error::InvalidChain(chain).into());
+    }
 
-    println!("{}", "  Creating configuration files...".dimmed());
-    project.create_config_files()?;
+    if !ui::is_json() {
+        println!(
+            "{}",
+            format!("Initializing x402 project: {}", name.cyan()).bold()
+        );
+    }
 
-    println!("{}", "  Installing dependencies...".dimmed());
-    project.install_dependencies()?;
+    let mut project = Project::new(name.clone(), chain, framework);
+    project.on_existing = on_existing;
+    project.package_manager = package_manager;
+    project.install = install;
+    project.output_dir = output_dir.map(|dir| expand_home(&dir));
 
-    project.generate_readme()?;
+    let base_dir = project.base_dir();
+    let created_root = first_missing_ancestor(&base_dir);
+    let scaffold = || -> Result<()> {
+        ui::step(1, "Creating project structure");
+        project.create_directories()?;
 
-    println!(
-        "{}",
-        format!("✓ Project initialized: {}", name.green()).bold()
-    );
+        ui::step(2, "Creating configuration files");
+        project.create_config_files()?;
 
-    println!(
-        "{}",
-        format!("  Project location: {}/", name.cyan()).dimmed()
+        ui::step(3, "Generating source files");
+        project.create_source_files()?;
+
+        ui::step(4, "Installing dependencies");
+        if install {
+            project.install_dependencies()?;
+        } else {
+            ui::info("Skipping dependency installation (--no-install)");
+        }
+
+        ui::step(5, "Generating README");
+        project.generate_readme()
+    };
+    if let Err(e) = scaffold() {
+        clean_up_failed_init(&base_dir, created_root.as_deref(), clean);
+        return Err(e);
+    }
+
+    ui::blank_line();
+    ui::done(&format!("Project initialized: {}", name.green()));
+
+    ui::summary(
+        "Project Summary",
+        &[
+            ("Name", project.name.clone()),
+            (
+                "Location",
+                format!("{}/", project.base_dir().display())
+                    .cyan()
+                    .to_string(),
+            ),
+            ("Chain", project.chain.clone()),
+            ("Framework", project.framework.clone()),
+            (
+                "Package manager",
+                project.package_manager.program().to_string(),
+            ),
+        ],
     );
 
     Ok(())
 }
 
+/// The outermost directory on the way to `path` that doesn't exist yet, i.e.
+/// the one `create_dir_all` would create first. `None` if `path` exists.
+fn first_missing_ancestor(path: &std::path::Path) -> Option<PathBuf> {
+    let absolute = std::env::current_dir().ok()?.join(path);
+    absolute
+        .ancestors()
+        .take_while(|dir| !dir.exists())
+        .last()
+        .map(|dir| dir.to_path_buf())
+}
+
+/// After a failed `init`: with `--clean`, removes the directories this run
+/// created; otherwise points at what was left behind. A project directory
+/// that existed beforehand is never removed.
+fn clean_up_failed_init(
+    base_dir: &std::path::Path,
+    created_root: Option<&std::path::Path>,
+    clean: bool,
+) {
+    match created_root {
+        Some(root) if root.exists() => {
+            if !clean {
+                ui::warn(&format!(
+                    "Partial project left at {} (use --clean to remove it on failure)",
+                    base_dir.display()
+                ));
+            } else if let Err(e) = std::fs::remove_dir_all(root) {
+                ui::warn(&format!(
+                    "Failed to remove partial project at {}: {}",
+                    root.display(),
+                    e
+                ));
+            } else {
+                ui::warn(&format!("Removed partial project at {}", root.display()));
+            }
+        }
+        Some(_) => {}
+        None if clean => ui::warn(&format!(
+            "{} existed before init, so it was left in place; files written before the failure remain",
+            base_dir.display()
+        )),
+        None => ui::warn(&format!(
+            "Files written before the failure remain in {}",
+            base_dir.display()
+        )),
+    }
+}
+
+/// Expands a leading `~` so `--output-dir=~/projects` works even when the
+/// shell doesn't expand it.
+fn expand_home(path: &std::path::Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
 pub async fn handle_wallet(command: WalletCommands) -> Result<()> {
     match command {
-        WalletCommands::Create { network } => {
-            println!("{}", "Creating wallet...".cyan());
+        WalletCommands::Create {
+            network,
+            json: _,
+            reveal,
+            no_wait,
+            no_fund,
+            fund_retries,
+            fund_from,
+            amount,
+            save_path,
+            count,
+            compact,
+            dry_run,
+        } => {
+            let json = ui::is_json();
+            if reveal && !json && !dry_run {
+                anyhow::bail!("--reveal only applies to --format json or --dry-run output");
+            }
+            let network = chains::registry().wallet_network(&network)?;
+            if dry_run {
+                return print_dry_run_wallet(&network, json, reveal).await;
+            }
+            let funding = match fund_from {
+                Some(from) => {
+                    let from = Wallet::load(&from)?;
+                    from.check_key_address()?;
+                    let from_network = chains::registry().wallet_network(&from.network)?;
+                    if from_network != network {
+                        anyhow::bail!(
+                            "Wallet {} is on {}, but the new wallet is on {}",
+                            from.address,
+                            from_network,
+                            network
+                        );
+                    }
+                    Some(Funding::Transfer {
+                        from,
+                        amount,
+                        // The next transfer needs the sequence number this one
+                        // uses up, which the fullnode only reports once it
+                        // commits.
+                        wait: !no_wait || count > 1,
+                    })
+                }
+                None => (!no_fund).then_some(Funding::Faucet(FaucetOptions {
+                    wait: !no_wait,
+                    retries: fund_retries,
+                    quiet: json,
+                })),
+            };
+            if count > 1 {
+                return create_wallets(count, &network, json, reveal, funding, save_path, compact)
+                    .await;
+            }
+
+            if !json {
+                println!("{}", "Creating wallet...".cyan());
+                ui::step(1, "Generating keypair");
+            }
+
+            let mut wallet = Wallet::create(&network).await?;
+            wallet.compact = compact;
 
-            let wallet = Wallet::create(&network).await?;
+            if !json {
+                ui::step(2, "Saving wallet");
+            }
+            let wallet_file = save_wallet(&wallet, save_path.as_deref())?;
+            if !json {
+                print_wallet_saved(&wallet_file);
+                ui::step(3, funding_step(&funding));
+            }
 
-            wallet.save_to_file()?;
+            // The wallet is already saved, so a failed funding is reported
+            // with it rather than in its place.
+            let outcome = match &funding {
+                Some(funding) => {
+                    if !json {
+                        print_funding_wait(funding, &wallet.network);
+                    }
+                    Some(
+                        funding
+                            .fund(&wallet)
+                            .await
+                            .unwrap_or_else(|e| FaucetOutcome::Failed(format!("{:#}", e))),
+                    )
+                }
+                None => None,
+            };
+
+            if json {
+                println!(
+                    "{}",
+                    created_wallet_json(&wallet, &wallet_file, &outcome, reveal)
+                );
+                return unfunded_error(&[outcome], &funding);
+            }
+
+            let status = match &outcome {
+                Some(outcome) => {
+                    print_faucet_outcome(outcome);
+                    faucet_status(outcome)
+                }
+                None => {
+                    ui::info("Skipping faucet funding (--no-fund)");
+                    "skipped (--no-fund)".to_string()
+                }
+            };
+
+            ui::blank_line();
+            ui::done("Wallet created successfully");
+            ui::summary(
+                "Wallet Summary",
+                &[
+                    ("Address", wallet.address.cyan().to_string()),
+                    ("Network", wallet.network.clone()),
+                    ("File", wallet_file.display().to_string()),
+                    ("Funding", status),
+                ],
+            );
+
+            unfunded_error(&[outcome], &funding)
+        }
+        WalletCommands::Import {
+            private_key,
+            mnemonic,
+            mnemonic_file,
+            network,
+            save_path,
+            compact,
+        } => {
+            let network = chains::registry().wallet_network(&network)?;
+            ui::header("Importing wallet...");
+            ui::step(1, "Deriving keys");
+
+            let mut wallet = match (private_key, mnemonic, mnemonic_file) {
+                (Some(private_key), _, _) => Wallet::import(&private_key, &network)?,
+                (_, Some(mnemonic), _) if mnemonic == "-" => {
+                    let mut seed_phrase = zeroize::Zeroizing::new(String::new());
+                    std::io::stdin()
+                        .read_to_string(&mut seed_phrase)
+                        .context("Failed to read seed phrase from stdin")?;
+                    Wallet::import_seed_phrase(&seed_phrase, &network)?
+                }
+                (_, Some(mnemonic), _) => {
+                    ui::warn(
+                        "Seed phrases passed as arguments end up in shell history; prefer `--mnemonic -` or --mnemonic-file",
+                    );
+                    Wallet::import_seed_phrase(&mnemonic, &network)?
+                }
+                (_, _, Some(path)) => {
+                    let seed_phrase = std::fs::read_to_string(&path)
+                        .map(zeroize::Zeroizing::new)
+                        .with_context(|| {
+                            format!("Failed to read seed phrase file: {}", path.display())
+                        })?;
+                    Wallet::import_seed_phrase(&seed_phrase, &network)?
+                }
+                (None, None, None) => {
+                    anyhow::bail!("Pass --private-key, --mnemonic or --mnemonic-file")
+                }
+            };
+            wallet.compact = compact;
+
+            save_imported_wallet(&wallet, save_path.as_deref())
+        }
+        WalletCommands::ImportPrivateKey {
+            private_key,
+            network,
+        } => {
+            let network = chains::registry().wallet_network(&network)?;
+            ui::header("Importing wallet...");
+            ui::step(1, "Deriving keys");
+
+            let wallet = if private_key == "-" {
+                let mut private_key = zeroize::Zeroizing::new(String::new());
+                std::io::stdin()
+                    .read_to_string(&mut private_key)
+                    .context("Failed to read private key from stdin")?;
+                Wallet::import(private_key.trim(), &network)?
+            } else {
+                ui::warn(
+                    "Private keys passed as arguments end up in shell history; prefer `-` to read it from stdin",
+                );
+                Wallet::import(&private_key, &network)?
+            };
+
+            save_imported_wallet(&wallet, None)?;
+            ui::info("This wallet has no seed phrase; back up its private key instead");
+            Ok(())
+        }
+        WalletCommands::List {
+            network,
+            funded,
+            json: _,
+        } => list_wallets(network.as_deref(), funded, ui::is_json()).await,
+        WalletCommands::Rename { address, alias } => {
+            let mut wallet = Wallet::load(&address)?;
+
+            wallet.set_alias(&alias)?;
+            let wallet_file = wallet.save_to_file()?;
+
+            ui::done(&format!(
+                "Wallet {} is now '{}'",
+                wallet.address,
+                alias.cyan()
+            ));
+            ui::summary(
+                "Wallet Summary",
+                &[
+                    ("Address", wallet.address.cyan().to_string()),
+                    ("Alias", alias),
+                    ("File", wallet_file.display().to_string()),
+                ],
+            );
 
-            wallet.fund_from_faucet().await?;
+            Ok(())
+        }
+        WalletCommands::Rotate { address } => {
+            let old_wallet = Wallet::load(&address)?;
+
+            ui::header("Rotating wallet...");
+            ui::step(1, "Generating new keypair");
+            let (wallet, wallet_file, archive) = old_wallet.rotate().await?;
+            print_wallet_saved(&wallet_file);
+            ui::success(&format!("Archived old wallet to {}", archive.display()));
+
+            ui::step(2, "Updating default wallet");
+            let mut user_config = config::UserConfig::load()?;
+            if user_config.default_wallet.as_deref() == Some(old_wallet.address.as_str()) {
+                user_config.default_wallet = Some(wallet.address.clone());
+                user_config.save()?;
+                ui::success("Default wallet now points at the new address");
+            } else {
+                ui::info("Old wallet wasn't the default by address; nothing to update");
+            }
+
+            ui::blank_line();
+            ui::done("Wallet rotated");
+            ui::warn(&format!(
+                "Funds are not moved: transfer any balance from {} to {} manually",
+                old_wallet.address, wallet.address
+            ));
+            ui::summary(
+                "Wallet Summary",
+                &[
+                    ("Old address", old_wallet.address.clone()),
+                    ("New address", wallet.address.cyan().to_string()),
+                    (
+                        "Alias",
+                        wallet.alias.clone().unwrap_or_else(|| "none".to_string()),
+                    ),
+                    ("Network", wallet.network.clone()),
+                    ("Archived to", archive.display().to_string()),
+                ],
+            );
+
+            Ok(())
+        }
+        WalletCommands::Default { address } => {
+            let mut user_config = config::UserConfig::load()?;
+
+            let Some(address) = address else {
+                match user_config.default_wallet {
+                    default_wallet if ui::is_json() => ui::print_json(&serde_json::json!({
+                        "default_wallet": default_wallet,
+                    }))?,
+                    Some(default_wallet) => println!("{}", default_wallet),
+                    None => println!("{}", "No default wallet set".yellow()),
+                }
+                return Ok(());
+            };
+
+            let wallet = Wallet::load(&address)?;
+            user_config.default_wallet = Some(wallet.address.clone());
+            let config_file = user_config.save()?;
 
-            println!(
-                "{}",
-                format!("  Wallet Address: {}", wallet.address.cyan()).dimmed()
+            ui::done(&format!("Default wallet set to {}", wallet.address.cyan()));
+            ui::summary(
+                "Wallet Summary",
+                &[
+                    ("Default wallet", wallet.address.cyan().to_string()),
+                    ("Saved to", config_file.display().to_string()),
+                ],
             );
 
             Ok(())
         }
-        WalletCommands::Import { private_key, network } => {
-            println!("{}", "Importing wallet...".cyan());
+        WalletCommands::Sign { address, message } => {
+            let wallet = Wallet::load(&address)?;
 
-            let wallet = Wallet::import(&private_key, &network)?;
+            let (signature, public_key) = wallet.sign(message.as_bytes())?;
 
-            wallet.save_to_file()?;
+            ui::done("Message signed");
+            ui::summary(
+                "Signature Summary",
+                &[
+                    ("Address", wallet.address.clone()),
+                    (
+                        "Signature",
+                        format!("0x{}", hex::encode(signature.to_bytes()))
+                            .cyan()
+                            .to_string(),
+                    ),
+                    (
+                        "Public Key",
+                        format!("0x{}", hex::encode(public_key.as_bytes()))
+                            .cyan()
+                            .to_string(),
+                    ),
+                ],
+            );
 
-            println!(
-                "{}",
-                format!("  Wallet Address: {}", wallet.address.cyan()).dimmed()
+            Ok(())
+        }
+        WalletCommands::Verify {
+            address,
+            signature,
+            message,
+        } => {
+            // A saved wallet wins; otherwise accept the argument as a public key.
+            let (signer, public_key) = match Wallet::load(&address) {
+                Ok(wallet) => {
+                    let public_key = wallet.verifying_key()?;
+                    (wallet.address, public_key)
+                }
+                Err(e) => match wallet::parse_public_key(&address) {
+                    Ok(public_key) => ("raw public key".to_string(), public_key),
+                    Err(_) => return Err(e),
+                },
+            };
+            let signature = wallet::parse_signature(&signature)?;
+
+            let valid = wallet::verify_signature(&public_key, message.as_bytes(), &signature);
+
+            if valid {
+                ui::done("Signature is valid");
+            } else {
+                ui::warn("Signature does not match this public key and message");
+            }
+            ui::summary(
+                "Verification Summary",
+                &[
+                    ("Signer", signer),
+                    (
+                        "Public Key",
+                        format!("0x{}", hex::encode(public_key.as_bytes()))
+                            .cyan()
+                            .to_string(),
+                    ),
+                    (
+                        "Valid",
+                        if valid {
+                            "yes".green().to_string()
+                        } else {
+                            "no".red().to_string()
+                        },
+                    ),
+                ],
             );
 
+            if !valid {
+                anyhow::bail!("Signature verification failed");
+            }
+
             Ok(())
         }
     }
 }
 
+/// Serves a facilitator from this process for `test payment --facilitator
+/// embedded`, as the default wallet or, with none saved, a throwaway one.
+/// It only answers `/verify` and `/settle`, so the wallet never signs.
+async fn start_embedded_facilitator(json: bool) -> Result<facilitator::EmbeddedFacilitator> {
+    let wallet = match Wallet::find_default() {
+        Ok(wallet) => wallet,
+        Err(_) => Wallet::create("testnet").await?,
+    };
+    let embedded = facilitator::EmbeddedFacilitator::start(wallet).await?;
+    if !json {
+        println!(
+            "{}",
+            format!(
+                "  Facilitator: embedded on {} (wallet {})",
+                embedded.url().cyan(),
+                embedded.wallet().address
+            )
+            .dimmed()
+        );
+    }
+    Ok(embedded)
+}
+
+/// Saves to `save_path` when given, else the keystore. Wallets saved outside
+/// the keystore hold a private key, so warn (on stderr, to keep `--json`
+/// output clean) when git would pick them up.
+fn save_wallet(wallet: &Wallet, save_path: Option<&std::path::Path>) -> Result<PathBuf> {
+    let Some(dir) = save_path else {
+        return wallet.save_to_file();
+    };
+
+    let wallet_file = wallet.save_to_dir(&expand_home(dir))?;
+    if is_tracked_by_git(&wallet_file) {
+        eprintln!(
+            "{}",
+            format!(
+                "  ⚠ {} is inside a git repository and not gitignored; add it to .gitignore to keep the private key out of commits",
+                wallet_file.display()
+            )
+            .yellow()
+        );
+    }
+
+    Ok(wallet_file)
+}
+
+/// True when `path` is inside a git work tree and not ignored. Without git,
+/// or outside a repository, there's nothing to warn about.
+fn is_tracked_by_git(path: &std::path::Path) -> bool {
+    let (Some(dir), Some(file_name)) = (path.parent(), path.file_name()) else {
+        return false;
+    };
+    let dir = if dir.as_os_str().is_empty() {
+        std::path::Path::new(".")
+    } else {
+        dir
+    };
+
+    std::process::Command::new("git")
+        .args(["check-ignore", "-q"])
+        .arg(file_name)
+        .current_dir(dir)
+        .output()
+        .map(|output| output.status.code() == Some(1))
+        .unwrap_or(false)
+}
+
+/// `wallet create --count N`: generates `count` wallets with distinct seed
+/// phrases, saves them, funds each with `funding` (if any), and prints one
+/// table (or a JSON array) covering all of them. Once the faucet has failed
+/// for one wallet, the rest aren't sent to it, and the command fails after
+/// reporting.
+async fn create_wallets(
+    count: u32,
+    network: &str,
+    json: bool,
+    reveal: bool,
+    funding: Option<Funding>,
+    save_path: Option<PathBuf>,
+    compact: bool,
+) -> Result<()> {
+    if !json {
+        println!("{}", format!("Creating {} wallets...", count).cyan());
+        ui::step(1, "Generating and saving keypairs");
+    }
+
+    let mut addresses = std::collections::HashSet::new();
+    let mut created = Vec::with_capacity(count as usize);
+    while created.len() < count as usize {
+        let mut wallet = Wallet::create(network).await?;
+        wallet.compact = compact;
+        // Seed phrases are random, so a repeat is astronomically unlikely;
+        // still, never hand out the same account twice.
+        if !addresses.insert(wallet.address.clone()) {
+            continue;
+        }
+        let wallet_file = save_wallet(&wallet, save_path.as_deref())?;
+        if !json {
+            print_wallet_saved(&wallet_file);
+        }
+        created.push((wallet, wallet_file));
+    }
+
+    if !json {
+        ui::step(2, funding_step(&funding));
+        match &funding {
+            None => ui::info("Skipping faucet funding (--no-fund)"),
+            Some(funding) => print_funding_wait(funding, network),
+        }
+    }
+
+    let mut outcomes = Vec::with_capacity(created.len());
+    let mut faucet_down = false;
+    for (index, (wallet, _)) in created.iter().enumerate() {
+        // The wallets are already saved, so a failed faucet call shouldn't
+        // hide the rest of the batch; it does stop further calls, since the
+        // faucet already exhausted its retries (or the funding wallet is
+        // likely out of funds).
+        let mut tripped = false;
+        let outcome = match &funding {
+            None => None,
+            Some(Funding::Faucet(_)) if faucet_down => Some(FaucetOutcome::Failed(
+                "not attempted, the faucet is unavailable".to_string(),
+            )),
+            Some(Funding::Transfer { .. }) if faucet_down => Some(FaucetOutcome::Failed(
+                "not attempted, an earlier transfer failed".to_string(),
+            )),
+            Some(funding) => Some(match funding.fund(wallet).await {
+                Ok(outcome) => outcome,
+                Err(e) => {
+                    faucet_down = true;
+                    tripped = true;
+                    FaucetOutcome::Failed(format!("{:#}", e))
+                }
+            }),
+        };
+        if !json {
+            if let Some(outcome) = &outcome {
+                print_faucet_outcome(outcome);
+            }
+            let remaining = created.len() - index - 1;
+            if tripped && remaining > 0 {
+                ui::warn(&format!(
+                    "Skipping funding for the remaining {} wallet(s)",
+                    remaining
+                ));
+            }
+        }
+        outcomes.push(outcome);
+    }
+
+    if json {
+        let output: Vec<_> = created
+            .iter()
+            .zip(&outcomes)
+            .map(|((wallet, file), outcome)| created_wallet_json(wallet, file, outcome, reveal))
+            .collect();
+        ui::print_json(&output)?;
+        return unfunded_error(&outcomes, &funding);
+    }
+
+    ui::blank_line();
+    ui::done(&format!("{} wallets created successfully", created.len()));
+    let width = created
+        .iter()
+        .map(|(wallet, _)| wallet.address.len())
+        .max()
+        .unwrap_or(0);
+    println!();
+    println!("{}", "Wallet Summary".cyan().bold());
+    println!(
+        "{}",
+        format!(
+            "  {:>3}  {:<width$}  Funding",
+            "#",
+            "Address",
+            width = width
+        )
+        .dimmed()
+    );
+    for (index, ((wallet, _), outcome)) in created.iter().zip(&outcomes).enumerate() {
+        let funding = match outcome {
+            Some(outcome) => faucet_status(outcome),
+            None => "skipped (--no-fund)".to_string(),
+        };
+        println!(
+            "  {:>3}  {:<width$}  {}",
+            index + 1,
+            wallet.address.cyan(),
+            funding,
+            width = width
+        );
+    }
+    let directory = created
+        .first()
+        .and_then(|(_, file)| file.parent())
+        .map(|dir| dir.display().to_string())
+        .unwrap_or_default();
+    println!();
+    println!("  Network:   {}", network);
+    println!("  Directory: {}", directory);
+
+    unfunded_error(&outcomes, &funding)
+}
+
+/// How `wallet create` funds new wallets.
+enum Funding {
+    Faucet(FaucetOptions),
+    /// `--fund-from`: a transfer of `amount` octas from a saved wallet.
+    Transfer {
+        from: Wallet,
+        amount: u64,
+        wait: bool,
+    },
+}
+
+impl Funding {
+    async fn fund(&self, wallet: &Wallet) -> Result<FaucetOutcome> {
+        match self {
+            Funding::Faucet(options) => wallet.fund_from_faucet(*options).await,
+            Funding::Transfer { from, amount, wait } => {
+                let transaction = from.transfer(&wallet.address, *amount, *wait).await?;
+                Ok(FaucetOutcome::Transferred {
+                    amount: *amount,
+                    transaction,
+                    confirmed: *wait,
+                })
+            }
+        }
+    }
+}
+
+fn funding_step(funding: &Option<Funding>) -> &'static str {
+    match funding {
+        Some(Funding::Transfer { .. }) => "Funding by transfer",
+        _ => "Funding from faucet",
+    }
+}
+
+fn print_funding_wait(funding: &Funding, network: &str) {
+    match funding {
+        Funding::Faucet(options) if options.wait && has_faucet(network) => {
+            ui::info("Waiting for faucet funds to confirm")
+        }
+        Funding::Transfer { from, amount, wait } => ui::info(&format!(
+            "Sending {} APT from {}{}",
+            test::format_apt(*amount),
+            from.address,
+            if *wait {
+                " and waiting for it to commit"
+            } else {
+                ""
+            }
+        )),
+        Funding::Faucet(_) => {}
+    }
+}
+
+/// Fails `wallet create` when any wallet's funding failed; the wallets
+/// themselves were already saved and reported.
+fn unfunded_error(outcomes: &[Option<FaucetOutcome>], funding: &Option<Funding>) -> Result<()> {
+    let failed = outcomes
+        .iter()
+        .filter(|outcome| matches!(outcome, Some(FaucetOutcome::Failed(_))))
+        .count();
+    if failed == 0 {
+        return Ok(());
+    }
+    let reason = format!(
+        "{} of {} wallet(s) could not be funded",
+        failed,
+        outcomes.len()
+    );
+    match funding {
+        Some(Funding::Transfer { from, .. }) => {
+            anyhow::bail!("{} by transfer from {}", reason, from.address)
+        }
+        _ => Err(X402Error::FaucetUnavailable(reason).into()),
+    }
+}
+
+/// `facilitator start --wait` and `restart --wait`: blocks until the
+/// facilitator process just launched answers `/health`, so the next command
+/// is sure to find it responsive.
+async fn wait_for_facilitator_health(
+    host: &str,
+    port: u16,
+    timeout_secs: u64,
+) -> Result<std::time::Duration> {
+    ui::info("Waiting for /health");
+    let elapsed =
+        Facilitator::wait_until_healthy(host, port, std::time::Duration::from_secs(timeout_secs))
+            .await
+            .context(
+                "Facilitator process started but is not healthy; see ~/.x402/facilitator.log",
+            )?;
+    ui::success(&format!("Healthy (responded in {}ms)", elapsed.as_millis()));
+    Ok(elapsed)
+}
+
+/// Ends `facilitator start` and `restart` with the headline and the
+/// summary table, or one JSON object with `--format json`. `log` is where a
+/// detached process writes; `health` is how fast `--wait` saw it answer.
+fn print_facilitator_summary(
+    headline: &str,
+    pid: u32,
+    url: &str,
+    wallet: &str,
+    log: Option<std::path::PathBuf>,
+    health: Option<std::time::Duration>,
+) {
+    let mut rows = vec![
+        ("PID", pid.to_string()),
+        ("URL", url.cyan().to_string()),
+        ("Wallet", wallet.cyan().to_string()),
+    ];
+    if let Some(log) = log {
+        rows.push(("Logs", log.display().to_string()));
+    }
+    if let Some(elapsed) = health {
+        rows.push(("Health", format!("healthy ({}ms)", elapsed.as_millis())));
+    }
+
+    ui::blank_line();
+    ui::done(headline);
+    ui::summary("Facilitator Summary", &rows);
+}
+
+/// `wallet list`: saved wallets sorted by address, filtered to `network`
+/// and, with `funded`, to those with a non-zero balance. Wallets whose
+/// balance can't be queried are reported on stderr and left out.
+async fn list_wallets(network: Option<&str>, funded: bool, json: bool) -> Result<()> {
+    let registry = chains::registry();
+    let filter = match network {
+        Some(network) => {
+            let (chain, network, _) = registry.require(network)?;
+            Some((chain, network))
+        }
+        None => None,
+    };
+
+    let mut wallets: Vec<Wallet> = Wallet::load_all()?
+        .into_iter()
+        .filter(|wallet| match filter {
+            Some(filter) => registry
+                .resolve(&wallet.network)
+                .is_some_and(|(chain, network, _)| (chain, network) == filter),
+            None => true,
+        })
+        .collect();
+    wallets.sort_by(|a, b| a.address.cmp(&b.address));
+
+    let mut listed: Vec<(Wallet, Option<u64>)> = Vec::with_capacity(wallets.len());
+    if funded {
+        let client = reqwest::Client::new();
+        // Query every balance at once, then collect them in address order.
+        let queries: Vec<_> = wallets
+            .into_iter()
+            .map(|wallet| {
+                let client = client.clone();
+                tokio::spawn(async move {
+                    let balance = wallet.balance(&client).await;
+                    (wallet, balance)
+                })
+            })
+            .collect();
+        for query in queries {
+            let (wallet, balance) = query.await.context("Balance query task failed")?;
+            match balance {
+                Ok(0) => {}
+                Ok(balance) => listed.push((wallet, Some(balance))),
+                Err(e) => eprintln!(
+                    "{}",
+                    format!("  ⚠ Skipping {}: {:#}", wallet.address, e).yellow()
+                ),
+            }
+        }
+    } else {
+        listed.extend(wallets.into_iter().map(|wallet| (wallet, None)));
+    }
+
+    let default_wallet = config::UserConfig::load()?.default_wallet;
+    let is_default = |wallet: &Wallet| default_wallet.as_deref() == Some(wallet.address.as_str());
+
+    if json {
+        let output: Vec<_> = listed
+            .iter()
+            .map(|(wallet, balance)| {
+                let mut entry = serde_json::json!({
+                    "address": wallet.address,
+                    "alias": wallet.alias,
+                    "network": wallet.network,
+                    "default": is_default(wallet),
+                });
+                if let Some(balance) = balance {
+                    entry["balance"] = serde_json::json!(balance);
+                }
+                entry
+            })
+            .collect();
+        return ui::print_json(&output);
+    }
+
+    if listed.is_empty() {
+        let message = match (network, funded) {
+            (None, false) => {
+                "No saved wallets. Create one with `x402-cli wallet create`".to_string()
+            }
+            (Some(network), false) => format!("No saved wallets on {}", network),
+            (None, true) => "No saved wallets hold funds".to_string(),
+            (Some(network), true) => format!("No saved wallets on {} hold funds", network),
+        };
+        println!("{}", message.yellow());
+        return Ok(());
+    }
+
+    let address_width = listed
+        .iter()
+        .map(|(w, _)| w.address.len())
+        .max()
+        .unwrap_or(0);
+    let alias_width = listed
+        .iter()
+        .map(|(w, _)| w.alias.as_deref().unwrap_or("-").len())
+        .max()
+        .unwrap_or(0)
+        .max("Alias".len());
+    let network_width = listed
+        .iter()
+        .map(|(w, _)| w.network.len())
+        .max()
+        .unwrap_or(0)
+        .max("Network".len());
+    println!(
+        "{}",
+        format!(
+            "    {:<address_width$}  {:<alias_width$}  {:<network_width$}{}",
+            "Address",
+            "Alias",
+            "Network",
+            if funded { "  Balance (APT)" } else { "" },
+        )
+        .dimmed()
+    );
+    for (wallet, balance) in &listed {
+        let marker = if is_default(wallet) { "*" } else { " " };
+        let balance = balance
+            .map(|octas| format!("  {}", test::format_apt(octas)))
+            .unwrap_or_default();
+        println!(
+            "  {} {}  {:<alias_width$}  {:<network_width$}{}",
+            marker.green().bold(),
+            format!("{:<address_width$}", wallet.address).cyan(),
+            wallet.alias.as_deref().unwrap_or("-"),
+            wallet.network,
+            balance,
+        );
+    }
+    let legend = if listed.iter().any(|(wallet, _)| is_default(wallet)) {
+        "; * marks the default"
+    } else {
+        ""
+    };
+    println!();
+    println!(
+        "{}",
+        format!("  {} wallet(s){}", listed.len(), legend).dimmed()
+    );
+
+    Ok(())
+}
+
+/// `wallet create --dry-run`: generates a wallet and prints it, touching
+/// neither `~/.x402/wallets` nor the faucet.
+async fn print_dry_run_wallet(network: &str, json: bool, reveal: bool) -> Result<()> {
+    if !json {
+        println!("{}", "Creating wallet (dry run)...".cyan());
+        ui::step(1, "Generating keypair");
+    }
+
+    let wallet = Wallet::create(network).await?;
+    let public_key = format!("0x{}", hex::encode(wallet.verifying_key()?.as_bytes()));
+
+    if json {
+        let mut output = serde_json::json!({
+            "address": wallet.address,
+            "network": wallet.network,
+            "public_key": public_key,
+            "dry_run": true,
+        });
+        if reveal {
+            output["private_key"] = serde_json::json!(wallet.private_key());
+            output["seed_phrase"] = serde_json::json!(wallet.seed_phrase());
+        }
+        return ui::print_json(&output);
+    }
+
+    ui::info("Dry run: not saving the wallet or funding it");
+    ui::blank_line();
+    ui::done("Wallet generated");
+
+    let mut pairs = vec![
+        ("Address", wallet.address.cyan().to_string()),
+        ("Network", wallet.network.clone()),
+        ("Public Key", public_key),
+    ];
+    if reveal {
+        pairs.push(("Private Key", wallet.private_key().to_string()));
+        pairs.push(("Seed Phrase", wallet.seed_phrase().to_string()));
+    }
+    ui::summary("Wallet Summary", &pairs);
+
+    if !reveal {
+        ui::info("Pass --reveal to print the private key and seed phrase; nothing else keeps them");
+    }
+
+    Ok(())
+}
+
+/// The `--json` object `wallet create` prints for one wallet.
+fn created_wallet_json(
+    wallet: &Wallet,
+    wallet_file: &std::path::Path,
+    outcome: &Option<FaucetOutcome>,
+    reveal: bool,
+) -> serde_json::Value {
+    let mut output = serde_json::json!({
+        "address": wallet.address,
+        "network": wallet.network,
+        "file": wallet_file.display().to_string(),
+        "funded": matches!(
+            outcome,
+            Some(FaucetOutcome::Funded { .. } | FaucetOutcome::Transferred { .. })
+        ),
+    });
+    if let Some(FaucetOutcome::Funded {
+        balance: Some(balance),
+    }) = outcome
+    {
+        output["balance"] = serde_json::json!(balance);
+    }
+    if let Some(FaucetOutcome::Failed(reason)) = outcome {
+        output["funding_error"] = serde_json::json!(reason);
+    }
+    if let Some(FaucetOutcome::Transferred {
+        amount,
+        transaction,
+        confirmed,
+    }) = outcome
+    {
+        output["amount"] = serde_json::json!(amount);
+        output["transaction"] = serde_json::json!(transaction);
+        output["confirmed"] = serde_json::json!(confirmed);
+    }
+    if reveal {
+        output["private_key"] = serde_json::json!(wallet.private_key());
+        output["seed_phrase"] = serde_json::json!(wallet.seed_phrase());
+    }
+    output
+}
+
+/// Saves a wallet `wallet import` or `wallet import-private-key` derived and
+/// prints its summary.
+fn save_imported_wallet(wallet: &Wallet, save_path: Option<&std::path::Path>) -> Result<()> {
+    ui::step(2, "Saving wallet");
+    let wallet_file = save_wallet(wallet, save_path)?;
+    print_wallet_saved(&wallet_file);
+
+    ui::blank_line();
+    ui::done("Wallet imported successfully");
+    ui::summary(
+        "Wallet Summary",
+        &[
+            ("Address", wallet.address.cyan().to_string()),
+            ("Network", wallet.network.clone()),
+            ("File", wallet_file.display().to_string()),
+        ],
+    );
+
+    Ok(())
+}
+
+fn print_wallet_saved(wallet_file: &std::path::Path) {
+    ui::success(&format!("Wallet saved to {}", wallet_file.display()));
+}
+
+fn print_faucet_outcome(outcome: &FaucetOutcome) {
+    match outcome {
+        FaucetOutcome::Funded { balance: None } => {
+            ui::success("Funded with 1 APT from faucet");
+        }
+        FaucetOutcome::Funded {
+            balance: Some(balance),
+        } => {
+            ui::success(&format!(
+                "Funded with 1 APT from faucet (confirmed balance: {} APT)",
+                *balance as f64 / 100_000_000.0
+            ));
+        }
+        FaucetOutcome::Unconfirmed => {
+            ui::warn("Faucet accepted the request, but the funds are not visible on-chain yet");
+        }
+        FaucetOutcome::Skipped => {
+            ui::info("Skipping faucet funding (the network has no faucet)");
+        }
+        FaucetOutcome::Failed(reason) => {
+            ui::warn(&format!("Wallet not funded: {}", reason));
+        }
+        FaucetOutcome::Transferred {
+            amount,
+            transaction,
+            confirmed,
+        } => {
+            let committed = if *confirmed {
+                ""
+            } else {
+                ", not yet confirmed"
+            };
+            ui::success(&format!(
+                "Funded with {} APT by transfer ({}{})",
+                test::format_apt(*amount),
+                transaction,
+                committed
+            ));
+        }
+    }
+}
+
+/// Whether wallets on `network` get faucet funds.
+fn has_faucet(network: &str) -> bool {
+    chains::registry().faucet_url(network).is_some()
+}
+
+/// One-word funding result for the wallet summary.
+fn faucet_status(outcome: &FaucetOutcome) -> String {
+    match outcome {
+        FaucetOutcome::Funded { .. } => "funded (1 APT)".green().to_string(),
+        FaucetOutcome::Unconfirmed => "unconfirmed".yellow().to_string(),
+        FaucetOutcome::Skipped => "skipped (no faucet)".to_string(),
+        FaucetOutcome::Failed(_) => "failed".red().to_string(),
+        FaucetOutcome::Transferred { amount, .. } => {
+            format!("transferred ({} APT)", test::format_apt(*amount))
+                .green()
+                .to_string()
+        }
+    }
+}
+
 pub async fn handle_facilitator(command: FacilitatorCommands) -> Result<()> {
     match command {
-        FacilitatorCommands::Start { port, wallet, private_key, network } => {
+        FacilitatorCommands::Start {
+            port,
+            host,
+            wallet,
+            private_key,
+            network,
+            foreground,
+            facilitator_bin,
+            max_body_size,
+            max_connections,
+            yes,
+            wait,
+            wait_timeout,
+        } => {
+            // Flags win over ~/.x402/facilitator.toml and the project's
+            // [facilitator] section.
+            let settings = config::FacilitatorSettings::load()?;
+            let port = port.or(settings.port).unwrap_or(facilitator::DEFAULT_PORT);
+            let host = host
+                .or(settings.host)
+                .unwrap_or_else(|| facilitator::DEFAULT_HOST.to_string());
+            let network = network
+                .or(settings.network)
+                .unwrap_or_else(|| "testnet".to_string());
+            let max_body_size = max_body_size
+                .or(settings.max_body_size)
+                .unwrap_or(facilitator::DEFAULT_MAX_BODY_SIZE);
+            let max_connections = max_connections.or(settings.max_connections);
+            if max_connections == Some(0) {
+                anyhow::bail!("max_connections must be at least 1");
+            }
+            if let Some(url) = settings.fullnode_url {
+                // `--fullnode-url` was applied first and wins; so does the env var.
+                if std::env::var_os(config::X402_FULLNODE_URL_ENV).is_none() {
+                    config::set_fullnode_url(url);
+                }
+            }
+
+            let private_key = private_key.or_else(|| {
+                std::env::var(facilitator::PRIVATE_KEY_ENV)
+                    .ok()
+                    .filter(|key| !key.is_empty())
+            });
             let wallet = if let Some(private_key) = private_key {
+                let network = chains::registry().wallet_network(&network)?;
                 Wallet::import(&private_key, &network)?
-            } else if let Some(wallet_address) = wallet {
-                Wallet::load_from_address(&wallet_address)?
+            } else if let Some(wallet_address) = wallet.or(settings.wallet) {
+                Wallet::load(&wallet_address)?
             } else {
                 Wallet::find_default()?
             };
 
-            let _facilitator = Facilitator::start(port, wallet)?;
+            if wallet.network == "mainnet" {
+                ui::confirm_mainnet(
+                    "Starting a facilitator that settles payments",
+                    &[("Wallet", wallet.address.clone())],
+                    yes,
+                )?;
+            }
+
+            if foreground {
+                let facilitator =
+                    Facilitator::start(port, &host, wallet, max_body_size, max_connections).await?;
+                let health = if wait {
+                    Some(wait_for_facilitator_health(&host, port, wait_timeout).await?)
+                } else {
+                    None
+                };
+                ui::info("Press Ctrl-C to stop");
+                print_facilitator_summary(
+                    "Facilitator started",
+                    std::process::id(),
+                    &facilitator.url,
+                    &facilitator.wallet.address,
+                    None,
+                    health,
+                );
+                return facilitator.run_until_signal().await;
+            }
 
-            println!("{}", "  Start facilitator in background...".dimmed());
-            println!(
-                "{}",
-                "  Run `x402 facilitator stop` to stop".yellow().dimmed()
+            let pid_file = match facilitator_bin {
+                Some(bin) => {
+                    Facilitator::spawn_external(
+                        &bin,
+                        port,
+                        &host,
+                        &wallet,
+                        max_body_size,
+                        max_connections,
+                    )
+                    .await?
+                }
+                None => {
+                    Facilitator::spawn_detached(
+                        port,
+                        &host,
+                        &wallet,
+                        max_body_size,
+                        max_connections,
+                    )
+                    .await?
+                }
+            };
+            let health = if wait {
+                Some(wait_for_facilitator_health(&host, port, wait_timeout).await?)
+            } else {
+                None
+            };
+            ui::info("Run `x402 facilitator stop` to stop");
+            print_facilitator_summary(
+                "Facilitator started",
+                pid_file.pid,
+                &pid_file.url(),
+                &pid_file.wallet,
+                Some(Facilitator::log_file_path()?),
+                health,
             );
 
             Ok(())
         }
         FacilitatorCommands::Stop => {
-            Facilitator::stop()?;
-            println!("{}", "✓ Facilitator stopped".green().bold());
+            let stopped = Facilitator::stop()?;
+
+            ui::blank_line();
+            if stopped.is_some() {
+                ui::done("Facilitator stopped");
+            }
+            ui::summary(
+                "Facilitator Summary",
+                &[
+                    (
+                        "Status",
+                        if stopped.is_some() {
+                            "stopped"
+                        } else {
+                            "not running"
+                        }
+                        .to_string(),
+                    ),
+                    (
+                        "PID",
+                        stopped.map_or_else(|| "-".to_string(), |pid| pid.to_string()),
+                    ),
+                ],
+            );
+            Ok(())
+        }
+        FacilitatorCommands::Restart {
+            port,
+            wallet,
+            wait,
+            wait_timeout,
+        } => {
+            let wallet = wallet.as_deref().map(Wallet::load).transpose()?;
+            let pid_file = Facilitator::restart(port, wallet).await?;
+            let health = if wait {
+                Some(
+                    wait_for_facilitator_health(&pid_file.host, pid_file.port, wait_timeout)
+                        .await?,
+                )
+            } else {
+                None
+            };
+
+            ui::info("Run `x402 facilitator stop` to stop");
+            print_facilitator_summary(
+                "Facilitator restarted",
+                pid_file.pid,
+                &pid_file.url(),
+                &pid_file.wallet,
+                Some(Facilitator::log_file_path()?),
+                health,
+            );
+
+            Ok(())
+        }
+        FacilitatorCommands::Metrics { port, json: _ } => {
+            let metrics = Facilitator::fetch_metrics(port).await?;
+
+            if ui::is_json() {
+                println!("{}", serde_json::to_string_pretty(&metrics)?);
+                return Ok(());
+            }
+
+            ui::summary(
+                "Facilitator Metrics",
+                &[
+                    ("Uptime", format!("{}s", metrics.uptime_secs)),
+                    ("Requests", metrics.requests.to_string()),
+                    ("Verify calls", metrics.verify_calls.to_string()),
+                    ("Settle calls", metrics.settle_calls.to_string()),
+                    (
+                        "Successes",
+                        metrics.successes.to_string().green().to_string(),
+                    ),
+                    ("Failures", metrics.failures.to_string().red().to_string()),
+                    ("Active connections", metrics.active_connections.to_string()),
+                    (
+                        "Rejected connections",
+                        metrics.rejected_connections.to_string(),
+                    ),
+                ],
+            );
+
             Ok(())
         }
     }
@@ -154,22 +1802,457 @@ pub async fn handle_facilitator(command: FacilitatorCommands) -> Result<()> {
 
 pub async fn handle_test(command: TestCommands) -> Result<()> {
     match command {
-        TestCommands::Payment { api, amount } => {
-            println!("{}", "Testing payment flow...".cyan());
-            println!("{}", format!("  API URL: {}", api.cyan()).dimmed());
-            println!("{}", format!("  Amount: {}", amount));
+        TestCommands::Payment {
+            api,
+            amount,
+            strict,
+            insecure,
+            json: _,
+            payment_required_header,
+            payment_signature_header,
+            x402_version,
+            asset,
+            pay_to,
+            payment_network,
+            facilitator,
+            wait_for_facilitator,
+            repeat,
+            concurrency,
+            expect_status,
+            expect_contains,
+            verbose,
+            max_gas,
+            gas_unit_price,
+            expiration_secs,
+            ignore_retry_after,
+            debug_http: _,
+            mock,
+            yes,
+            carry_headers,
+            headers,
+            method,
+            body,
+            sign_with,
+            prefer_network,
+            dump_payload,
+            output,
+        } => {
+            let json = ui::is_json();
+            if json && dump_payload.as_deref() == Some(std::path::Path::new("-")) {
+                anyhow::bail!(
+                    "--dump-payload - can't share stdout with JSON output; give it a file path"
+                );
+            }
+            let sign_with = sign_with.map(|handle| Wallet::load(&handle)).transpose()?;
+            let body = body.as_deref().map(test::RequestBody::load).transpose()?;
+            let method = method.unwrap_or(match body {
+                Some(_) => reqwest::Method::POST,
+                None => reqwest::Method::GET,
+            });
+            if !json {
+                println!("{}", "Testing payment flow...".cyan());
+                println!(
+                    "{}",
+                    format!("  API URL: {} {}", method, api.cyan()).dimmed()
+                );
+                if let Some(amount) = amount {
+                    println!(
+                        "  Amount: {} octas ({} APT)",
+                        amount,
+                        test::format_apt(amount)
+                    );
+                }
+            }
+
+            // Kept alive until the command returns; dropping it stops the
+            // embedded facilitator.
+            let embedded = match facilitator.as_deref() {
+                Some(test::EMBEDDED_FACILITATOR) => Some(start_embedded_facilitator(json).await?),
+                _ => None,
+            };
+            let facilitator_url = match (&embedded, facilitator) {
+                (Some(embedded), _) => embedded.url().to_string(),
+                (None, Some(url)) => url.trim_end_matches('/').to_string(),
+                (None, None) => test::DEFAULT_FACILITATOR_URL.to_string(),
+            };
+
+            let options = test::PaymentTestOptions {
+                api_url: api,
+                facilitator_url,
+                amount,
+                strict,
+                insecure,
+                json,
+                payment_required_header,
+                payment_signature_header,
+                x402_version,
+                asset,
+                pay_to,
+                payment_network,
+                wait_for_facilitator,
+                expect_status,
+                expect_contains,
+                verbose,
+                max_gas,
+                gas_unit_price,
+                expiration_secs,
+                ignore_retry_after,
+                yes,
+                carry_headers,
+                headers,
+                method,
+                body,
+                sign_with,
+                prefer_network,
+                dump_payload,
+            };
+
+            if repeat > 1 || concurrency > 1 {
+                let report = test::soak_payment_flow(options, repeat, concurrency).await?;
+
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                }
+                if let Some(path) = &output {
+                    write_report(path, &report, json)?;
+                }
 
-            test::test_payment_flow(&api, amount).await?;
+                if report.failures > 0 {
+                    return Err(X402Error::PaymentTestFailed(format!(
+                        "{} of {} run(s) failed",
+                        report.failures, repeat
+                    ))
+                    .into());
+                }
+
+                return Ok(());
+            }
+
+            let report = if mock {
+                test::mock_payment_flow(&options).await?
+            } else {
+                test::test_payment_flow(&options).await?
+            };
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            }
+            if let Some(path) = &output {
+                write_report(path, &report, json)?;
+            }
+
+            if !report.is_success() {
+                let reason = report.error.unwrap_or(report.outcome);
+                return Err(X402Error::PaymentTestFailed(reason).into());
+            }
+
+            Ok(())
+        }
+        TestCommands::Batch {
+            file,
+            insecure,
+            json: _,
+            yes,
+            fail_fast,
+            junit,
+            output,
+        } => {
+            let json = ui::is_json();
+            let scenarios = test::load_batch_file(&file)?;
+
+            if !json {
+                println!(
+                    "{}",
+                    format!(
+                        "Running {} scenario(s) from {}...",
+                        scenarios.len(),
+                        file.display()
+                    )
+                    .cyan()
+                );
+            }
+
+            let results =
+                test::batch_payment_flow(scenarios, insecure, json, yes, fail_fast).await?;
+
+            if let Some(path) = &junit {
+                let suite = file
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "batch".to_string());
+                test::write_junit_report(path, &suite, &results)?;
+                if !json {
+                    println!(
+                        "{}",
+                        format!("  JUnit report written to {}", path.display()).dimmed()
+                    );
+                }
+            }
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&results)?);
+            }
+            if let Some(path) = &output {
+                write_report(path, &results, json)?;
+            }
+
+            let failures = results.iter().filter(|result| !result.passed).count();
+            if failures > 0 {
+                return Err(X402Error::PaymentTestFailed(format!(
+                    "{} of {} scenario(s) failed",
+                    failures,
+                    results.len()
+                ))
+                .into());
+            }
 
             Ok(())
         }
     }
 }
 
-pub async fn deploy(provider: String) -> Result<()> {
-    println!("{}", format!("Deploying to {}", provider.cyan()).bold());
+/// Writes a test command's `--output` report and says where it went.
+fn write_report(path: &std::path::Path, report: &impl serde::Serialize, json: bool) -> Result<()> {
+    test::write_json_report(path, report)?;
+    if !json {
+        println!(
+            "{}",
+            format!("  Report written to {}", path.display()).dimmed()
+        );
+    }
+    Ok(())
+}
 
-    deploy::deploy(&provider).await?;
+pub async fn handle_config(command: ConfigCommands) -> Result<()> {
+    match command {
+        ConfigCommands::Validate { path } => {
+            ui::header(&format!("Validating {}...", path.display()));
 
-    Ok(())
+            let source = std::fs::read_to_string(&path).map_err(|e| {
+                anyhow::anyhow!("Failed to read config file {}: {}", path.display(), e)
+            })?;
+            let lines: Vec<&str> = source.lines().collect();
+
+            let problems = Config::validate_source(&source);
+
+            if ui::is_json() {
+                ui::print_json(&serde_json::json!({
+                    "path": path.display().to_string(),
+                    "valid": problems.is_empty(),
+                    "problems": problems,
+                }))?;
+                if problems.is_empty() {
+                    return Ok(());
+                }
+                anyhow::bail!("{} problem(s) found in {}", problems.len(), path.display());
+            }
+
+            if problems.is_empty() {
+                println!("{}", "✓ Configuration is valid".green().bold());
+                return Ok(());
+            }
+
+            for problem in &problems {
+                match problem.line {
+                    Some(line) => {
+                        println!(
+                            "{}",
+                            format!("  ✗ line {}: {}", line, problem.message).red()
+                        );
+                        if let Some(text) = lines.get(line - 1) {
+                            println!("{}", format!("    | {}", text.trim_end()).dimmed());
+                        }
+                    }
+                    None => println!("{}", format!("  ✗ {}", problem.message).red()),
+                }
+            }
+
+            anyhow::bail!("{} problem(s) found in {}", problems.len(), path.display())
+        }
+    }
+}
+
+pub async fn handle_project(command: ProjectCommands) -> Result<()> {
+    match command {
+        ProjectCommands::Info { dir, json: _ } => {
+            let json = ui::is_json();
+            let path = dir.join(config::DEFAULT_CONFIG_PATH);
+            if !path.is_file() {
+                return Err(X402Error::NotAProject(dir.display().to_string()).into());
+            }
+
+            let config = Config::load(&path)?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&config)?);
+                return Ok(());
+            }
+
+            let facilitator = if config.facilitator.enabled {
+                "enabled".green()
+            } else {
+                "disabled".yellow()
+            };
+
+            ui::summary(
+                &config.project_name,
+                &[
+                    ("Config", path.display().to_string()),
+                    ("Chain", config.chain.clone()),
+                    ("Network", config.blockchain.network.clone()),
+                    ("Framework", config.framework.clone()),
+                    ("Version", config.version.clone()),
+                    (
+                        "Server",
+                        format!("{}:{}", config.server.host, config.server.port),
+                    ),
+                    (
+                        "Facilitator",
+                        format!("{} (port {})", facilitator, config.facilitator.port),
+                    ),
+                ],
+            );
+
+            Ok(())
+        }
+        ProjectCommands::AddEndpoint {
+            path,
+            amount,
+            asset,
+            pay_to,
+            dir,
+            force,
+        } => {
+            let config_path = dir.join(config::DEFAULT_CONFIG_PATH);
+            if !config_path.is_file() {
+                return Err(X402Error::NotAProject(dir.display().to_string()).into());
+            }
+            let config = Config::load(&config_path)?;
+
+            // Check the price the same way `test payment` checks a server's.
+            test::PaymentRequirements {
+                scheme: "exact".to_string(),
+                network: config.payment_network(),
+                amount: amount.to_string(),
+                asset: asset.clone(),
+                pay_to: pay_to
+                    .clone()
+                    .unwrap_or_else(|| format!("0x{}", "0".repeat(64))),
+                extra: Default::default(),
+            }
+            .validate()?;
+
+            ui::header(&format!("Adding endpoint {}...", path));
+            let endpoint = project::Endpoint {
+                path,
+                amount,
+                asset,
+                pay_to,
+            };
+            let file = project::add_endpoint(&dir, &config, &endpoint, force)?;
+            ui::success(&format!("Created {}", file.display()));
+
+            ui::summary(
+                "Endpoint Summary",
+                &[
+                    ("Path", endpoint.path.clone()),
+                    ("Framework", config.framework.clone()),
+                    (
+                        "Price",
+                        format!("{} octas ({} APT)", amount, test::format_apt(amount)),
+                    ),
+                    ("Asset", endpoint.asset.clone()),
+                    (
+                        "Pay To",
+                        endpoint
+                            .pay_to
+                            .clone()
+                            .unwrap_or_else(|| "$X402_PAY_TO".to_string()),
+                    ),
+                    ("File", file.display().to_string()),
+                ],
+            );
+
+            Ok(())
+        }
+    }
+}
+
+pub async fn doctor() -> Result<()> {
+    doctor::run().await
+}
+
+pub async fn version_check() -> Result<()> {
+    update::run().await
+}
+
+pub struct MockServerOptions {
+    pub host: String,
+    pub port: u16,
+    pub amount: u64,
+    pub asset: String,
+    pub pay_to: Option<String>,
+    pub network: String,
+}
+
+/// `x402 mock-server`: serves a stand-in paid API for `test payment`. The
+/// payee defaults to the default wallet, else the zero address.
+pub async fn mock_server(options: MockServerOptions) -> Result<()> {
+    let pay_to = match options.pay_to {
+        Some(pay_to) => pay_to,
+        None => Wallet::find_default()
+            .map(|wallet| wallet.address)
+            .unwrap_or_else(|_| format!("0x{}", "0".repeat(64))),
+    };
+
+    mock_server::MockServer {
+        host: options.host,
+        port: options.port,
+        requirements: test::PaymentRequirements {
+            scheme: "exact".to_string(),
+            network: options.network,
+            amount: options.amount.to_string(),
+            asset: options.asset,
+            pay_to,
+            extra: Default::default(),
+        },
+    }
+    .run()
+    .await
+}
+
+pub async fn deploy(
+    provider: String,
+    dry_run: bool,
+    env_file: Option<PathBuf>,
+    build: deploy::BuildOptions,
+    wait: bool,
+) -> Result<()> {
+    let env = match env_file {
+        Some(path) => deploy::load_env_file(&path)?,
+        None => Vec::new(),
+    };
+
+    if !ui::is_json() {
+        let suffix = if dry_run { " (dry run)" } else { "" };
+        println!(
+            "{}",
+            format!("Deploying to {}{}", provider.cyan(), suffix).bold()
+        );
+    }
+
+    let result = deploy::deploy(&provider, dry_run, env, build, wait).await?;
+    if matches!(
+        result.status,
+        deploy::DeployStatus::DryRun | deploy::DeployStatus::Unsupported
+    ) {
+        return Ok(());
+    }
+
+    if ui::is_json() {
+        ui::print_json(&result)?;
+    } else {
+        result.print_summary();
+    }
+    result.check()
 }
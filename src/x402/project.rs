@@ -1,16 +1,365 @@
 use anyhow::{Context, Result};
-use colored::Colorize;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::{Confirm, Input, Select};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+use crate::x402::config;
+use crate::x402::error::X402Error;
+use crate::x402::ui;
+
+/// What `init` does with generated files that already exist.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExistingFilePolicy {
+    /// Keep the existing file untouched.
+    #[default]
+    Skip,
+    /// Move the existing file to `<name>.bak` and write a fresh one.
+    Backup,
+    /// Replace the existing file.
+    Overwrite,
+}
+
+/// Node package manager used to initialize and run generated projects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum PackageManager {
+    #[default]
+    Npm,
+    Pnpm,
+    Yarn,
+    Bun,
+}
+
+impl PackageManager {
+    pub fn program(&self) -> &'static str {
+        match self {
+            PackageManager::Npm => "npm",
+            PackageManager::Pnpm => "pnpm",
+            PackageManager::Yarn => "yarn",
+            PackageManager::Bun => "bun",
+        }
+    }
+
+    /// Arguments that create a `package.json` non-interactively.
+    pub fn init_args(&self) -> &'static [&'static str] {
+        match self {
+            PackageManager::Pnpm => &["init"],
+            PackageManager::Npm | PackageManager::Yarn | PackageManager::Bun => &["init", "-y"],
+        }
+    }
+
+    pub fn install_command(&self) -> String {
+        match self {
+            PackageManager::Yarn => "yarn".to_string(),
+            _ => format!("{} install", self.program()),
+        }
+    }
+
+    pub fn run_command(&self, script: &str) -> String {
+        match self {
+            PackageManager::Npm | PackageManager::Bun => {
+                format!("{} run {}", self.program(), script)
+            }
+            PackageManager::Pnpm | PackageManager::Yarn => format!("{} {}", self.program(), script),
+        }
+    }
+}
+
+/// Frameworks `project add-endpoint` can add routes to.
+pub const ENDPOINT_FRAMEWORKS: &[&str] = &["next", "vanilla"];
+
+/// Variable the generated vanilla server loads `src/routes` through; its
+/// absence means the server predates `project add-endpoint`.
+const VANILLA_ROUTES_MARKER: &str = "routesDir";
+
+/// A paid route added to an existing project with `project add-endpoint`.
+pub struct Endpoint {
+    /// URL path, e.g. `/api/weather`.
+    pub path: String,
+    pub amount: u64,
+    pub asset: String,
+    /// Recipient address; the generated code falls back to `X402_PAY_TO`
+    /// when unset.
+    pub pay_to: Option<String>,
+}
+
+impl Endpoint {
+    /// Checks that the path is `/`-separated segments of letters, digits,
+    /// `-`, `_`, and `.` (no `..`), so it maps safely onto a file path.
+    fn validate_path(&self) -> Result<()> {
+        let segments: Vec<&str> = match self.path.strip_prefix('/') {
+            Some(rest) => rest.split('/').collect(),
+            None => anyhow::bail!("Endpoint path '{}' must start with /", self.path),
+        };
+
+        let valid = segments.iter().all(|segment| {
+            !segment.is_empty()
+                && *segment != "."
+                && *segment != ".."
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        });
+        if !valid {
+            anyhow::bail!(
+                "Endpoint path '{}' must be non-empty segments of letters, digits, '-', '_', or '.' (e.g. /api/weather)",
+                self.path
+            );
+        }
+
+        Ok(())
+    }
+
+    /// The `paymentRequirements` object literal for the generated handler.
+    fn requirements_js(&self, network: &str, indent: &str) -> String {
+        let pay_to = match &self.pay_to {
+            Some(pay_to) => format!("\"{}\"", pay_to),
+            None => format!("process.env.X402_PAY_TO || \"0x{}\"", "0".repeat(64)),
+        };
+        [
+            "{".to_string(),
+            format!("{}  scheme: \"exact\",", indent),
+            format!("{}  network: \"{}\",", indent, network),
+            format!("{}  amount: \"{}\",", indent, self.amount),
+            format!("{}  asset: \"{}\",", indent, self.asset),
+            format!("{}  payTo: {},", indent, pay_to),
+            format!("{}}}", indent),
+        ]
+        .join("\n")
+    }
+}
+
+/// Writes a handler for `endpoint` into the project at `dir`, laid out for
+/// the framework in its `config/x402.toml`: a Next.js route handler at
+/// `src/app/<path>/route.js`, or a `src/routes/<name>.js` module that the
+/// vanilla server loads. Refuses to replace an existing handler unless
+/// `force`. Returns the file written.
+pub fn add_endpoint(
+    dir: &Path,
+    config: &config::Config,
+    endpoint: &Endpoint,
+    force: bool,
+) -> Result<PathBuf> {
+    endpoint.validate_path()?;
+
+    let network = config.payment_network();
+    let framework = config.framework.to_lowercase();
+    let (file, content) = match framework.as_str() {
+        "next" | "nextjs" => {
+            let file = dir
+                .join("src")
+                .join("app")
+                .join(endpoint.path.trim_start_matches('/'))
+                .join("route.js");
+            let content = format!(
+                r#"// x402 paid route for {path} - added by `x402-cli project add-endpoint`.
+const paymentRequirements = {requirements};
+
+export async function GET(request) {{
+  if (!request.headers.get("payment-signature")) {{
+    const encoded = Buffer.from(JSON.stringify(paymentRequirements)).toString("base64");
+    return Response.json(
+      {{ error: "Payment required" }},
+      {{ status: 402, headers: {{ "PAYMENT-REQUIRED": encoded }} }}
+    );
+  }}
+
+  return Response.json({{ message: "Payment accepted", path: "{path}" }});
+}}
+"#,
+                path = endpoint.path,
+                requirements = endpoint.requirements_js(&network, "")
+            );
+            (file, content)
+        }
+        "vanilla" => {
+            let name = endpoint.path.trim_start_matches('/').replace('/', "-");
+            let file = dir.join("src").join("routes").join(format!("{}.js", name));
+            let content = format!(
+                r#"// x402 paid route for {path} - added by `x402-cli project add-endpoint`.
+// src/server.js loads every module in src/routes.
+module.exports = {{
+  path: "{path}",
+  paymentRequirements: {requirements},
+  handle(req, res) {{
+    res.writeHead(200, {{ "Content-Type": "application/json" }});
+    res.end(JSON.stringify({{ message: "Payment accepted", path: "{path}" }}));
+  }},
+}};
+"#,
+                path = endpoint.path,
+                requirements = endpoint.requirements_js(&network, "  ")
+            );
+            (file, content)
+        }
+        _ => anyhow::bail!(
+            "Can't add endpoints to a '{}' project (supported frameworks: {})",
+            config.framework,
+            ENDPOINT_FRAMEWORKS.join(", ")
+        ),
+    };
+
+    if file.exists() && !force {
+        anyhow::bail!(
+            "{} already exists (use --force to replace it)",
+            file.display()
+        );
+    }
+
+    let parent = file
+        .parent()
+        .context("Handler path has no parent directory")?;
+    fs::create_dir_all(parent)
+        .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    fs::write(&file, content).with_context(|| format!("Failed to create {}", file.display()))?;
+
+    if framework == "vanilla" {
+        let server = dir.join("src").join("server.js");
+        let loads_routes = fs::read_to_string(&server)
+            .map(|source| source.contains(VANILLA_ROUTES_MARKER))
+            .unwrap_or(false);
+        if !loads_routes {
+            ui::warn(&format!(
+                "{} doesn't load src/routes; regenerate it with `x402-cli init --backup` (same name and framework) or require the route yourself",
+                server.display()
+            ));
+        }
+    }
+
+    Ok(file)
+}
+
+/// Frameworks offered by `init --interactive`.
+pub const FRAMEWORKS: &[&str] = &["next", "react", "vanilla"];
+
+/// Project settings gathered by [`prompt_init`].
+#[derive(Debug, Clone)]
+pub struct InitAnswers {
+    pub name: String,
+    pub chain: String,
+    pub framework: String,
+}
+
+/// Fills in whatever `init` wasn't given. With `interactive`, or when any
+/// value is missing, asks for each one (flag values become the defaults) and
+/// confirms before scaffolding; `None` means the user declined. Without a
+/// terminal, missing values are an error so scripts fail fast.
+pub fn prompt_init(
+    name: Option<String>,
+    chain: Option<String>,
+    framework: Option<String>,
+    interactive: bool,
+) -> Result<Option<InitAnswers>> {
+    if !interactive {
+        if let (Some(name), Some(chain), Some(framework)) = (&name, &chain, &framework) {
+            return Ok(Some(InitAnswers {
+                name: name.clone(),
+                chain: chain.clone(),
+                framework: framework.clone(),
+            }));
+        }
+    }
+
+    if !std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+        let missing: Vec<&str> = [
+            ("--name", name.is_none()),
+            ("--chain", chain.is_none()),
+            ("--framework", framework.is_none()),
+        ]
+        .iter()
+        .filter(|(_, missing)| *missing)
+        .map(|(flag, _)| *flag)
+        .collect();
+
+        if missing.is_empty() {
+            anyhow::bail!("--interactive needs a terminal");
+        }
+        anyhow::bail!(
+            "Missing {} (pass them as flags, or run in a terminal to be prompted)",
+            missing.join(", ")
+        );
+    }
+
+    let theme = ColorfulTheme::default();
+
+    let name: String = Input::with_theme(&theme)
+        .with_prompt("Project name")
+        .with_initial_text(name.unwrap_or_default())
+        .validate_with(|input: &String| -> Result<(), &str> {
+            let input = input.trim();
+            if input.is_empty() {
+                Err("Name can't be empty")
+            } else if input.contains(['/', '\\']) || input == "." || input == ".." {
+                Err("Name must be a single directory name")
+            } else {
+                Ok(())
+            }
+        })
+        .interact_text()?;
+
+    let chains = crate::x402::chains::registry().chain_names();
+    let chain = select(&theme, "Chain", &chains, chain.as_deref())?;
+    let framework = select(&theme, "Framework", FRAMEWORKS, framework.as_deref())?;
+
+    let answers = InitAnswers {
+        name: name.trim().to_string(),
+        chain,
+        framework,
+    };
+
+    let confirmed = Confirm::with_theme(&theme)
+        .with_prompt(format!(
+            "Create {} ({}, {})?",
+            answers.name, answers.chain, answers.framework
+        ))
+        .default(true)
+        .interact()?;
+
+    Ok(confirmed.then_some(answers))
+}
+
+/// Picks one of `items`, starting on `current` when it's in the list.
+fn select(
+    theme: &ColorfulTheme,
+    prompt: &str,
+    items: &[&str],
+    current: Option<&str>,
+) -> Result<String> {
+    let default = current
+        .and_then(|current| {
+            items
+                .iter()
+                .position(|item| item.eq_ignore_ascii_case(current))
+        })
+        .unwrap_or(0);
+
+    let index = Select::with_theme(theme)
+        .with_prompt(prompt)
+        .items(items)
+        .default(default)
+        .interact()?;
+
+    Ok(items[index].to_string())
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Project {
     pub name: String,
     pub chain: String,
     pub framework: String,
     pub version: String,
+    #[serde(skip)]
+    pub on_existing: ExistingFilePolicy,
+    #[serde(skip)]
+    pub package_manager: PackageManager,
+    /// Cleared by `init --no-install`; noted in the generated README.
+    #[serde(skip)]
+    pub install: bool,
+    /// Parent directory for the project (default: the current directory).
+    #[serde(skip)]
+    pub output_dir: Option<PathBuf>,
 }
 
 impl Project {
@@ -21,11 +370,52 @@ impl Project {
             chain,
             framework,
             version,
+            on_existing: ExistingFilePolicy::default(),
+            package_manager: PackageManager::default(),
+            install: true,
+            output_dir: None,
+        }
+    }
+
+    /// Directory the project is generated into: `<output_dir>/<name>`.
+    pub fn base_dir(&self) -> PathBuf {
+        match &self.output_dir {
+            Some(output_dir) => output_dir.join(&self.name),
+            None => PathBuf::from(&self.name),
+        }
+    }
+
+    /// Writes a generated file, honoring `on_existing` if it is already there.
+    fn write_generated(&self, path: &Path, content: &str) -> Result<()> {
+        if path.exists() {
+            match self.on_existing {
+                ExistingFilePolicy::Skip => {
+                    ui::info(&format!(
+                        "Keeping existing {} (use --force or --backup to replace)",
+                        path.display()
+                    ));
+                    return Ok(());
+                }
+                ExistingFilePolicy::Backup => {
+                    let mut backup = path.as_os_str().to_owned();
+                    backup.push(".bak");
+                    fs::rename(path, &backup)
+                        .with_context(|| format!("Failed to back up {}", path.display()))?;
+                    ui::info(&format!(
+                        "Backed up {} to {}.bak",
+                        path.display(),
+                        path.display()
+                    ));
+                }
+                ExistingFilePolicy::Overwrite => {}
+            }
         }
+
+        fs::write(path, content).with_context(|| format!("Failed to create {}", path.display()))
     }
 
     pub fn create_directories(&self) -> Result<()> {
-        let base_dir = PathBuf::from(&self.name);
+        let base_dir = self.base_dir();
 
         let dirs = vec![
             base_dir.join("src"),
@@ -39,15 +429,12 @@ impl Project {
                 .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
         }
 
-        println!(
-            "{}",
-            format!("  ✓ Created directories for {}", self.name.green()).dimmed()
-        );
+        ui::success(&format!("Created directories for {}", self.name));
         Ok(())
     }
 
     pub fn create_config_files(&self) -> Result<()> {
-        let base_dir = PathBuf::from(&self.name);
+        let base_dir = self.base_dir();
         let config_dir = base_dir.join("config");
 
         let config_content = format!(
@@ -63,16 +450,22 @@ host = "localhost"
 
 [blockchain]
 network = "{}"
+# Point at a private or self-hosted node (default: the network's public fullnode)
+# fullnode_url = "http://127.0.0.1:8080/v1"
 
 [facilitator]
 enabled = true
 port = 3001
+# Defaults for `x402-cli facilitator start` (flags override them)
+# host = "127.0.0.1"
+# wallet = "my-wallet"
+# max_body_size = 1048576
+# max_connections = 256
 "#,
             self.name, self.chain, self.framework, self.version, self.chain
         );
 
-        fs::write(config_dir.join("x402.toml"), config_content)
-            .with_context(|| format!("Failed to create config file"))?;
+        self.write_generated(&config_dir.join("x402.toml"), &config_content)?;
 
         let env_content = format!(
             r#"# x402 Environment Variables
@@ -83,8 +476,7 @@ X402_PROJECT={}
             self.chain, self.name
         );
 
-        fs::write(base_dir.join(".env.example"), env_content)
-            .with_context(|| format!("Failed to create .env.example"))?;
+        self.write_generated(&base_dir.join(".env.example"), &env_content)?;
 
         let gitignore_content = r#"# Dependencies
 node_modules/
@@ -105,53 +497,165 @@ npm-debug.log*
 dist/
 build/"#;
 
-        fs::write(base_dir.join(".gitignore"), gitignore_content)
-            .with_context(|| format!("Failed to create .gitignore"))?;
+        self.write_generated(&base_dir.join(".gitignore"), gitignore_content)?;
+
+        ui::success("Created configuration files");
+        Ok(())
+    }
+
+    /// Generates framework source files that don't need a package manager.
+    pub fn create_source_files(&self) -> Result<()> {
+        if self.framework.to_lowercase() == "vanilla" {
+            self.create_vanilla_server()?;
+        } else {
+            ui::info(&format!("No sources to generate for {}", self.framework));
+        }
 
-        println!("{}", "  ✓ Created configuration files".dimmed());
         Ok(())
     }
 
     pub fn install_dependencies(&self) -> Result<()> {
         match self.framework.to_lowercase().as_str() {
             "next" | "nextjs" => {
-                let output = Command::new("npm")
-                    .args(["init", "-y"])
-                    .current_dir(&self.name)
-                    .output()
-                    .context("Failed to run npm init")?;
+                let manager = self.package_manager;
+                let init_command =
+                    format!("{} {}", manager.program(), manager.init_args().join(" "));
 
-                if output.status.success() {
-                    println!("{}", "  ✓ Installed Node.js dependencies".dimmed());
-                } else {
+                let output = match Command::new(manager.program())
+                    .args(manager.init_args())
+                    .current_dir(self.base_dir())
+                    .output()
+                {
+                    Ok(output) => output,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                        // A missing package manager shouldn't abort scaffolding.
+                        let error = X402Error::ToolNotFound(manager.program().to_string());
+                        ui::warn(&format!(
+                            "{}. {}, then run `{}` in the project",
+                            error,
+                            error.hint(),
+                            init_command
+                        ));
+                        return Ok(());
+                    }
+                    Err(e) => {
+                        return Err(e).with_context(|| format!("Failed to run {}", init_command))
+                    }
+                };
+
+                if !output.status.success() {
                     let error = String::from_utf8_lossy(&output.stderr);
-                    println!(
-                        "{}",
-                        format!("  ⚠ npm init warning: {}", error.trim())
-                            .yellow()
-                            .dimmed()
+                    anyhow::bail!(
+                        "{} failed ({}): {}",
+                        init_command,
+                        output.status,
+                        error.trim()
                     );
                 }
+
+                ui::success("Installed Node.js dependencies");
+            }
+            "vanilla" => {
+                ui::info("Vanilla server has no dependencies to install");
             }
             "react" => {
-                println!(
-                    "{}",
-                    "  ⚠ React: Run `npm install` after project creation"
-                        .yellow()
-                        .dimmed()
-                );
+                ui::warn(&format!(
+                    "React: Run `{}` after project creation",
+                    self.package_manager.install_command()
+                ));
             }
             _ => {
-                println!(
-                    "{}",
-                    "  ℹ Custom framework: Install dependencies manually".dimmed()
-                );
+                ui::info("Custom framework: Install dependencies manually");
             }
         }
 
         Ok(())
     }
 
+    /// Writes a dependency-free Node server that answers with 402 and a
+    /// `PAYMENT-REQUIRED` header until a `PAYMENT-SIGNATURE` is presented.
+    fn create_vanilla_server(&self) -> Result<()> {
+        let base_dir = self.base_dir();
+
+        let server_content = format!(
+            r#"// x402 vanilla server - no dependencies, uses only Node's `http` module.
+const fs = require("fs");
+const http = require("http");
+const path = require("path");
+
+const PORT = process.env.PORT || 3000;
+
+const paymentRequirements = {{
+  scheme: "exact",
+  network: "{chain}:testnet",
+  amount: process.env.X402_AMOUNT || "1000",
+  asset: process.env.X402_ASSET || "0x1::aptos_coin::AptosCoin",
+  payTo: process.env.X402_PAY_TO || "0x0000000000000000000000000000000000000000000000000000000000000000",
+}};
+
+// Paid routes added with `x402-cli project add-endpoint`, keyed by path.
+const {routes_marker} = path.join(__dirname, "routes");
+const routes = {{}};
+if (fs.existsSync({routes_marker})) {{
+  for (const file of fs.readdirSync({routes_marker}).filter((f) => f.endsWith(".js"))) {{
+    const route = require(path.join({routes_marker}, file));
+    routes[route.path] = route;
+  }}
+}}
+
+const server = http.createServer((req, res) => {{
+  const route = routes[req.url.split("?")[0]];
+  if (!req.headers["payment-signature"]) {{
+    const requirements = route ? route.paymentRequirements : paymentRequirements;
+    const encoded = Buffer.from(JSON.stringify(requirements)).toString("base64");
+    res.writeHead(402, {{
+      "Content-Type": "application/json",
+      "PAYMENT-REQUIRED": encoded,
+    }});
+    res.end(JSON.stringify({{ error: "Payment required" }}));
+    return;
+  }}
+
+  if (route) {{
+    route.handle(req, res);
+    return;
+  }}
+
+  res.writeHead(200, {{ "Content-Type": "application/json" }});
+  res.end(JSON.stringify({{ message: "Payment accepted", project: "{name}" }}));
+}});
+
+server.listen(PORT, () => {{
+  console.log(`{name} listening on http://localhost:${{PORT}}`);
+}});
+"#,
+            chain = self.chain,
+            name = self.name,
+            routes_marker = VANILLA_ROUTES_MARKER
+        );
+
+        self.write_generated(&base_dir.join("src").join("server.js"), &server_content)?;
+
+        let package_content = format!(
+            r#"{{
+  "name": "{}",
+  "version": "{}",
+  "private": true,
+  "scripts": {{
+    "start": "node src/server.js",
+    "dev": "node src/server.js"
+  }}
+}}
+"#,
+            self.name, self.version
+        );
+
+        self.write_generated(&base_dir.join("package.json"), &package_content)?;
+
+        ui::success("Created dependency-free server (src/server.js)");
+        Ok(())
+    }
+
     pub fn generate_readme(&self) -> Result<()> {
         let readme_content = format!(
             r#"# {} - {} Framework
@@ -165,16 +669,16 @@ An x402-enabled API built on {} blockchain.
 - Development facilitator integration
 
 ## Getting Started
-
+{install_note}
 ```bash
 # Install dependencies (if applicable)
-npm install
+{install}
 
 # Copy environment variables
 cp .env.example .env
 
 # Run the development server
-npm run dev
+{dev}
 
 # Start the facilitator
 x402 facilitator start
@@ -207,13 +711,24 @@ x402 deploy --provider vercel
 
 See the `docs/` directory for additional documentation.
 "#,
-            self.name, self.framework, self.chain
+            self.name,
+            self.framework,
+            self.chain,
+            install_note = if self.install {
+                String::new()
+            } else {
+                format!(
+                    "\n> Dependencies were not installed (`x402 init --no-install`). Run `{}` first.\n",
+                    self.package_manager.install_command()
+                )
+            },
+            install = self.package_manager.install_command(),
+            dev = self.package_manager.run_command("dev"),
         );
 
-        fs::write(format!("{}/README.md", self.name), readme_content)
-            .with_context(|| format!("Failed to create README.md"))?;
+        self.write_generated(&self.base_dir().join("README.md"), &readme_content)?;
 
-        println!("{}", "  ✓ Generated README.md".dimmed());
+        ui::success("Generated README.md");
         Ok(())
     }
 }
@@ -4,213 +4,2780 @@ use colored::Colorize;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::time::Instant;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use base64::engine::general_purpose::STANDARD as Engine;
 
-const FACILITATOR_URL: &str = "http://localhost:3001";
+use crate::x402::chains;
+use crate::x402::config;
+use crate::x402::tx::{self, AccountAddress, RawTransaction, TransactionPayload};
+use crate::x402::ui;
+use crate::x402::wallet::{is_valid_aptos_address, is_valid_move_address, Wallet};
+
+pub const DEFAULT_FACILITATOR_URL: &str = "http://localhost:3001";
+/// `--facilitator` value that serves a facilitator from this process for
+/// the length of the test instead of calling a running one.
+pub const EMBEDDED_FACILITATOR: &str = "embedded";
+
+pub const PAYMENT_REQUIRED_HEADER: &str = "PAYMENT-REQUIRED";
+pub const PAYMENT_SIGNATURE_HEADER: &str = "PAYMENT-SIGNATURE";
+pub const X402_VERSION_HEADER: &str = "X402-VERSION";
+/// Sent on every request of a payment flow, and echoed by the facilitator,
+/// so one payment can be traced across client and facilitator logs.
+pub const REQUEST_ID_HEADER: &str = "X-Request-ID";
+
+pub const SUPPORTED_X402_VERSIONS: &[u32] = &[2];
+pub const DEFAULT_X402_VERSION: u32 = 2;
 
 #[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct PaymentPayload {
-    pub x402Version: u32,
+    pub x402_version: u32,
     pub accepted: PaymentRequirements,
     pub payload: Payload,
 }
 
 #[derive(Serialize, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct PaymentRequirements {
     pub scheme: String,
     pub network: String,
     pub amount: String,
     pub asset: String,
-    pub payTo: String,
+    pub pay_to: String,
     #[serde(flatten)]
-    pub extra: Option<Extra>,
+    pub extra: Extra,
+}
+
+pub const KNOWN_SCHEMES: &[&str] = &["exact"];
+
+impl PaymentRequirements {
+    /// Checks that the decoded requirements are usable, reporting every bad
+    /// field at once rather than failing later in the flow.
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+
+        if !KNOWN_SCHEMES.contains(&self.scheme.as_str()) {
+            problems.push(format!(
+                "unknown scheme '{}' (supported: {})",
+                self.scheme,
+                KNOWN_SCHEMES.join(", ")
+            ));
+        }
+
+        let registry = chains::registry();
+        match self.network.split_once(':') {
+            Some((chain, network))
+                if registry.chain(chain).is_some()
+                    && (registry.resolve(&self.network).is_some()
+                        || network.parse::<u64>().is_ok()) => {}
+            _ => problems.push(format!(
+                "unrecognized network '{}' (expected <chain>:<network>, e.g. aptos:testnet)",
+                self.network
+            )),
+        }
+
+        match self.amount.parse::<u64>() {
+            Ok(amount) if amount > 0 => {}
+            _ => problems.push(format!(
+                "amount '{}' is not a positive integer",
+                self.amount
+            )),
+        }
+
+        if !is_valid_aptos_address(&self.pay_to) {
+            problems.push(format!(
+                "payTo '{}' is not a valid account address",
+                self.pay_to
+            ));
+        }
+
+        // Assets are either a fungible asset address or a coin type such as
+        // `0x1::aptos_coin::AptosCoin`.
+        let asset_address = self.asset.split("::").next().unwrap_or_default();
+        let is_coin_type = self.asset.split("::").count() == 3
+            && self.asset.split("::").skip(1).all(|part| !part.is_empty());
+        if !is_valid_move_address(asset_address) || (self.asset.contains("::") && !is_coin_type) {
+            problems.push(format!(
+                "asset '{}' is not a valid asset address or coin type",
+                self.asset
+            ));
+        }
+
+        if !problems.is_empty() {
+            anyhow::bail!("Invalid PaymentRequirements: {}", problems.join("; "));
+        }
+
+        Ok(())
+    }
 }
 
+/// Extension fields carried alongside the core requirements. Unknown fields
+/// are kept in `other` so echoing the requirements back in `accepted` doesn't
+/// drop anything the server sent.
 #[derive(Serialize, Clone, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
 pub struct Extra {
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub sponsored: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_payer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource: Option<String>,
+    #[serde(flatten)]
+    pub other: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct Payload {
     pub transaction: String,
-    pub senderAuthenticator: String,
+    pub sender_authenticator: String,
+    /// Fee payer named by a sponsored transaction. The sender only signs as
+    /// sender; the facilitator adds the fee payer's authenticator when it
+    /// settles. Absent for self-funded payments.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_payer_address: Option<String>,
+}
+
+/// Who pays gas for the payment transaction.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum FeeMode {
+    /// The payer's own account pays gas.
+    SelfFunded,
+    /// `sponsored: true`: the facilitator's `feePayer` account pays gas.
+    Sponsored { fee_payer: String },
+}
+
+impl FeeMode {
+    /// Reads `sponsored` and `feePayer`. A sponsored payment
+    /// needs a fee payer to name in the transaction, so one missing or
+    /// malformed is an error rather than a silent fall back to self-funding.
+    pub fn from_requirements(requirements: &PaymentRequirements) -> Result<Self> {
+        if requirements.extra.sponsored != Some(true) {
+            return Ok(FeeMode::SelfFunded);
+        }
+
+        match requirements.extra.fee_payer.as_deref() {
+            Some(fee_payer) if is_valid_aptos_address(fee_payer) => Ok(FeeMode::Sponsored {
+                fee_payer: fee_payer.to_string(),
+            }),
+            Some(fee_payer) => anyhow::bail!(
+                "Requirements ask for a sponsored transaction, but feePayer '{}' is not a valid account address",
+                fee_payer
+            ),
+            None => anyhow::bail!(
+                "Requirements ask for a sponsored transaction (sponsored: true), but name no feePayer"
+            ),
+        }
+    }
+}
+
+/// Who the payment transaction is from, the key it is signed with, and
+/// which chain it is for.
+struct TransactionContext {
+    signer: ed25519_dalek::SigningKey,
+    sender: AccountAddress,
+    sequence_number: u64,
+    chain_id: u8,
+}
+
+/// Builds the payment payload and its digest, the SHA-256 of the transaction
+/// bytes (not Aptos's on-chain hash, which covers the signed transaction).
+/// The transaction is a BCS `RawTransaction` transferring the required
+/// amount to `payTo`; a sponsored one is wrapped with its fee payer. The
+/// sender signs it (as `RawTransactionWithData` naming the fee payer when
+/// sponsored) and the facilitator submits it when it settles.
+fn build_payload(
+    requirements: &PaymentRequirements,
+    gas: &GasParameters,
+    fee_mode: &FeeMode,
+    context: &TransactionContext,
+) -> Result<(Payload, String)> {
+    let amount: u64 = requirements.amount.parse().with_context(|| {
+        format!(
+            "amount '{}' is not a whole number of octas",
+            requirements.amount
+        )
+    })?;
+    let raw_transaction = RawTransaction {
+        sender: context.sender,
+        sequence_number: context.sequence_number,
+        payload: TransactionPayload::transfer(
+            &requirements.asset,
+            requirements.pay_to.parse()?,
+            amount,
+        )?,
+        max_gas_amount: gas.max_gas,
+        gas_unit_price: gas.gas_unit_price,
+        expiration_timestamp_secs: gas.expiration_timestamp_secs,
+        chain_id: context.chain_id,
+    };
+
+    let (fee_payer, fee_payer_address) = match fee_mode {
+        FeeMode::SelfFunded => (None, None),
+        FeeMode::Sponsored { fee_payer } => (Some(fee_payer.parse()?), Some(fee_payer.clone())),
+    };
+    let transaction_bytes = tx::encode_payment(&raw_transaction, fee_payer)?;
+
+    let transaction_digest = format!("0x{}", hex::encode(Sha256::digest(&transaction_bytes)));
+    let sender_authenticator = tx::sign_payment(&raw_transaction, fee_payer, &context.signer)?;
+    let sender_authenticator_bytes = bcs::to_bytes(&sender_authenticator)
+        .context("Failed to BCS-encode the sender authenticator")?;
+
+    let payload = Payload {
+        transaction: Engine.encode(&transaction_bytes),
+        sender_authenticator: Engine.encode(&sender_authenticator_bytes),
+        fee_payer_address,
+    };
+
+    Ok((payload, transaction_digest))
+}
+
+/// The paying account, `--sign-with` or else the default wallet, its next
+/// sequence number from the network's fullnode, and the network's chain id:
+/// from the chain registry, else the fullnode's ledger info.
+async fn transaction_context(
+    client: &Client,
+    options: &PaymentTestOptions,
+    network: &str,
+) -> Result<TransactionContext> {
+    let wallet = match &options.sign_with {
+        Some(wallet) => wallet.clone(),
+        None => Wallet::find_default()
+            .context("A wallet must sign the payment; pass --sign-with or create one")?,
+    };
+    wallet.check_key_address()?;
+
+    let fullnode = config::fullnode_url(network)
+        .with_context(|| format!("No known fullnode for network '{}'", network))?;
+    let sequence_number = wallet
+        .sequence_number(client, &fullnode)
+        .await
+        .with_context(|| format!("Failed to get the sequence number of {}", wallet.address))?;
+    let chain_id = resolve_chain_id(client, network).await?;
+
+    Ok(TransactionContext {
+        signer: wallet.signing_key()?,
+        sender: wallet.address.parse()?,
+        sequence_number,
+        chain_id,
+    })
+}
+
+/// The network's chain id: from the chain registry, else the fullnode's
+/// ledger info.
+pub async fn resolve_chain_id(client: &Client, network: &str) -> Result<u8> {
+    let chain_id = match chains::registry()
+        .resolve(network)
+        .and_then(|(_, _, config)| config.chain_id)
+    {
+        Some(chain_id) => chain_id,
+        None => fetch_chain_id(client, network).await.with_context(|| {
+            format!(
+                "No chain_id known for network '{}'; set one in the chains file",
+                network
+            )
+        })?,
+    };
+    u8::try_from(chain_id).with_context(|| {
+        format!(
+            "chain_id {} of network '{}' does not fit in a u8",
+            chain_id, network
+        )
+    })
+}
+
+async fn fetch_chain_id(client: &Client, network: &str) -> Result<u64> {
+    #[derive(Deserialize)]
+    struct LedgerInfo {
+        chain_id: u64,
+    }
+
+    let fullnode = config::fullnode_url(network)
+        .with_context(|| format!("No known fullnode for network '{}'", network))?;
+
+    let ledger: LedgerInfo = client
+        .get(&fullnode)
+        .timeout(Duration::from_secs(3))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(ledger.chain_id)
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct VerifyResponse {
-    pub isValid: bool,
-    pub invalidReason: Option<String>,
+    pub is_valid: bool,
+    pub invalid_reason: Option<String>,
+    /// The request field the facilitator blames, e.g.
+    /// `paymentRequirements.payTo`.
+    #[serde(default)]
+    pub invalid_field: Option<String>,
     pub payer: Option<String>,
 }
 
 #[derive(Deserialize)]
 struct SettleResponse {
     pub success: bool,
+    #[serde(rename = "errorReason", default)]
+    pub error_reason: Option<String>,
+    #[serde(default)]
     pub transaction: String,
+    #[serde(default)]
     pub network: String,
-    pub payer: String,
+    #[serde(default)]
+    pub payer: Option<String>,
 }
 
-#[derive(Deserialize)]
-struct ErrorResponse {
+/// Prints flow progress unless the caller asked for machine-readable output.
+macro_rules! progress {
+    ($quiet:expr) => {
+        if !$quiet {
+            println!();
+        }
+    };
+    ($quiet:expr, $($arg:tt)*) => {
+        if !$quiet {
+            println!($($arg)*);
+        }
+    };
+}
+
+pub const DEFAULT_MAX_GAS: u64 = 200_000;
+pub const DEFAULT_GAS_UNIT_PRICE: u64 = 100;
+pub const DEFAULT_EXPIRATION_SECS: u64 = 60;
+
+/// Transaction parameters for the payment transaction, encoded into its
+/// `RawTransaction` and reported.
+#[derive(Serialize, Clone, Debug)]
+pub struct GasParameters {
+    pub max_gas: u64,
+    pub gas_unit_price: u64,
+    /// Where `gas_unit_price` came from: `flag`, `estimate`, or `default`.
+    pub gas_unit_price_source: String,
+    pub expiration_secs: u64,
+    pub expiration_timestamp_secs: u64,
+}
+
+/// Amount the mock server asks for when `--amount` isn't given.
+pub const DEFAULT_AMOUNT: u64 = 1000;
+
+/// Octas per APT.
+pub const OCTAS_PER_APT: u64 = 100_000_000;
+
+/// Parses a `--header KEY=VALUE` into a header name and value, checking both
+/// are valid in an HTTP request.
+pub fn parse_header(value: &str) -> std::result::Result<(String, String), String> {
+    let (name, header_value) = value
+        .split_once('=')
+        .ok_or_else(|| format!("'{}' is not in KEY=VALUE form", value))?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(format!("'{}' has an empty header name", value));
+    }
+    reqwest::header::HeaderName::from_bytes(name.as_bytes())
+        .map_err(|_| format!("'{}' is not a valid header name", name))?;
+    reqwest::header::HeaderValue::from_str(header_value).map_err(|_| {
+        format!(
+            "the value of '{}' contains characters not allowed in a header",
+            name
+        )
+    })?;
+    Ok((name.to_string(), header_value.to_string()))
+}
+
+/// Parses `--method`, e.g. `post` or `PATCH`, into an HTTP method.
+pub fn parse_method(value: &str) -> std::result::Result<reqwest::Method, String> {
+    reqwest::Method::from_bytes(value.trim().to_uppercase().as_bytes())
+        .map_err(|_| format!("'{}' is not a valid HTTP method", value))
+}
+
+/// A request body for the API (`--body`), with the `Content-Type` it is
+/// sent with.
+#[derive(Clone, Debug)]
+pub struct RequestBody {
+    pub bytes: Vec<u8>,
+    pub content_type: &'static str,
+}
+
+impl RequestBody {
+    /// Reads `--body`: the body itself, or `@<path>` to read it from a file.
+    /// JSON is sent as `application/json`, other text as `text/plain`, and
+    /// anything else as `application/octet-stream`.
+    pub fn load(value: &str) -> Result<Self> {
+        let bytes = match value.strip_prefix('@') {
+            Some(path) => std::fs::read(path)
+                .with_context(|| format!("Failed to read request body file: {}", path))?,
+            None => value.as_bytes().to_vec(),
+        };
+
+        let content_type = if serde_json::from_slice::<serde_json::Value>(&bytes).is_ok() {
+            "application/json"
+        } else if std::str::from_utf8(&bytes).is_ok() {
+            "text/plain; charset=utf-8"
+        } else {
+            "application/octet-stream"
+        };
+
+        Ok(RequestBody {
+            bytes,
+            content_type,
+        })
+    }
+}
+
+/// Parses a payment amount into octas. Accepts plain integers (octas),
+/// `<n>octas`, and `<decimal>APT` (up to 8 decimal places), case-insensitive
+/// and with optional whitespace before the unit.
+pub fn parse_amount(value: &str) -> std::result::Result<u64, String> {
+    let value = value.trim();
+    let lower = value.to_lowercase();
+
+    let (number, is_apt) = if let Some(number) = lower.strip_suffix("apt") {
+        (number.trim_end(), true)
+    } else if let Some(number) = lower
+        .strip_suffix("octas")
+        .or_else(|| lower.strip_suffix("octa"))
+    {
+        (number.trim_end(), false)
+    } else {
+        (lower.as_str(), false)
+    };
+
+    if number.is_empty() {
+        return Err(format!(
+            "'{}' has no number (expected e.g. 1000, 1000octas, or 0.5APT)",
+            value
+        ));
+    }
+
+    if !is_apt {
+        return number.parse::<u64>().map_err(|_| {
+            format!(
+                "'{}' is not a whole number of octas (use an APT suffix for decimals, e.g. 0.5APT)",
+                value
+            )
+        });
+    }
+
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    let is_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty()) || !is_digits(whole) || !is_digits(fraction) {
+        return Err(format!("'{}' is not a valid APT amount", value));
+    }
+    if fraction.len() > 8 {
+        return Err(format!(
+            "'{}' has more than 8 decimal places (1 octa = 0.00000001 APT)",
+            value
+        ));
+    }
+
+    let whole: u64 = if whole.is_empty() {
+        0
+    } else {
+        whole
+            .parse()
+            .map_err(|_| format!("'{}' is too large", value))?
+    };
+    let fraction: u64 = format!("{:0<8}", fraction)
+        .parse()
+        .map_err(|_| format!("'{}' is not a valid APT amount", value))?;
+
+    whole
+        .checked_mul(OCTAS_PER_APT)
+        .and_then(|octas| octas.checked_add(fraction))
+        .ok_or_else(|| format!("'{}' is too large", value))
+}
+
+/// Formats octas as an APT amount without trailing zeros, e.g. `0.5`.
+pub fn format_apt(octas: u64) -> String {
+    let whole = octas / OCTAS_PER_APT;
+    let fraction = octas % OCTAS_PER_APT;
+    if fraction == 0 {
+        return whole.to_string();
+    }
+    let fraction = format!("{:08}", fraction);
+    format!("{}.{}", whole, fraction.trim_end_matches('0'))
+}
+
+pub struct PaymentTestOptions {
+    pub api_url: String,
+    pub facilitator_url: String,
+    /// The amount the user expects to pay; compared with the server's.
+    pub amount: Option<u64>,
+    /// Fail on mismatches between the flags and the server's requirements.
+    pub strict: bool,
+    pub insecure: bool,
+    pub json: bool,
+    pub payment_required_header: String,
+    pub payment_signature_header: String,
+    pub x402_version: Option<u32>,
+    pub asset: Option<String>,
+    pub pay_to: Option<String>,
+    pub payment_network: Option<String>,
+    pub wait_for_facilitator: Option<u64>,
+    pub expect_status: Option<u16>,
+    pub expect_contains: Option<String>,
+    pub verbose: bool,
+    pub max_gas: Option<u64>,
+    pub gas_unit_price: Option<u64>,
+    pub expiration_secs: Option<u64>,
+    pub ignore_retry_after: bool,
+    /// Skip the mainnet payment confirmation.
+    pub yes: bool,
+    /// Headers copied from the 402 response onto the paid retry, alongside
+    /// its cookies.
+    pub carry_headers: Vec<String>,
+    /// Extra headers (`--header`), e.g. API keys, sent on the initial request
+    /// and the paid retry.
+    pub headers: Vec<(String, String)>,
+    /// Method of the initial request and the paid retry.
+    pub method: reqwest::Method,
+    /// Body sent with both requests.
+    pub body: Option<RequestBody>,
+    /// The wallet paying; when the server offers several requirements, the
+    /// one on this wallet's network is chosen.
+    pub sign_with: Option<Wallet>,
+    /// Network to choose among the server's requirements, overriding
+    /// `sign_with`'s network.
+    pub prefer_network: Option<String>,
+    /// Where to write the payment payload and requirements sent to
+    /// `/verify`; `-` is stdout.
+    pub dump_payload: Option<PathBuf>,
+}
+
+#[derive(Serialize, Default)]
+pub struct StepTimings {
+    pub initial_ms: Option<u128>,
+    pub verify_ms: Option<u128>,
+    pub settle_ms: Option<u128>,
+    pub final_ms: Option<u128>,
+}
+
+#[derive(Serialize, Default)]
+pub struct PaymentFlowReport {
+    pub api: String,
+    pub outcome: String,
+    /// The flow's `X-Request-ID`.
+    pub request_id: String,
+    pub initial_status: Option<u16>,
+    pub x402_version: Option<u32>,
+    pub requirements: Option<PaymentRequirements>,
+    pub gas: Option<GasParameters>,
+    pub fee_mode: Option<FeeMode>,
+    pub transaction: Option<String>,
+    pub payer: Option<String>,
+    pub network: Option<String>,
+    pub final_status: Option<u16>,
+    /// SHA-256 digest of the transaction bytes in the payment payload.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_transaction: Option<String>,
+    /// The facilitator's `/verify` and `/settle` answers (as JSON when they
+    /// parse, else as text).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verify_response: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settle_response: Option<serde_json::Value>,
+    /// The paid request's response body.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub final_body: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub assertion_failures: Vec<String>,
+    /// Why the flow failed, when `outcome` isn't a success.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// The payload field the facilitator's `/verify` rejected, if it named
+    /// one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invalid_field: Option<String>,
+    /// Suggestions for fixing a payment the facilitator rejected.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hints: Vec<String>,
+    pub elapsed_ms: u128,
+    pub timings: StepTimings,
+}
+
+impl PaymentFlowReport {
+    /// A completed payment, or a server that didn't ask for one, counts as
+    /// success; every other outcome should fail the command.
+    pub fn is_success(&self) -> bool {
+        matches!(self.outcome.as_str(), "paid" | "no_payment_required")
+    }
+}
+
+#[derive(Serialize, Default)]
+pub struct SoakReport {
+    pub api: String,
+    pub runs: u32,
+    pub concurrency: u32,
+    pub successes: u32,
+    pub failures: u32,
+    /// Number of runs per outcome (`paid`, `verify_failed`, `error`, ...).
+    pub outcomes: BTreeMap<String, u32>,
+    pub min_ms: u128,
+    pub median_ms: u128,
+    pub p95_ms: u128,
+    pub max_ms: u128,
+    pub elapsed_ms: u128,
+}
+
+fn build_client(insecure: bool) -> Result<Client> {
+    if insecure {
+        eprintln!(
+            "{}",
+            "  ⚠ WARNING: TLS certificate verification is DISABLED (--insecure)"
+                .yellow()
+                .bold()
+        );
+        eprintln!(
+            "{}",
+            "  ⚠ Only use this against trusted staging endpoints".yellow()
+        );
+    }
+
+    Client::builder()
+        .danger_accept_invalid_certs(insecure)
+        .build()
+        .context("Failed to build HTTP client")
+}
+
+/// Expands a bare network name such as `testnet` (the form wallets store)
+/// to the `aptos:testnet` form used in requirements.
+fn qualify_network(network: &str) -> String {
+    if network.contains(':') {
+        network.to_string()
+    } else {
+        format!("aptos:{}", network)
+    }
+}
+
+/// The network to choose requirements by, and where it came from:
+/// `--prefer-network`, else the `--sign-with` wallet's network.
+fn preferred_network(options: &PaymentTestOptions) -> Option<(String, String)> {
+    if let Some(network) = &options.prefer_network {
+        return Some((qualify_network(network), "--prefer-network".to_string()));
+    }
+    options.sign_with.as_ref().map(|wallet| {
+        let label = wallet
+            .alias
+            .clone()
+            .unwrap_or_else(|| wallet.address.clone());
+        (
+            qualify_network(&wallet.network),
+            format!("wallet {}", label),
+        )
+    })
+}
+
+/// Splits the decoded requirements body into the options the server
+/// accepts: a single requirements object, an array of them, or an
+/// `{ "accepts": [...] }` envelope.
+fn requirement_options(value: serde_json::Value) -> Result<Vec<PaymentRequirements>> {
+    let candidates = match value {
+        serde_json::Value::Array(items) => items,
+        serde_json::Value::Object(mut object) if object.contains_key("accepts") => {
+            match object.remove("accepts") {
+                Some(serde_json::Value::Array(items)) => items,
+                _ => anyhow::bail!("Failed to parse PaymentRequirements: accepts is not an array"),
+            }
+        }
+        other => vec![other],
+    };
+
+    if candidates.is_empty() {
+        anyhow::bail!("Server returned no payment requirements");
+    }
+
+    candidates
+        .into_iter()
+        .map(|candidate| {
+            serde_json::from_value(candidate)
+                .map_err(|e| anyhow::anyhow!("Failed to parse PaymentRequirements: {}", e))
+        })
+        .collect()
+}
+
+/// Picks the requirement to pay. With a preferred network only a
+/// requirement on that network will do, and the error lists what the server
+/// offered; without one the server's first option is used.
+fn select_requirement(
+    mut candidates: Vec<PaymentRequirements>,
+    options: &PaymentTestOptions,
+    quiet: bool,
+) -> Result<PaymentRequirements> {
+    let offered = candidates.len();
+
+    let Some((network, source)) = preferred_network(options) else {
+        if offered > 1 {
+            progress!(
+                quiet,
+                "{}",
+                format!(
+                    "  ℹ Server offers {} payment options; using the first ({}). Pass --sign-with or --prefer-network to choose",
+                    offered, candidates[0].network
+                )
+                .dimmed()
+            );
+        }
+        return Ok(candidates.swap_remove(0));
+    };
+
+    match candidates
+        .iter()
+        .position(|candidate| candidate.network == network)
+    {
+        Some(index) => {
+            if offered > 1 {
+                progress!(
+                    quiet,
+                    "{}",
+                    format!(
+                        "  ℹ Server offers {} payment options; using {} (from {})",
+                        offered, network, source
+                    )
+                    .dimmed()
+                );
+            }
+            Ok(candidates.swap_remove(index))
+        }
+        None => {
+            let listed: Vec<String> = candidates
+                .iter()
+                .map(|candidate| {
+                    format!(
+                        "  - {} on {}: {} {} to {}",
+                        candidate.scheme,
+                        candidate.network,
+                        candidate.amount,
+                        candidate.asset,
+                        candidate.pay_to
+                    )
+                })
+                .collect();
+            anyhow::bail!(
+                "No payment option on {} (from {}). The server accepts:\n{}\nPass --prefer-network <NETWORK> to pick one",
+                network,
+                source,
+                listed.join("\n")
+            )
+        }
+    }
+}
+
+/// Suggestions for a payment `/verify` rejected, from the facilitator's
+/// reason and blamed field and from how this run built the payment.
+fn invalid_payment_hints(
+    reason: &str,
+    field: Option<&str>,
+    requirements: &PaymentRequirements,
+    options: &PaymentTestOptions,
+) -> Vec<String> {
+    let reason = reason.to_lowercase();
+    let field = field.unwrap_or_default();
+    let mut hints = Vec::new();
+
+    if reason.contains("replay") {
+        hints.push(
+            "This transaction was already settled; run the test again to pay with a fresh one"
+                .to_string(),
+        );
+    }
+
+    let mismatched = ["asset", "payTo", "amount", "network"]
+        .into_iter()
+        .find(|name| field.ends_with(name));
+    if let Some(name) = mismatched {
+        let overridden = match name {
+            "asset" => options.asset.is_some(),
+            "payTo" => options.pay_to.is_some(),
+            "network" => options.payment_network.is_some(),
+            _ => false,
+        };
+        if overridden {
+            hints.push(format!(
+                "{} was overridden on the command line; drop the override to pay what the API asked for",
+                name
+            ));
+        } else {
+            hints.push(format!(
+                "The transaction's {} doesn't match the requirements; compare them with --dump-payload <PATH>",
+                name
+            ));
+        }
+    } else if field.ends_with("transaction")
+        || ["transaction", "encoding", "bcs"]
+            .iter()
+            .any(|word| reason.contains(word))
+    {
+        hints.push(format!(
+            "The facilitator couldn't use the transaction; check that it supports x402 {} payments on {}",
+            requirements.scheme, requirements.network
+        ));
+    }
+
+    let registry = chains::registry();
+    let payment_network = registry
+        .resolve(&requirements.network)
+        .map(|(chain, network, _)| (chain, network));
+    match &options.sign_with {
+        Some(wallet)
+            if registry
+                .resolve(&wallet.network)
+                .map(|(chain, network, _)| (chain, network))
+                != payment_network =>
+        {
+            hints.push(format!(
+                "The --sign-with wallet is on {}, but the payment is on {}; use a wallet on the payment's network",
+                wallet.network, requirements.network
+            ));
+        }
+        _ if ["network", "chain", "signature", "sender", "balance", "fund"]
+            .iter()
+            .any(|word| reason.contains(word)) =>
+        {
+            hints.push(format!(
+                "Check that the --sign-with wallet is on the right network ({}) and funded",
+                requirements.network
+            ));
+        }
+        _ => {}
+    }
+
+    if hints.is_empty() {
+        hints.push(
+            "Re-run with --verbose to see the /verify exchange, or --dump-payload <PATH> to inspect what was sent"
+                .to_string(),
+        );
+    }
+    hints
+}
+
+/// Replaces server-provided requirement fields with the user's `--asset`,
+/// `--pay-to`, and `--payment-network` values, e.g. to exercise facilitator
+/// validation with deliberately mismatched requirements.
+fn apply_requirement_overrides(
+    requirements: &mut PaymentRequirements,
+    options: &PaymentTestOptions,
+    quiet: bool,
+) {
+    let overrides = [
+        ("asset", &mut requirements.asset, &options.asset),
+        ("payTo", &mut requirements.pay_to, &options.pay_to),
+        (
+            "network",
+            &mut requirements.network,
+            &options.payment_network,
+        ),
+    ];
+
+    for (field, current, replacement) in overrides {
+        if let Some(replacement) = replacement {
+            progress!(
+                quiet,
+                "{}",
+                format!("  ℹ Overriding {}: {} → {}", field, current, replacement)
+                    .yellow()
+                    .dimmed()
+            );
+            *current = replacement.clone();
+        }
+    }
+}
+
+pub async fn estimate_gas_unit_price(client: &Client, network: &str) -> Result<u64> {
+    #[derive(Deserialize)]
+    struct GasEstimate {
+        gas_estimate: u64,
+    }
+
+    let fullnode = config::fullnode_url(network)
+        .with_context(|| format!("No known fullnode for network '{}'", network))?;
+
+    let estimate: GasEstimate = client
+        .get(format!("{}/estimate_gas_price", fullnode))
+        .timeout(Duration::from_secs(3))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(estimate.gas_estimate)
+}
+
+/// Fills in gas parameters from the flags, asking the fullnode for a gas
+/// price estimate when `--gas-unit-price` isn't given.
+async fn resolve_gas_parameters(
+    client: Option<&Client>,
+    options: &PaymentTestOptions,
+    network: &str,
+    quiet: bool,
+) -> GasParameters {
+    let (gas_unit_price, source) = match (options.gas_unit_price, client) {
+        (Some(price), _) => (price, "flag"),
+        (None, Some(client)) => match estimate_gas_unit_price(client, network).await {
+            Ok(price) => (price, "estimate"),
+            Err(e) => {
+                progress!(
+                    quiet,
+                    "{}",
+                    format!(
+                        "  ℹ Gas price estimate unavailable ({}), using {}",
+                        e, DEFAULT_GAS_UNIT_PRICE
+                    )
+                    .dimmed()
+                );
+                (DEFAULT_GAS_UNIT_PRICE, "default")
+            }
+        },
+        (None, None) => (DEFAULT_GAS_UNIT_PRICE, "default"),
+    };
+
+    let expiration_secs = options.expiration_secs.unwrap_or(DEFAULT_EXPIRATION_SECS);
+
+    GasParameters {
+        max_gas: options.max_gas.unwrap_or(DEFAULT_MAX_GAS),
+        gas_unit_price,
+        gas_unit_price_source: source.to_string(),
+        expiration_secs,
+        expiration_timestamp_secs: chrono::Utc::now().timestamp() as u64 + expiration_secs,
+    }
+}
+
+fn print_gas_parameters(gas: &GasParameters, quiet: bool) {
+    progress!(
+        quiet,
+        "{}",
+        format!(
+            "  Gas: max {} · unit price {} ({}) · expires in {}s",
+            gas.max_gas, gas.gas_unit_price, gas.gas_unit_price_source, gas.expiration_secs
+        )
+        .dimmed()
+    );
+}
+
+fn print_fee_mode(fee_mode: &FeeMode, quiet: bool) {
+    let payer = match fee_mode {
+        FeeMode::SelfFunded => "sender".to_string(),
+        FeeMode::Sponsored { fee_payer } => format!("fee payer {} (sponsored)", fee_payer),
+    };
+    progress!(quiet, "{}", format!("  Gas paid by: {}", payer).dimmed());
+}
+
+/// Writes `--dump-payload`: the `/verify` request body (the payment payload
+/// and its requirements) as pretty JSON, to `path` or to stdout for `-`.
+fn dump_payload(path: &Path, verify_request: &serde_json::Value, quiet: bool) -> Result<()> {
+    let pretty = serde_json::to_string_pretty(verify_request)?;
+    if path == Path::new("-") {
+        println!("{}", pretty);
+        return Ok(());
+    }
+
+    std::fs::write(path, pretty + "\n")
+        .with_context(|| format!("Failed to write payment payload to {}", path.display()))?;
+    progress!(
+        quiet,
+        "{}",
+        format!("  Payment payload written to {}", path.display()).dimmed()
+    );
+    Ok(())
+}
+
+/// A response body for the report: its JSON if it parses, else the text.
+fn body_value(body: &str) -> serde_json::Value {
+    serde_json::from_str(body).unwrap_or_else(|_| serde_json::Value::String(body.to_string()))
+}
+
+/// Log target for `--debug-http` wire logging.
+pub const HTTP_LOG_TARGET: &str = "x402::http";
+
+const MAX_LOGGED_BODY_BYTES: usize = 2048;
+
+/// Header and JSON key fragments whose values are redacted in HTTP logs.
+const SENSITIVE_NAMES: &[&str] = &[
+    "authorization",
+    "cookie",
+    "signature",
+    "authenticator",
+    "private",
+    "secret",
+    "seed",
+    "mnemonic",
+];
+
+/// A fully read response, so the body can be logged and then parsed.
+struct HttpExchange {
+    status: reqwest::StatusCode,
+    headers: reqwest::header::HeaderMap,
+    /// The body decoded as UTF-8 (lossily); `bytes` keeps the original.
+    body: String,
+    bytes: Vec<u8>,
+}
+
+/// Sends a request and reads the whole response, logging both at debug level
+/// on [`HTTP_LOG_TARGET`] with secrets redacted.
+async fn send_logged(
+    request: reqwest::RequestBuilder,
+    options: &PaymentTestOptions,
+) -> Result<HttpExchange> {
+    let log_enabled = log::log_enabled!(target: HTTP_LOG_TARGET, log::Level::Debug);
+
+    if log_enabled {
+        if let Some(built) = request.try_clone().and_then(|r| r.build().ok()) {
+            log::debug!(target: HTTP_LOG_TARGET, "→ {} {}", built.method(), built.url());
+            log_headers(built.headers(), options);
+            if let Some(body) = built.body().and_then(|b| b.as_bytes()) {
+                log::debug!(
+                    target: HTTP_LOG_TARGET,
+                    "  body: {}",
+                    redact_body(&String::from_utf8_lossy(body))
+                );
+            }
+        }
+    }
+
+    let response = request.send().await?;
+    let status = response.status();
+    let headers = response.headers().clone();
+    let bytes = response
+        .bytes()
+        .await
+        .context("Failed to read response body")?
+        .to_vec();
+    let body = String::from_utf8_lossy(&bytes).into_owned();
+
+    if log_enabled {
+        log::debug!(target: HTTP_LOG_TARGET, "← {}", status);
+        log_headers(&headers, options);
+        log::debug!(target: HTTP_LOG_TARGET, "  body: {}", redact_body(&body));
+    }
+
+    Ok(HttpExchange {
+        status,
+        headers,
+        body,
+        bytes,
+    })
 }
 
-pub async fn test_payment_flow(api_url: &str, _amount: u64) -> Result<()> {
-    let client = Client::new();
+/// `--header` values are always redacted, since they usually carry
+/// credentials.
+fn is_sensitive(name: &str, options: &PaymentTestOptions) -> bool {
+    let name = name.to_lowercase();
+    name == options.payment_signature_header.to_lowercase()
+        || SENSITIVE_NAMES
+            .iter()
+            .any(|fragment| name.contains(fragment))
+        || options
+            .headers
+            .iter()
+            .any(|(header, _)| header.eq_ignore_ascii_case(&name))
+}
+
+fn log_headers(headers: &reqwest::header::HeaderMap, options: &PaymentTestOptions) {
+    for (name, value) in headers {
+        let value = value.to_str().unwrap_or("<binary>");
+        let value = if is_sensitive(name.as_str(), options) {
+            redact(value)
+        } else {
+            value.to_string()
+        };
+        log::debug!(target: HTTP_LOG_TARGET, "  {}: {}", name, value);
+    }
+}
+
+/// Keeps a short prefix so values can still be told apart: up to 8
+/// characters, and never more than a quarter of the value, so short
+/// secrets like API keys aren't shown whole.
+fn redact(value: &str) -> String {
+    let length = value.chars().count();
+    let prefix: String = value.chars().take((length / 4).min(8)).collect();
+    format!("{}…[redacted {} chars]", prefix, length)
+}
+
+fn redact_body(body: &str) -> String {
+    let text = match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(mut value) => {
+            redact_json(&mut value);
+            value.to_string()
+        }
+        Err(_) => body.to_string(),
+    };
+
+    if text.len() > MAX_LOGGED_BODY_BYTES {
+        let cut = (0..=MAX_LOGGED_BODY_BYTES)
+            .rev()
+            .find(|i| text.is_char_boundary(*i))
+            .unwrap_or(0);
+        format!("{}…[{} more bytes]", &text[..cut], text.len() - cut)
+    } else {
+        text
+    }
+}
+
+fn redact_json(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let key = key.to_lowercase();
+                if SENSITIVE_NAMES
+                    .iter()
+                    .any(|fragment| key.contains(fragment))
+                {
+                    if let serde_json::Value::String(secret) = value {
+                        *secret = redact(secret);
+                        continue;
+                    }
+                }
+                redact_json(value);
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+/// Checks the facilitator's `/health` endpoint before verifying. With
+/// `wait_secs`, keeps polling until it answers or the wait expires.
+async fn wait_for_facilitator(
+    client: &Client,
+    facilitator_url: &str,
+    wait_secs: Option<u64>,
+    quiet: bool,
+) -> Result<()> {
+    let health_url = format!("{}/health", facilitator_url);
+    let deadline = Instant::now() + Duration::from_secs(wait_secs.unwrap_or(0));
+    let mut announced = false;
+
+    loop {
+        let last_error = match client.get(&health_url).send().await {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => format!("health check returned {}", response.status()),
+            Err(e) => e.to_string(),
+        };
+
+        if Instant::now() >= deadline {
+            if wait_secs.is_some() {
+                anyhow::bail!(
+                    "Facilitator at {} not ready after {}s ({}). Start it with `x402 facilitator start`",
+                    facilitator_url,
+                    wait_secs.unwrap_or(0),
+                    last_error
+                );
+            }
+            anyhow::bail!(
+                "Facilitator at {} is not reachable ({}). Start it with `x402 facilitator start` or pass --wait-for-facilitator <secs>",
+                facilitator_url,
+                last_error
+            );
+        }
+
+        if !announced {
+            progress!(
+                quiet,
+                "{}",
+                format!("  ℹ Waiting for facilitator at {}...", facilitator_url).dimmed()
+            );
+            announced = true;
+        }
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
+/// Compares `--amount` with what the server asks for. The server's amount is
+/// always what gets paid, so a mismatch warns, or fails with `--strict`.
+fn check_expected_amount(
+    requirements: &PaymentRequirements,
+    options: &PaymentTestOptions,
+    quiet: bool,
+) -> Result<()> {
+    let Some(expected) = options.amount else {
+        return Ok(());
+    };
+    let required: u64 = requirements.amount.parse()?;
+    if required == expected {
+        return Ok(());
+    }
+
+    let message = format!(
+        "--amount {} octas ({} APT) differs from the server's required amount {} octas ({} APT)",
+        expected,
+        format_apt(expected),
+        required,
+        format_apt(required)
+    );
+
+    if options.strict {
+        anyhow::bail!("{}", message);
+    }
+
+    progress!(
+        quiet,
+        "{}",
+        format!("  ⚠ {}; paying the server's amount", message).yellow()
+    );
+    Ok(())
+}
+
+/// Longest `Retry-After` delay honored, so a misbehaving server can't stall
+/// the test indefinitely.
+const MAX_RETRY_AFTER_SECS: u64 = 300;
+
+/// Parses a `Retry-After` value: delay seconds or an HTTP-date.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delay = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(delay.to_std().unwrap_or(Duration::ZERO))
+}
+
+/// Formats a response body for the terminal based on its `Content-Type`:
+/// JSON is pretty-printed and colored, text is shown as-is, and anything
+/// else is summarized by size so binary data doesn't garble the terminal.
+fn render_body(headers: &reqwest::header::HeaderMap, bytes: &[u8]) -> String {
+    let content_type = headers
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_lowercase();
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+
+    if mime == "application/json" || mime.ends_with("+json") {
+        if let Ok(value) = serde_json::from_slice::<serde_json::Value>(bytes) {
+            return colorize_json(&value, 0);
+        }
+    }
+
+    let is_text = mime.starts_with("text/")
+        || mime.ends_with("+xml")
+        || matches!(
+            mime,
+            "application/json"
+                | "application/xml"
+                | "application/javascript"
+                | "application/x-www-form-urlencoded"
+        );
+
+    match std::str::from_utf8(bytes) {
+        Ok(text) if is_text || mime.is_empty() => text.to_string(),
+        _ => format!(
+            "<{} bytes of {}>",
+            bytes.len(),
+            if mime.is_empty() { "binary data" } else { mime }
+        )
+        .dimmed()
+        .to_string(),
+    }
+}
+
+/// Pretty-prints JSON with colored keys and values.
+fn colorize_json(value: &serde_json::Value, indent: usize) -> String {
+    use serde_json::Value;
+
+    let pad = "  ".repeat(indent + 1);
+    let close_pad = "  ".repeat(indent);
+
+    match value {
+        Value::Null => "null".magenta().to_string(),
+        Value::Bool(b) => b.to_string().magenta().to_string(),
+        Value::Number(n) => n.to_string().yellow().to_string(),
+        Value::String(_) => value.to_string().green().to_string(),
+        Value::Array(items) if items.is_empty() => "[]".to_string(),
+        Value::Array(items) => {
+            let items: Vec<String> = items
+                .iter()
+                .map(|item| format!("{}{}", pad, colorize_json(item, indent + 1)))
+                .collect();
+            format!("[\n{}\n{}]", items.join(",\n"), close_pad)
+        }
+        Value::Object(fields) if fields.is_empty() => "{}".to_string(),
+        Value::Object(fields) => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|(key, value)| {
+                    format!(
+                        "{}{}: {}",
+                        pad,
+                        Value::String(key.clone()).to_string().cyan(),
+                        colorize_json(value, indent + 1)
+                    )
+                })
+                .collect();
+            format!("{{\n{}\n{}}}", fields.join(",\n"), close_pad)
+        }
+    }
+}
+
+/// Session state from the 402 response to replay on the paid retry, for
+/// APIs that tie the payment and the resource fetch together: every
+/// `Set-Cookie` folded into one `Cookie` header (name=value only, attributes
+/// dropped), plus each `--carry-header` present on the response.
+fn session_headers(
+    headers: &reqwest::header::HeaderMap,
+    carry_headers: &[String],
+) -> Vec<(String, String)> {
+    let mut session = Vec::new();
+
+    let cookies: Vec<&str> = headers
+        .get_all(reqwest::header::SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .filter_map(|cookie| cookie.split(';').next())
+        .map(str::trim)
+        .filter(|pair| pair.contains('='))
+        .collect();
+    if !cookies.is_empty() {
+        session.push(("cookie".to_string(), cookies.join("; ")));
+    }
+
+    for name in carry_headers {
+        for value in headers.get_all(name.as_str()) {
+            if let Ok(value) = value.to_str() {
+                session.push((name.to_lowercase(), value.to_string()));
+            }
+        }
+    }
+
+    session
+}
+
+fn is_mainnet(network: &str) -> bool {
+    matches!(network, "aptos:mainnet" | "aptos:1" | "mainnet")
+}
+
+/// Mainnet payments move real funds, so ask before paying. The answer is
+/// remembered for the rest of the process so `--repeat`, `--concurrency`,
+/// and batch scenarios prompt at most once.
+fn confirm_mainnet_payment(
+    requirements: &PaymentRequirements,
+    options: &PaymentTestOptions,
+) -> Result<()> {
+    static ANSWER: std::sync::Mutex<Option<bool>> = std::sync::Mutex::new(None);
+
+    if !is_mainnet(&requirements.network) {
+        return Ok(());
+    }
+
+    let mut answer = ANSWER.lock().unwrap_or_else(|e| e.into_inner());
+    match *answer {
+        Some(true) => return Ok(()),
+        Some(false) => anyhow::bail!("Aborted; nothing was sent to mainnet"),
+        None => {}
+    }
+
+    let amount = match requirements.amount.parse::<u64>() {
+        Ok(octas) => format!("{} octas ({} APT)", octas, format_apt(octas)),
+        Err(_) => requirements.amount.clone(),
+    };
+
+    let result = ui::confirm_mainnet(
+        "Paying",
+        &[
+            ("API", options.api_url.clone()),
+            ("Pay to", requirements.pay_to.clone()),
+            ("Amount", amount),
+            ("Asset", requirements.asset.clone()),
+        ],
+        options.yes,
+    );
+    *answer = Some(result.is_ok());
+    result
+}
+
+/// Checks the paid response, sets the final outcome, and prints the summary.
+fn finish_payment_flow(
+    report: &mut PaymentFlowReport,
+    options: &PaymentTestOptions,
+    final_response: &HttpExchange,
+    start_time: Instant,
+    quiet: bool,
+) {
+    let final_status = final_response.status;
+    let final_body = final_response.body.as_str();
+
+    // A second 402 means the server didn't accept the proof. The flow never
+    // pays again, so this can't loop, but it is its own kind of failure.
+    let proof_rejected =
+        final_status == reqwest::StatusCode::PAYMENT_REQUIRED && options.expect_status != Some(402);
+    let rejection =
+        proof_rejected.then(|| rejection_reason(final_response, &options.payment_required_header));
+
+    if final_status.is_success() {
+        progress!(quiet, "{}", "  ✓ Received response from API".bold().green());
+    } else if let Some(reason) = &rejection {
+        let message = match reason {
+            Some(reason) => format!("  ✗ Payment proof rejected (402 again): {}", reason),
+            None => "  ✗ Payment proof rejected (402 again)".to_string(),
+        };
+        progress!(quiet, "{}", message.bold().red());
+    } else {
+        progress!(
+            quiet,
+            "{}",
+            format!("  ℹ API returned: {}", final_status)
+                .dimmed()
+                .yellow()
+        );
+    }
+
+    if options.verbose {
+        progress!(quiet, "{}", "  Response body:".dimmed());
+        progress!(
+            quiet,
+            "{}",
+            render_body(&final_response.headers, &final_response.bytes)
+        );
+    }
+
+    report.assertion_failures = check_expectations(options, final_status.as_u16(), final_body);
+    for failure in &report.assertion_failures {
+        progress!(quiet, "  ✗ {}", failure.bold().red());
+    }
+
+    let elapsed = start_time.elapsed();
+    if let Some(reason) = rejection {
+        report.outcome = "payment_rejected".to_string();
+        report.error = Some(match reason {
+            Some(reason) => format!("Payment proof rejected: {}", reason),
+            None => "Payment proof rejected: the paid request returned 402 again".to_string(),
+        });
+    } else if !report.assertion_failures.is_empty() {
+        report.outcome = "assertion_failed".to_string();
+        report.error = Some(report.assertion_failures.join("; "));
+    } else if options.expect_status.is_none() && !final_status.is_success() {
+        // An explicit --expect-status already decided whether this is OK.
+        report.outcome = "final_request_failed".to_string();
+        report.error = Some(format!("Paid request returned {}", final_status));
+    } else {
+        report.outcome = "paid".to_string();
+    }
+    report.elapsed_ms = elapsed.as_millis();
+
+    progress!(quiet);
+    progress!(quiet, "{}", "Payment Flow Complete".cyan().bold());
+    progress!(
+        quiet,
+        "Transaction: {}",
+        report.transaction.as_deref().unwrap_or_default().cyan()
+    );
+    progress!(quiet, "Request ID: {}", report.request_id);
+    if let Some(payer) = &report.payer {
+        progress!(quiet, "Payer: {}", payer.cyan());
+    }
+    progress!(quiet, "Time: {}ms", elapsed.as_millis());
+    progress!(
+        quiet,
+        "{}",
+        format!(
+            "  initial {}ms · verify {}ms · settle {}ms · final {}ms",
+            report.timings.initial_ms.unwrap_or_default(),
+            report.timings.verify_ms.unwrap_or_default(),
+            report.timings.settle_ms.unwrap_or_default(),
+            report.timings.final_ms.unwrap_or_default()
+        )
+        .dimmed()
+    );
+}
+
+/// The server's explanation for answering the paid request with another
+/// 402: an `error`, `message`, `reason`, or `invalidReason` field of a JSON
+/// body (or of the re-sent payment requirements), else a short text body.
+fn rejection_reason(response: &HttpExchange, requirements_header: &str) -> Option<String> {
+    const REASON_FIELDS: &[&str] = &["error", "message", "reason", "invalidReason"];
+    let reason_in = |value: &serde_json::Value| {
+        REASON_FIELDS
+            .iter()
+            .find_map(|field| value.get(field).and_then(|v| v.as_str()))
+            .map(str::to_string)
+    };
+
+    if let Ok(body) = serde_json::from_str::<serde_json::Value>(&response.body) {
+        if let Some(reason) = reason_in(&body) {
+            return Some(reason);
+        }
+    }
+
+    let requirements = response
+        .headers
+        .get(requirements_header)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| decode_base64_header(value).ok())
+        .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok());
+    if let Some(reason) = requirements.as_ref().and_then(reason_in) {
+        return Some(reason);
+    }
+
+    let body = response.body.trim();
+    (!body.is_empty() && body.len() <= 200 && !body.starts_with(['{', '[', '<']))
+        .then(|| body.to_string())
+}
+
+/// Checks the final response against `--expect-status` and
+/// `--expect-contains`, returning a message for each mismatch.
+fn check_expectations(options: &PaymentTestOptions, status: u16, body: &str) -> Vec<String> {
+    let mut failures = Vec::new();
+
+    if let Some(expected) = options.expect_status {
+        if status != expected {
+            failures.push(format!("Expected status {}, got {}", expected, status));
+        }
+    }
+
+    if let Some(expected) = &options.expect_contains {
+        if !body.contains(expected.as_str()) {
+            failures.push(format!("Expected response body to contain {:?}", expected));
+        }
+    }
+
+    failures
+}
+
+/// Decodes a base64 header value, accepting both the standard and URL-safe
+/// alphabets with or without padding since servers differ on which they emit.
+pub(crate) fn decode_base64_header(value: &str) -> Result<Vec<u8>> {
+    let value = value.trim();
+    let standard_error = match Engine.decode(value) {
+        Ok(bytes) => return Ok(bytes),
+        Err(e) => e,
+    };
+
+    BASE64_STANDARD_NO_PAD
+        .decode(value.trim_end_matches('='))
+        .or_else(|_| BASE64_URL_SAFE_NO_PAD.decode(value.trim_end_matches('=')))
+        .map_err(|_| anyhow::anyhow!("not valid standard or URL-safe base64 ({})", standard_error))
+}
+
+/// A random (version 4) UUID identifying one payment flow.
+fn new_request_id() -> String {
+    let mut bytes: [u8; 16] = rand::random();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex::encode(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// Reads the protocol version the server advertised, preferring the
+/// requirements body over the response header.
+fn advertised_x402_version(
+    requirements: &serde_json::Value,
+    headers: &reqwest::header::HeaderMap,
+) -> Option<u32> {
+    requirements
+        .get("x402Version")
+        .and_then(|v| v.as_u64())
+        .map(|v| v as u32)
+        .or_else(|| {
+            headers
+                .get(X402_VERSION_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse().ok())
+        })
+}
+
+fn negotiate_x402_version(requested: Option<u32>, advertised: Option<u32>) -> Result<u32> {
+    let version = requested.or(advertised).unwrap_or(DEFAULT_X402_VERSION);
+
+    if !SUPPORTED_X402_VERSIONS.contains(&version) {
+        let supported: Vec<String> = SUPPORTED_X402_VERSIONS
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+        let source = if requested.is_some() {
+            "--x402-version"
+        } else {
+            "Server"
+        };
+        anyhow::bail!(
+            "{} requested x402Version {}, but this client can only produce version(s): {}",
+            source,
+            version,
+            supported.join(", ")
+        );
+    }
+
+    Ok(version)
+}
+
+pub async fn test_payment_flow(options: &PaymentTestOptions) -> Result<PaymentFlowReport> {
+    let client = build_client(options.insecure)?;
+    run_payment_flow(&client, options, options.json).await
+}
+
+/// Walks through every step of the payment flow with a fabricated 402,
+/// synthetic requirements, and a fake settlement, without any network calls.
+pub async fn mock_payment_flow(options: &PaymentTestOptions) -> Result<PaymentFlowReport> {
+    let quiet = options.json;
     let start_time = Instant::now();
+    let mut report = PaymentFlowReport {
+        api: options.api_url.clone(),
+        request_id: new_request_id(),
+        ..Default::default()
+    };
+    let random_address = || format!("0x{}", hex::encode(rand::random::<[u8; 32]>()));
+
+    progress!(
+        quiet,
+        "{}",
+        "  ℹ Mock mode: simulating the server and facilitator, no requests are sent"
+            .yellow()
+            .dimmed()
+    );
+
+    progress!(quiet, "{}", "  Step 1: Sending initial request...".dimmed());
+    progress!(
+        quiet,
+        "{}",
+        format!("  Request ID: {}", report.request_id).dimmed()
+    );
+    report.initial_status = Some(402);
+    report.timings.initial_ms = Some(0);
+    progress!(quiet, "  Status: {}", reqwest::StatusCode::PAYMENT_REQUIRED);
+    progress!(quiet, "  ✓ Received 402 Payment Required");
+
+    // The simulated server offers whichever network the payer prefers.
+    let network = preferred_network(options)
+        .map(|(network, _)| network)
+        .unwrap_or_else(|| "aptos:testnet".to_string());
+    let mut requirements = PaymentRequirements {
+        scheme: "exact".to_string(),
+        network,
+        amount: options.amount.unwrap_or(DEFAULT_AMOUNT).to_string(),
+        asset: "0x1::aptos_coin::AptosCoin".to_string(),
+        pay_to: random_address(),
+        extra: Extra::default(),
+    };
+    requirements.validate()?;
+    apply_requirement_overrides(&mut requirements, options, quiet);
+    report.requirements = Some(requirements.clone());
+
+    let x402_version = negotiate_x402_version(options.x402_version, None)?;
+    report.x402_version = Some(x402_version);
+    progress!(quiet, "  x402 Version: {}", x402_version);
+    progress!(
+        quiet,
+        "  Payment Requirements: {} {} to {}",
+        requirements.amount.dimmed().cyan(),
+        requirements.asset,
+        requirements.pay_to.dimmed()
+    );
+
+    progress!(
+        quiet,
+        "{}",
+        "  Step 2: Building payment payload...".dimmed()
+    );
+    let fee_mode = FeeMode::from_requirements(&requirements)?;
+    let gas = resolve_gas_parameters(None, options, &requirements.network, quiet).await;
+    print_gas_parameters(&gas, quiet);
+    print_fee_mode(&fee_mode, quiet);
+    report.gas = Some(gas);
+    report.fee_mode = Some(fee_mode);
+    let transaction_digest = random_address();
+    report.payment_transaction = Some(transaction_digest.clone());
+    progress!(quiet, "  Transaction Digest: {}", transaction_digest.cyan());
+
+    progress!(
+        quiet,
+        "{}",
+        "  Step 3: Verifying payment with facilitator...".dimmed()
+    );
+    let payer = random_address();
+    report.timings.verify_ms = Some(0);
+    progress!(quiet, "{}", "  ✓ Payment verified".dimmed().green());
+    progress!(quiet, "  Payer: {}", payer.cyan());
+
+    progress!(
+        quiet,
+        "{}",
+        "  Step 4: Settling payment with facilitator...".dimmed()
+    );
+    report.timings.settle_ms = Some(0);
+    report.transaction = Some(transaction_digest.clone());
+    report.payer = Some(payer.clone());
+    report.network = Some(requirements.network.clone());
+    progress!(quiet, "{}", "  ✓ Payment settled".dimmed().green());
+    progress!(quiet, "  Transaction: {}", transaction_digest.cyan());
+    progress!(quiet, "  Payer: {}", payer.cyan());
+    progress!(quiet, "  Network: {}", requirements.network.cyan());
+
+    progress!(
+        quiet,
+        "{}",
+        "  Step 5: Retrying original request with payment proof...".dimmed()
+    );
+    let final_body = json!({ "message": "Mock paid response", "mock": true }).to_string();
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        reqwest::header::CONTENT_TYPE,
+        reqwest::header::HeaderValue::from_static("application/json"),
+    );
+    let final_response = HttpExchange {
+        status: reqwest::StatusCode::OK,
+        headers,
+        bytes: final_body.clone().into_bytes(),
+        body: final_body,
+    };
+    report.final_status = Some(final_response.status.as_u16());
+    report.timings.final_ms = Some(0);
+
+    finish_payment_flow(&mut report, options, &final_response, start_time, quiet);
+
+    Ok(report)
+}
+
+/// A request to the API as the user configured it: `--method`, `--header`s,
+/// and `--body` (whose `Content-Type` a `--header` can override), tagged
+/// with the flow's request ID. Both the initial request and the paid retry
+/// start from this.
+fn api_request(
+    client: &Client,
+    options: &PaymentTestOptions,
+    request_id: &str,
+) -> reqwest::RequestBuilder {
+    let mut request = client
+        .request(options.method.clone(), options.api_url.as_str())
+        .header(REQUEST_ID_HEADER, request_id);
+    for (name, value) in &options.headers {
+        request = request.header(name.as_str(), value.as_str());
+    }
+    if let Some(body) = &options.body {
+        let has_content_type = options
+            .headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("content-type"));
+        if !has_content_type {
+            request = request.header(reqwest::header::CONTENT_TYPE, body.content_type);
+        }
+        request = request.body(body.bytes.clone());
+    }
+    request
+}
+
+async fn run_payment_flow(
+    client: &Client,
+    options: &PaymentTestOptions,
+    quiet: bool,
+) -> Result<PaymentFlowReport> {
+    let api_url = options.api_url.as_str();
+    let start_time = Instant::now();
+    let request_id = new_request_id();
+    let mut report = PaymentFlowReport {
+        api: api_url.to_string(),
+        request_id: request_id.clone(),
+        ..Default::default()
+    };
 
     let step1_msg = "  Step 1: Sending initial request...".dimmed();
-    println!("{}", step1_msg);
+    progress!(quiet, "{}", step1_msg);
+    progress!(
+        quiet,
+        "{}",
+        format!("  Request ID: {}", request_id).dimmed()
+    );
 
-    let response = client
-        .get(api_url)
-        .send()
+    let step_start = Instant::now();
+    let initial_request = api_request(client, options, &request_id);
+    let response = send_logged(initial_request, options)
         .await
         .context("Failed to send initial request")?;
+    report.timings.initial_ms = Some(step_start.elapsed().as_millis());
 
-    let status = response.status();
-    println!("{}", format!("  Status: {}", status));
+    let status = response.status;
+    report.initial_status = Some(status.as_u16());
+    progress!(quiet, "  Status: {}", status);
 
     if status.as_u16() != 402 {
         let status_str = format!("{}", status);
-        println!("  ℹ Expected 402, got {}", status_str);
-        println!("  ℹ Note: For real x402 testing, API must return 402 Payment Required");
-        return Ok(());
+        progress!(quiet, "  ℹ Expected 402, got {}", status_str);
+        progress!(
+            quiet,
+            "  ℹ Note: For real x402 testing, API must return 402 Payment Required"
+        );
+        report.outcome = "no_payment_required".to_string();
+        report.elapsed_ms = start_time.elapsed().as_millis();
+        return Ok(report);
+    }
+
+    progress!(quiet, "  ✓ Received 402 Payment Required");
+
+    let session = session_headers(&response.headers, &options.carry_headers);
+    if !session.is_empty() {
+        let names: Vec<&str> = session.iter().map(|(name, _)| name.as_str()).collect();
+        progress!(
+            quiet,
+            "{}",
+            format!("  ℹ Carrying {} to the paid request", names.join(", ")).dimmed()
+        );
     }
 
-    println!("  ✓ Received 402 Payment Required");
+    // The server may throttle the paid retry; remember when it allows it.
+    let retry_at = response
+        .headers
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_retry_after)
+        .map(|delay| Instant::now() + delay.min(Duration::from_secs(MAX_RETRY_AFTER_SECS)));
 
+    let required_header_name = options.payment_required_header.as_str();
     let payment_required_header = response
-        .headers()
-        .get("PAYMENT-REQUIRED")
-        .context("Missing PAYMENT-REQUIRED header")?;
+        .headers
+        .get(required_header_name)
+        .with_context(|| format!("Missing {} header", required_header_name))?;
 
     let header_str = payment_required_header.to_str()?;
-    let decoded_bytes = Engine.decode(header_str)
-        .map_err(|e| anyhow::anyhow!("Failed to decode PAYMENT-REQUIRED header: {}", e))?;
+    let decoded_bytes = decode_base64_header(header_str)
+        .map_err(|e| anyhow::anyhow!("Failed to decode {} header: {}", required_header_name, e))?;
 
     let requirements_str = String::from_utf8(decoded_bytes)
         .map_err(|e| anyhow::anyhow!("Failed to convert decoded bytes to UTF-8: {}", e))?;
-    let requirements: PaymentRequirements = serde_json::from_str(&requirements_str)
+    let requirements_value: serde_json::Value = serde_json::from_str(&requirements_str)
         .map_err(|e| anyhow::anyhow!("Failed to parse PaymentRequirements: {}", e))?;
+    let advertised_version = advertised_x402_version(&requirements_value, &response.headers);
+    let mut requirements =
+        select_requirement(requirement_options(requirements_value)?, options, quiet)?;
+    requirements.validate()?;
+    check_expected_amount(&requirements, options, quiet)?;
+    apply_requirement_overrides(&mut requirements, options, quiet);
+    report.requirements = Some(requirements.clone());
 
-    println!(
-        "{}",
-        format!("  Payment Requirements: {} {} to {}",
-                 requirements.amount.dimmed().cyan(), requirements.asset, requirements.payTo.dimmed())
+    let x402_version = negotiate_x402_version(options.x402_version, advertised_version)?;
+    report.x402_version = Some(x402_version);
+    progress!(quiet, "  x402 Version: {}", x402_version);
+
+    progress!(
+        quiet,
+        "  Payment Requirements: {} {} to {}",
+        requirements.amount.dimmed().cyan(),
+        requirements.asset,
+        requirements.pay_to.dimmed()
     );
 
-    println!("{}", "  Step 2: Building payment payload...".dimmed());
+    confirm_mainnet_payment(&requirements, options)?;
 
-    let random_bytes: [u8; 32] = rand::random();
-    let transaction_hash = format!("0x{}", hex::encode(random_bytes));
-    let transaction_bytes = vec![0u8; 64];
-    let sender_authenticator_bytes = vec![0u8; 64];
+    progress!(
+        quiet,
+        "{}",
+        "  Step 2: Building payment payload...".dimmed()
+    );
 
-    let payload = Payload {
-        transaction: Engine.encode(transaction_bytes.as_slice()),
-        senderAuthenticator: Engine.encode(sender_authenticator_bytes.as_slice()),
-    };
+    let fee_mode = FeeMode::from_requirements(&requirements)?;
+    let gas = resolve_gas_parameters(Some(client), options, &requirements.network, quiet).await;
+    print_gas_parameters(&gas, quiet);
+    print_fee_mode(&fee_mode, quiet);
+
+    let context = transaction_context(client, options, &requirements.network).await?;
+    let (payload, transaction_digest) = build_payload(&requirements, &gas, &fee_mode, &context)?;
+    report.gas = Some(gas);
+    report.fee_mode = Some(fee_mode);
+    report.payment_transaction = Some(transaction_digest.clone());
 
     let payment_payload = PaymentPayload {
-        x402Version: 2,
+        x402_version,
         accepted: requirements.clone(),
         payload,
     };
 
-    println!("{}", format!("  Transaction Hash: {}", transaction_hash.cyan()));
-
-    println!("{}", "  Step 3: Verifying payment with facilitator...".dimmed());
+    progress!(quiet, "  Transaction Digest: {}", transaction_digest.cyan());
 
     let verify_request = json!({
         "paymentPayload": payment_payload,
         "paymentRequirements": requirements
     });
+    if let Some(path) = &options.dump_payload {
+        dump_payload(path, &verify_request, quiet)?;
+    }
 
-    let verify_response = client
-        .post(&format!("{}/verify", FACILITATOR_URL))
-        .header("Content-Type", "application/json")
-        .json(&verify_request)
-        .send()
-        .await
-        .context("Failed to verify payment")?;
+    progress!(
+        quiet,
+        "{}",
+        "  Step 3: Verifying payment with facilitator...".dimmed()
+    );
 
-    if !verify_response.status().is_success() {
-        let error_text = verify_response.text().await.unwrap_or_default();
-        println!("{}", format!("  ⚠ Verification failed: {}", error_text).dimmed().yellow());
-        return Ok(());
+    wait_for_facilitator(
+        client,
+        &options.facilitator_url,
+        options.wait_for_facilitator,
+        quiet,
+    )
+    .await?;
+
+    let step_start = Instant::now();
+    let verify_response = send_logged(
+        client
+            .post(format!("{}/verify", options.facilitator_url))
+            .header("Content-Type", "application/json")
+            .header(REQUEST_ID_HEADER, &request_id)
+            .json(&verify_request),
+        options,
+    )
+    .await
+    .context("Failed to verify payment")?;
+    report.timings.verify_ms = Some(step_start.elapsed().as_millis());
+    report.verify_response = Some(body_value(&verify_response.body));
+
+    if !verify_response.status.is_success() {
+        let error_text = verify_response.body;
+        progress!(
+            quiet,
+            "{}",
+            format!("  ⚠ Verification failed: {}", error_text)
+                .dimmed()
+                .yellow()
+        );
+        report.outcome = "verify_failed".to_string();
+        report.error = Some(format!("Verification failed: {}", error_text));
+        report.elapsed_ms = start_time.elapsed().as_millis();
+        return Ok(report);
     }
 
-    let verify_result: VerifyResponse = verify_response.json().await
-        .context("Failed to parse verify response")?;
+    let verify_result: VerifyResponse =
+        serde_json::from_str(&verify_response.body).context("Failed to parse verify response")?;
 
-    if !verify_result.isValid {
-        let reason = verify_result.invalidReason.unwrap_or_else(|| "Unknown".to_string());
-        println!("{}", format!("  ✗ Payment invalid: {}", reason.bold().red()));
-        return Ok(());
+    if !verify_result.is_valid {
+        let reason = verify_result
+            .invalid_reason
+            .unwrap_or_else(|| "Unknown".to_string());
+        let field = verify_result.invalid_field;
+        let hints = invalid_payment_hints(&reason, field.as_deref(), &requirements, options);
+
+        progress!(quiet, "  ✗ Payment invalid: {}", reason.bold().red());
+        if let Some(field) = &field {
+            progress!(quiet, "    Field: {}", field.yellow());
+        }
+        if let Some(payer) = &verify_result.payer {
+            progress!(quiet, "    Payer: {}", payer);
+        }
+        progress!(
+            quiet,
+            "    Response: {}",
+            verify_response.body.trim().dimmed()
+        );
+        for hint in &hints {
+            progress!(quiet, "{}", format!("    → {}", hint).yellow());
+        }
+
+        report.outcome = "invalid_payment".to_string();
+        report.error = Some(match &field {
+            Some(field) => format!("Payment invalid: {} ({})", reason, field),
+            None => format!("Payment invalid: {}", reason),
+        });
+        report.invalid_field = field;
+        report.hints = hints;
+        report.elapsed_ms = start_time.elapsed().as_millis();
+        return Ok(report);
     }
 
-    println!("{}", "  ✓ Payment verified".dimmed().green());
+    progress!(quiet, "{}", "  ✓ Payment verified".dimmed().green());
+    if let Some(payer) = &verify_result.payer {
+        progress!(quiet, "  Payer: {}", payer.cyan());
+    }
 
-    println!("{}", "  Step 4: Settling payment with facilitator...".dimmed());
+    progress!(
+        quiet,
+        "{}",
+        "  Step 4: Settling payment with facilitator...".dimmed()
+    );
 
-    let settle_response = client
-        .post(&format!("{}/settle", FACILITATOR_URL))
-        .header("Content-Type", "application/json")
-        .json(&verify_request)
-        .send()
-        .await
-        .context("Failed to settle payment")?;
+    let step_start = Instant::now();
+    let settle_response = send_logged(
+        client
+            .post(format!("{}/settle", options.facilitator_url))
+            .header("Content-Type", "application/json")
+            .header(REQUEST_ID_HEADER, &request_id)
+            .json(&verify_request),
+        options,
+    )
+    .await
+    .context("Failed to settle payment")?;
+    report.timings.settle_ms = Some(step_start.elapsed().as_millis());
+    report.settle_response = Some(body_value(&settle_response.body));
 
-    if !settle_response.status().is_success() {
-        let error_text = settle_response.text().await.unwrap_or_default();
-        println!("{}", format!("  ⚠ Settlement failed: {}", error_text).dimmed().yellow());
-        return Ok(());
+    if !settle_response.status.is_success() {
+        let error_text = settle_response.body;
+        progress!(
+            quiet,
+            "{}",
+            format!("  ⚠ Settlement failed: {}", error_text)
+                .dimmed()
+                .yellow()
+        );
+        report.outcome = "settle_failed".to_string();
+        report.error = Some(format!("Settlement failed: {}", error_text));
+        report.elapsed_ms = start_time.elapsed().as_millis();
+        return Ok(report);
     }
 
-    let settle_result: SettleResponse = settle_response.json().await
-        .context("Failed to parse settle response")?;
+    let settle_result: SettleResponse =
+        serde_json::from_str(&settle_response.body).context("Failed to parse settle response")?;
 
     if !settle_result.success {
-        println!("{}", "  ✗ Settlement failed".bold().red());
-        return Ok(());
+        let reason = settle_result
+            .error_reason
+            .unwrap_or_else(|| "Unknown".to_string());
+        progress!(quiet, "  ✗ Settlement failed: {}", reason.bold().red());
+        report.outcome = "settle_failed".to_string();
+        report.error = Some(format!("Settlement failed: {}", reason));
+        report.elapsed_ms = start_time.elapsed().as_millis();
+        return Ok(report);
+    }
+
+    report.transaction = Some(settle_result.transaction.clone());
+    report.payer = settle_result.payer.clone();
+    report.network = Some(settle_result.network.clone());
+
+    progress!(quiet, "{}", "  ✓ Payment settled".dimmed().green());
+    progress!(quiet, "  Transaction: {}", settle_result.transaction.cyan());
+    if let Some(payer) = &settle_result.payer {
+        progress!(quiet, "  Payer: {}", payer.cyan());
     }
+    progress!(quiet, "  Network: {}", settle_result.network.cyan());
 
-    println!("{}", "  ✓ Payment settled".dimmed().green());
-    println!("{}", format!("  Transaction: {}", settle_result.transaction.cyan()));
-    println!("{}", format!("  Payer: {}", settle_result.payer.cyan()));
+    progress!(
+        quiet,
+        "{}",
+        "  Step 5: Retrying original request with payment proof...".dimmed()
+    );
 
-    println!("{}", "  Step 5: Retrying original request with payment proof...".dimmed());
+    if let Some(retry_at) = retry_at {
+        let wait = retry_at.saturating_duration_since(Instant::now());
+        if options.ignore_retry_after {
+            progress!(
+                quiet,
+                "{}",
+                "  ℹ Ignoring Retry-After (--ignore-retry-after)".dimmed()
+            );
+        } else if !wait.is_zero() {
+            progress!(
+                quiet,
+                "{}",
+                format!(
+                    "  ℹ Honoring Retry-After: waiting {:.1}s",
+                    wait.as_secs_f64()
+                )
+                .dimmed()
+            );
+            tokio::time::sleep(wait).await;
+        }
+    }
 
     let payload_bytes = serde_json::to_vec(&payment_payload)
         .map_err(|e| anyhow::anyhow!("Failed to serialize payment payload: {}", e))?;
     let payment_signature = Engine.encode(&payload_bytes);
 
-    let final_response = client
-        .get(api_url)
-        .header("PAYMENT-SIGNATURE", payment_signature)
-        .send()
+    let mut final_request = api_request(client, options, &request_id)
+        .header(options.payment_signature_header.as_str(), payment_signature);
+    for (name, value) in &session {
+        final_request = final_request.header(name.as_str(), value.as_str());
+    }
+
+    let step_start = Instant::now();
+    let final_response = send_logged(final_request, options)
         .await
         .context("Failed to send final request")?;
+    report.final_status = Some(final_response.status.as_u16());
+    report.final_body = Some(final_response.body.clone());
+    report.timings.final_ms = Some(step_start.elapsed().as_millis());
+
+    finish_payment_flow(&mut report, options, &final_response, start_time, quiet);
+
+    Ok(report)
+}
+
+/// Runs the payment flow `repeat` times across `concurrency` tokio tasks that
+/// share one `Client`, then summarizes outcomes and latencies.
+pub async fn soak_payment_flow(
+    options: PaymentTestOptions,
+    repeat: u32,
+    concurrency: u32,
+) -> Result<SoakReport> {
+    let client = build_client(options.insecure)?;
+    let quiet = options.json;
+    let options = Arc::new(options);
+    let next_run = Arc::new(AtomicU32::new(0));
+    let start_time = Instant::now();
+
+    let workers: Vec<_> = (0..concurrency.min(repeat))
+        .map(|_| {
+            let client = client.clone();
+            let options = Arc::clone(&options);
+            let next_run = Arc::clone(&next_run);
+
+            tokio::spawn(async move {
+                let mut results = Vec::new();
+
+                loop {
+                    let run = next_run.fetch_add(1, Ordering::SeqCst) + 1;
+                    if run > repeat {
+                        break;
+                    }
+
+                    let run_start = Instant::now();
+                    let (success, outcome) = match run_payment_flow(&client, &options, true).await {
+                        Ok(report) => (report.is_success(), report.outcome),
+                        Err(e) => {
+                            progress!(
+                                quiet,
+                                "{}",
+                                format!("  ✗ Run {}/{}: {}", run, repeat, e).red()
+                            );
+                            (false, "error".to_string())
+                        }
+                    };
+                    let elapsed_ms = run_start.elapsed().as_millis();
+
+                    progress!(
+                        quiet,
+                        "{}",
+                        format!("  Run {}/{}: {} ({}ms)", run, repeat, outcome, elapsed_ms)
+                            .dimmed()
+                    );
+                    results.push((success, outcome, elapsed_ms));
+                }
+
+                results
+            })
+        })
+        .collect();
+
+    let mut report = SoakReport {
+        api: options.api_url.clone(),
+        runs: repeat,
+        concurrency,
+        ..Default::default()
+    };
+    let mut elapsed_times = Vec::new();
+
+    for worker in workers {
+        for (success, outcome, elapsed_ms) in worker.await.context("Payment test task panicked")? {
+            if success {
+                report.successes += 1;
+            } else {
+                report.failures += 1;
+            }
+            *report.outcomes.entry(outcome).or_default() += 1;
+            elapsed_times.push(elapsed_ms);
+        }
+    }
+
+    elapsed_times.sort_unstable();
+    report.min_ms = elapsed_times.first().copied().unwrap_or_default();
+    report.median_ms = percentile(&elapsed_times, 50);
+    report.p95_ms = percentile(&elapsed_times, 95);
+    report.max_ms = elapsed_times.last().copied().unwrap_or_default();
+    report.elapsed_ms = start_time.elapsed().as_millis();
+
+    progress!(quiet);
+    progress!(quiet, "{}", "Soak Test Complete".cyan().bold());
+    progress!(
+        quiet,
+        "Runs: {} (concurrency {})",
+        report.runs,
+        report.concurrency
+    );
+    progress!(quiet, "Succeeded: {}", report.successes.to_string().green());
+    progress!(quiet, "Failed: {}", report.failures.to_string().red());
+    for (outcome, count) in &report.outcomes {
+        progress!(quiet, "{}", format!("  {}: {}", outcome, count).dimmed());
+    }
+    progress!(
+        quiet,
+        "Latency: min {}ms · median {}ms · p95 {}ms · max {}ms",
+        report.min_ms,
+        report.median_ms,
+        report.p95_ms,
+        report.max_ms
+    );
+    progress!(quiet, "Time: {}ms", report.elapsed_ms);
+
+    Ok(report)
+}
+
+/// Nearest-rank percentile of an already sorted slice.
+fn percentile(sorted: &[u128], pct: usize) -> u128 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (pct * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// One entry of a `test batch` scenarios file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchScenario {
+    pub name: Option<String>,
+    pub api: String,
+    pub amount: Option<u64>,
+    pub expect_status: Option<u16>,
+    pub expect_contains: Option<String>,
+    pub facilitator_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BatchFile {
+    scenarios: Vec<BatchScenario>,
+}
+
+#[derive(Serialize)]
+pub struct BatchResult {
+    pub name: String,
+    pub api: String,
+    pub passed: bool,
+    pub outcome: String,
+    pub final_status: Option<u16>,
+    pub elapsed_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The scenario's full flow report, when it got far enough to have one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<PaymentFlowReport>,
+}
 
-    if final_response.status().is_success() {
-        println!("{}", "  ✓ Received response from API".bold().green());
+/// Reads a scenarios file: TOML when the extension is `.toml`, JSON
+/// otherwise. Both hold a `scenarios` list.
+pub fn load_batch_file(path: &Path) -> Result<Vec<BatchScenario>> {
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read scenarios file {}", path.display()))?;
+    parse_batch_file(&source, path)
+}
+
+/// Parses the contents of the scenarios file at `path`, whose extension
+/// picks the format.
+fn parse_batch_file(source: &str, path: &Path) -> Result<Vec<BatchScenario>> {
+    let file: BatchFile = if path.extension().is_some_and(|ext| ext == "toml") {
+        toml::from_str(source)
+            .with_context(|| format!("Failed to parse scenarios file {}", path.display()))?
     } else {
-        let final_status = final_response.status();
-        println!("{}", format!("  ℹ API returned: {}", final_status).dimmed().yellow());
+        serde_json::from_str(source)
+            .with_context(|| format!("Failed to parse scenarios file {}", path.display()))?
+    };
+
+    if file.scenarios.is_empty() {
+        anyhow::bail!("Scenarios file {} defines no scenarios", path.display());
     }
 
-    let elapsed = start_time.elapsed();
-    println!();
-    println!("{}", "Payment Flow Complete".cyan().bold());
-    println!("{}", format!("Transaction: {}", settle_result.transaction.cyan()));
-    println!("{}", format!("Payer: {}", settle_result.payer.cyan()));
-    println!("{}", format!("Time: {}ms", elapsed.as_millis()));
+    Ok(file.scenarios)
+}
+
+/// Runs each scenario's payment flow in order and prints a pass/fail table.
+/// Runs each scenario in order. With `fail_fast`, stops after the first
+/// failing one, so the returned results only cover the scenarios that ran.
+pub async fn batch_payment_flow(
+    scenarios: Vec<BatchScenario>,
+    insecure: bool,
+    quiet: bool,
+    yes: bool,
+    fail_fast: bool,
+) -> Result<Vec<BatchResult>> {
+    let client = build_client(insecure)?;
+    let total = scenarios.len();
+    let mut results = Vec::with_capacity(total);
+
+    for (index, scenario) in scenarios.into_iter().enumerate() {
+        let name = scenario
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("scenario {}", index + 1));
+        progress!(
+            quiet,
+            "{}",
+            format!(
+                "  Running {}/{}: {} ({})",
+                index + 1,
+                total,
+                name,
+                scenario.api
+            )
+            .dimmed()
+        );
+
+        let options = PaymentTestOptions {
+            api_url: scenario.api.clone(),
+            facilitator_url: scenario
+                .facilitator_url
+                .unwrap_or_else(|| DEFAULT_FACILITATOR_URL.to_string()),
+            amount: scenario.amount,
+            strict: false,
+            insecure,
+            json: true,
+            payment_required_header: PAYMENT_REQUIRED_HEADER.to_string(),
+            payment_signature_header: PAYMENT_SIGNATURE_HEADER.to_string(),
+            x402_version: None,
+            asset: None,
+            pay_to: None,
+            payment_network: None,
+            wait_for_facilitator: None,
+            expect_status: scenario.expect_status,
+            expect_contains: scenario.expect_contains,
+            verbose: false,
+            max_gas: None,
+            gas_unit_price: None,
+            expiration_secs: None,
+            ignore_retry_after: false,
+            yes,
+            carry_headers: Vec::new(),
+            headers: Vec::new(),
+            method: reqwest::Method::GET,
+            body: None,
+            sign_with: None,
+            prefer_network: None,
+            dump_payload: None,
+        };
+
+        let run_start = Instant::now();
+        let result = match run_payment_flow(&client, &options, true).await {
+            Ok(report) => BatchResult {
+                name,
+                api: scenario.api,
+                passed: report.is_success(),
+                outcome: report.outcome.clone(),
+                final_status: report.final_status,
+                elapsed_ms: run_start.elapsed().as_millis(),
+                error: report.error.clone(),
+                report: Some(report),
+            },
+            Err(e) => BatchResult {
+                name,
+                api: scenario.api,
+                passed: false,
+                outcome: "error".to_string(),
+                final_status: None,
+                elapsed_ms: run_start.elapsed().as_millis(),
+                error: Some(e.to_string()),
+                report: None,
+            },
+        };
+        let failed = !result.passed;
+        results.push(result);
+
+        if failed && fail_fast && index + 1 < total {
+            progress!(
+                quiet,
+                "{}",
+                format!(
+                    "  Stopping at the first failure (--fail-fast); {} scenario(s) not run",
+                    total - index - 1
+                )
+                .yellow()
+                .dimmed()
+            );
+            break;
+        }
+    }
+
+    print_batch_summary(&results, quiet);
+
+    Ok(results)
+}
 
+/// Writes batch results as a JUnit XML report, one `<testcase>` per
+/// scenario that ran. Scenarios that errored before a verdict (unreachable
+/// API, bad requirements, ...) are reported as `<error>`, the rest as
+/// `<failure>`.
+pub fn write_junit_report(path: &Path, suite: &str, results: &[BatchResult]) -> Result<()> {
+    create_parent_dir(path)?;
+    std::fs::write(path, junit_report(suite, results))
+        .with_context(|| format!("Failed to write JUnit report: {}", path.display()))
+}
+
+fn junit_report(suite: &str, results: &[BatchResult]) -> String {
+    let failures = results
+        .iter()
+        .filter(|result| !result.passed && result.outcome != "error")
+        .count();
+    let errors = results
+        .iter()
+        .filter(|result| !result.passed && result.outcome == "error")
+        .count();
+    let total_secs: f64 = results
+        .iter()
+        .map(|result| result.elapsed_ms as f64 / 1000.0)
+        .sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"x402 batch\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+        results.len(),
+        failures,
+        errors,
+        total_secs
+    ));
+    xml.push_str(&format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"0\" time=\"{:.3}\" timestamp=\"{}\">\n",
+        xml_escape(suite),
+        results.len(),
+        failures,
+        errors,
+        total_secs,
+        chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S")
+    ));
+
+    for result in results {
+        xml.push_str(&format!(
+            "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+            xml_escape(&result.name),
+            xml_escape(&result.api),
+            result.elapsed_ms as f64 / 1000.0
+        ));
+
+        if result.passed {
+            xml.push_str("/>\n");
+            continue;
+        }
+
+        let element = if result.outcome == "error" {
+            "error"
+        } else {
+            "failure"
+        };
+        let status = result
+            .final_status
+            .map(|status| format!("final status {}", status))
+            .unwrap_or_else(|| "no final status".to_string());
+        let message = result.error.as_deref().unwrap_or(&result.outcome);
+        xml.push_str(&format!(
+            ">\n      <{element} message=\"{}\" type=\"{}\">{} ({})</{element}>\n    </testcase>\n",
+            xml_escape(message),
+            xml_escape(&result.outcome),
+            xml_escape(message),
+            status,
+            element = element
+        ));
+    }
+
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+/// Writes `--output`: `report` as pretty JSON, creating missing parent
+/// directories.
+pub fn write_json_report(path: &Path, report: &impl Serialize) -> Result<()> {
+    create_parent_dir(path)?;
+    let json = serde_json::to_string_pretty(report)?;
+    std::fs::write(path, json + "\n")
+        .with_context(|| format!("Failed to write report: {}", path.display()))
+}
+
+fn create_parent_dir(path: &Path) -> Result<()> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
     Ok(())
 }
+
+fn xml_escape(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_control() || matches!(c, '\n' | '\t'))
+        .fold(String::with_capacity(text.len()), |mut out, c| {
+            match c {
+                '&' => out.push_str("&amp;"),
+                '<' => out.push_str("&lt;"),
+                '>' => out.push_str("&gt;"),
+                '"' => out.push_str("&quot;"),
+                '\'' => out.push_str("&apos;"),
+                c => out.push(c),
+            }
+            out
+        })
+}
+
+fn print_batch_summary(results: &[BatchResult], quiet: bool) {
+    let name_width = results
+        .iter()
+        .map(|result| result.name.len())
+        .max()
+        .unwrap_or(0)
+        .max("Scenario".len());
+
+    progress!(quiet);
+    progress!(quiet, "{}", "Batch Test Complete".cyan().bold());
+    progress!(
+        quiet,
+        "{}",
+        format!(
+            "  {:<4}  {:<name_width$}  {:<6}  {:>8}  Outcome",
+            "", "Scenario", "Status", "Time"
+        )
+        .dimmed()
+    );
+
+    for result in results {
+        let verdict = if result.passed {
+            format!("{:<4}", "PASS").green()
+        } else {
+            format!("{:<4}", "FAIL").red()
+        };
+        let status = result
+            .final_status
+            .map(|status| status.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let detail = match &result.error {
+            Some(error) if !result.passed => format!("{} ({})", result.outcome, error),
+            _ => result.outcome.clone(),
+        };
+
+        progress!(
+            quiet,
+            "  {}  {:<name_width$}  {:<6}  {:>6}ms  {}",
+            verdict,
+            result.name,
+            status,
+            result.elapsed_ms,
+            detail.dimmed()
+        );
+    }
+
+    let passed = results.iter().filter(|result| result.passed).count();
+    progress!(quiet);
+    progress!(quiet, "Passed: {}", passed.to_string().green());
+    progress!(
+        quiet,
+        "Failed: {}",
+        (results.len() - passed).to_string().red()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn requirements() -> PaymentRequirements {
+        PaymentRequirements {
+            scheme: "exact".to_string(),
+            network: "aptos:testnet".to_string(),
+            amount: "1000".to_string(),
+            asset: "0x1::aptos_coin::AptosCoin".to_string(),
+            pay_to: format!("0x{}", "ab".repeat(32)),
+            extra: Extra::default(),
+        }
+    }
+
+    #[test]
+    fn parse_amount_accepts_octas_and_apt() {
+        assert_eq!(parse_amount("1000"), Ok(1000));
+        assert_eq!(parse_amount(" 1000octas "), Ok(1000));
+        assert_eq!(parse_amount("1 octa"), Ok(1));
+        assert_eq!(parse_amount("0.5APT"), Ok(50_000_000));
+        assert_eq!(parse_amount("2 apt"), Ok(200_000_000));
+        assert_eq!(parse_amount(".00000001APT"), Ok(1));
+    }
+
+    #[test]
+    fn parse_amount_rejects_bad_amounts() {
+        assert!(parse_amount("").is_err());
+        assert!(parse_amount("APT").is_err());
+        assert!(parse_amount("1.5").is_err());
+        assert!(parse_amount("0.000000001APT").is_err());
+        assert!(parse_amount("-1").is_err());
+        assert!(parse_amount("1.2.3APT").is_err());
+        assert!(parse_amount("200000000000APT").is_err());
+    }
+
+    #[test]
+    fn parse_header_splits_on_the_first_equals() {
+        assert_eq!(
+            parse_header("X-Token = a=b"),
+            Ok(("X-Token".to_string(), " a=b".to_string()))
+        );
+        assert!(parse_header("X-Token").is_err());
+        assert!(parse_header("=value").is_err());
+        assert!(parse_header("Bad Name=value").is_err());
+        assert!(parse_header("X-Token=line\nbreak").is_err());
+    }
+
+    #[test]
+    fn parse_method_is_case_insensitive() {
+        assert_eq!(parse_method("post"), Ok(reqwest::Method::POST));
+        assert_eq!(parse_method(" PATCH "), Ok(reqwest::Method::PATCH));
+        assert!(parse_method("NOT A METHOD").is_err());
+    }
+
+    #[test]
+    fn validate_accepts_well_formed_requirements() {
+        requirements().validate().unwrap();
+
+        let fungible_asset = PaymentRequirements {
+            asset: "0xa".to_string(),
+            network: "aptos:2".to_string(),
+            ..requirements()
+        };
+        fungible_asset.validate().unwrap();
+    }
+
+    #[test]
+    fn validate_reports_every_bad_field() {
+        let requirements = PaymentRequirements {
+            scheme: "upto".to_string(),
+            network: "testnet".to_string(),
+            amount: "0".to_string(),
+            asset: "0x1::aptos_coin".to_string(),
+            pay_to: "nope".to_string(),
+            ..requirements()
+        };
+        let error = requirements.validate().unwrap_err().to_string();
+
+        for field in ["scheme", "network", "amount", "payTo", "asset"] {
+            assert!(
+                error.contains(field),
+                "{} not reported in: {}",
+                field,
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn parses_toml_and_json_batch_files() {
+        let scenarios = parse_batch_file(
+            r#"
+[[scenarios]]
+name = "premium"
+api = "http://localhost:3000/premium"
+amount = 1000
+expect_status = 200
+
+[[scenarios]]
+api = "http://localhost:3000/free"
+expect_contains = "ok"
+"#,
+            Path::new("batch.toml"),
+        )
+        .unwrap();
+        assert_eq!(scenarios.len(), 2);
+        assert_eq!(scenarios[0].name.as_deref(), Some("premium"));
+        assert_eq!(scenarios[0].amount, Some(1000));
+        assert_eq!(scenarios[0].expect_status, Some(200));
+        assert_eq!(scenarios[1].name, None);
+        assert_eq!(scenarios[1].expect_contains.as_deref(), Some("ok"));
+
+        let scenarios = parse_batch_file(
+            r#"{"scenarios": [{"api": "http://localhost:3000/premium", "facilitator_url": "http://localhost:3001"}]}"#,
+            Path::new("batch.json"),
+        )
+        .unwrap();
+        assert_eq!(scenarios[0].api, "http://localhost:3000/premium");
+        assert_eq!(
+            scenarios[0].facilitator_url.as_deref(),
+            Some("http://localhost:3001")
+        );
+    }
+
+    #[test]
+    fn rejects_bad_batch_files() {
+        let err = parse_batch_file(
+            r#"{"scenarios": [{"api": "http://localhost:3000", "expected": 200}]}"#,
+            Path::new("batch.json"),
+        )
+        .unwrap_err();
+        assert!(format!("{:#}", err).contains("unknown field `expected`"));
+
+        let err = parse_batch_file("scenarios = []", Path::new("batch.toml")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Scenarios file batch.toml defines no scenarios"
+        );
+
+        // Anything but `.toml` is read as JSON.
+        assert!(parse_batch_file("scenarios = []", Path::new("batch.txt")).is_err());
+    }
+
+    fn batch_result(name: &str, passed: bool, outcome: &str, error: Option<&str>) -> BatchResult {
+        BatchResult {
+            name: name.to_string(),
+            api: "http://localhost:3000/premium".to_string(),
+            passed,
+            outcome: outcome.to_string(),
+            final_status: passed.then_some(200),
+            elapsed_ms: 1500,
+            error: error.map(str::to_string),
+            report: None,
+        }
+    }
+
+    #[test]
+    fn junit_report_separates_failures_from_errors() {
+        let xml = junit_report(
+            "smoke & <co>",
+            &[
+                batch_result("paid", true, "paid", None),
+                batch_result("rejected", false, "verify_failed", Some("bad \"sig\"")),
+                batch_result("down", false, "error", Some("connection refused")),
+            ],
+        );
+
+        assert!(xml.contains(
+            r#"<testsuite name="smoke &amp; &lt;co&gt;" tests="3" failures="1" errors="1" skipped="0" time="4.500""#
+        ));
+        assert!(xml.contains(
+            r#"<testcase name="paid" classname="http://localhost:3000/premium" time="1.500"/>"#
+        ));
+        assert!(xml.contains(
+            r#"<failure message="bad &quot;sig&quot;" type="verify_failed">bad &quot;sig&quot; (no final status)</failure>"#
+        ));
+        assert!(xml.contains(
+            r#"<error message="connection refused" type="error">connection refused (no final status)</error>"#
+        ));
+        assert!(xml.ends_with("</testsuite>\n</testsuites>\n"));
+    }
+
+    fn unreachable_scenario() -> BatchScenario {
+        BatchScenario {
+            name: None,
+            api: "http://127.0.0.1:1/premium".to_string(),
+            amount: None,
+            expect_status: None,
+            expect_contains: None,
+            facilitator_url: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn fail_fast_stops_after_the_first_failure() {
+        let scenarios = || (0..3).map(|_| unreachable_scenario()).collect::<Vec<_>>();
+
+        let results = batch_payment_flow(scenarios(), false, true, true, true)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(!results[0].passed);
+        assert_eq!(results[0].name, "scenario 1");
+
+        let results = batch_payment_flow(scenarios(), false, true, true, false)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|result| !result.passed));
+    }
+}
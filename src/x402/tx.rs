@@ -0,0 +1,681 @@
+use anyhow::{Context, Result};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use std::fmt;
+use std::str::FromStr;
+
+/// Address of the Aptos framework (`0x1`).
+pub const FRAMEWORK_ADDRESS: AccountAddress = AccountAddress({
+    let mut bytes = [0u8; 32];
+    bytes[31] = 1;
+    bytes
+});
+
+/// SHA3-256 of `APTOS::RawTransaction`, which prefixes the BCS raw
+/// transaction in the message a sender signs.
+const RAW_TRANSACTION_SALT: [u8; 32] = [
+    0xb5, 0xe9, 0x7d, 0xb0, 0x7f, 0xa0, 0xbd, 0x0e, 0x55, 0x98, 0xaa, 0x36, 0x43, 0xa9, 0xbc, 0x6f,
+    0x66, 0x93, 0xbd, 0xdc, 0x1a, 0x9f, 0xec, 0x9e, 0x67, 0x4a, 0x46, 0x1e, 0xaa, 0x00, 0xb1, 0x93,
+];
+
+/// Hashed into the salt of the message a sponsored transaction's signers
+/// sign, in place of `APTOS::RawTransaction`.
+const RAW_TRANSACTION_WITH_DATA_DOMAIN: &[u8] = b"APTOS::RawTransactionWithData";
+
+/// Authentication scheme byte of a single Ed25519 key, appended to the
+/// public key when deriving its account address.
+const ED25519_SCHEME: u8 = 0x00;
+
+/// Content type of a BCS `SignedTransaction` submitted to a fullnode's
+/// `POST /transactions`.
+pub const SIGNED_TRANSACTION_CONTENT_TYPE: &str = "application/x.aptos.signed_transaction+bcs";
+
+/// A 32-byte Aptos account address. BCS encodes it as the raw bytes, with no
+/// length prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AccountAddress(pub [u8; 32]);
+
+impl FromStr for AccountAddress {
+    type Err = anyhow::Error;
+
+    /// Parses `0x`-prefixed hex, left-padding short forms like `0x1`.
+    fn from_str(address: &str) -> Result<Self> {
+        let digits = address.strip_prefix("0x").unwrap_or(address);
+        if digits.is_empty() || digits.len() > 64 {
+            anyhow::bail!("'{}' is not a valid account address", address);
+        }
+        hex::decode(format!("{:0>64}", digits))
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(AccountAddress)
+            .with_context(|| format!("'{}' is not a valid account address", address))
+    }
+}
+
+impl AccountAddress {
+    /// The account address of a single Ed25519 key: SHA3-256 of the public
+    /// key followed by the Ed25519 authentication scheme byte.
+    pub fn from_ed25519_public_key(public_key: &[u8]) -> Self {
+        let mut hasher = Sha3_256::new();
+        hasher.update(public_key);
+        hasher.update([ED25519_SCHEME]);
+        AccountAddress(hasher.finalize().into())
+    }
+}
+
+impl fmt::Display for AccountAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{}", hex::encode(self.0))
+    }
+}
+
+/// A Move type argument. Variant order is the BCS tag, so it must match
+/// Aptos's `TypeTag`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TypeTag {
+    Bool,
+    U8,
+    U64,
+    U128,
+    Address,
+    Signer,
+    Vector(Box<TypeTag>),
+    Struct(Box<StructTag>),
+    U16,
+    U32,
+    U256,
+}
+
+/// A Move struct type such as `0x1::aptos_coin::AptosCoin`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StructTag {
+    pub address: AccountAddress,
+    pub module: String,
+    pub name: String,
+    pub type_args: Vec<TypeTag>,
+}
+
+impl FromStr for StructTag {
+    type Err = anyhow::Error;
+
+    /// Parses `<address>::<module>::<name>`; generic structs aren't
+    /// supported.
+    fn from_str(value: &str) -> Result<Self> {
+        let parts: Vec<&str> = value.split("::").collect();
+        let [address, module, name] = parts[..] else {
+            anyhow::bail!(
+                "'{}' is not a struct type of the form <address>::<module>::<name>",
+                value
+            );
+        };
+        if module.is_empty() || name.is_empty() || name.contains('<') {
+            anyhow::bail!(
+                "'{}' is not a struct type of the form <address>::<module>::<name>",
+                value
+            );
+        }
+        Ok(StructTag {
+            address: address.parse()?,
+            module: module.to_string(),
+            name: name.to_string(),
+            type_args: Vec::new(),
+        })
+    }
+}
+
+impl fmt::Display for StructTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}::{}::{}", self.address, self.module, self.name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleId {
+    pub address: AccountAddress,
+    pub name: String,
+}
+
+/// A call to a public entry function. Each argument is its own BCS
+/// encoding.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryFunction {
+    pub module: ModuleId,
+    pub function: String,
+    pub ty_args: Vec<TypeTag>,
+    pub args: Vec<Vec<u8>>,
+}
+
+/// Stands in for payload kinds this CLI never builds, so that
+/// `EntryFunction` keeps its BCS tag of 2. It has no values; decoding one of
+/// those payloads fails.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Unsupported {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransactionPayload {
+    Script(Unsupported),
+    ModuleBundle(Unsupported),
+    EntryFunction(EntryFunction),
+}
+
+/// The unsigned transaction, laid out field for field as Aptos's
+/// `RawTransaction`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RawTransaction {
+    pub sender: AccountAddress,
+    pub sequence_number: u64,
+    pub payload: TransactionPayload,
+    pub max_gas_amount: u64,
+    pub gas_unit_price: u64,
+    pub expiration_timestamp_secs: u64,
+    pub chain_id: u8,
+}
+
+impl RawTransaction {
+    /// Signs the transaction with a single Ed25519 key, ready to submit.
+    pub fn sign(self, key: &SigningKey) -> Result<SignedTransaction> {
+        let sender = sign_payment(&self, None, key)?;
+        Ok(SignedTransaction::payment(self, sender, None))
+    }
+}
+
+/// One signer's proof over a transaction, as a sponsored transaction carries
+/// for its sender and fee payer. Only the first of Aptos's
+/// `AccountAuthenticator` variants is needed, so BCS tags match.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccountAuthenticator {
+    Ed25519 {
+        public_key: Vec<u8>,
+        signature: Vec<u8>,
+    },
+}
+
+/// Proof that a transaction's signers signed it. Variants this CLI never
+/// builds stand in as [`Unsupported`] so the rest keep Aptos's
+/// `TransactionAuthenticator` BCS tags.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransactionAuthenticator {
+    Ed25519 {
+        public_key: Vec<u8>,
+        signature: Vec<u8>,
+    },
+    MultiEd25519(Unsupported),
+    MultiAgent(Unsupported),
+    /// A sponsored transaction: the sender's signature and the fee payer's,
+    /// both over the `RawTransactionWithData` naming the fee payer.
+    FeePayer {
+        sender: AccountAuthenticator,
+        secondary_signer_addresses: Vec<AccountAddress>,
+        secondary_signers: Vec<AccountAuthenticator>,
+        fee_payer_address: AccountAddress,
+        fee_payer_signer: AccountAuthenticator,
+    },
+}
+
+/// A raw transaction and its authenticator, laid out as Aptos's
+/// `SignedTransaction`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedTransaction {
+    pub raw_txn: RawTransaction,
+    pub authenticator: TransactionAuthenticator,
+}
+
+impl SignedTransaction {
+    /// Assembles a payment from its sender's authenticator. A sponsored
+    /// payment also takes the fee payer's address and authenticator.
+    pub fn payment(
+        raw_txn: RawTransaction,
+        sender: AccountAuthenticator,
+        fee_payer: Option<(AccountAddress, AccountAuthenticator)>,
+    ) -> Self {
+        let authenticator = match (sender, fee_payer) {
+            (
+                AccountAuthenticator::Ed25519 {
+                    public_key,
+                    signature,
+                },
+                None,
+            ) => TransactionAuthenticator::Ed25519 {
+                public_key,
+                signature,
+            },
+            (sender, Some((fee_payer_address, fee_payer_signer))) => {
+                TransactionAuthenticator::FeePayer {
+                    sender,
+                    secondary_signer_addresses: Vec::new(),
+                    secondary_signers: Vec::new(),
+                    fee_payer_address,
+                    fee_payer_signer,
+                }
+            }
+        };
+        SignedTransaction {
+            raw_txn,
+            authenticator,
+        }
+    }
+}
+
+/// A raw transaction with its extra signers, which is what a sponsored
+/// transaction's signers sign.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RawTransactionWithData {
+    MultiAgent {
+        raw_txn: RawTransaction,
+        secondary_signer_addresses: Vec<AccountAddress>,
+    },
+    MultiAgentWithFeePayer {
+        raw_txn: RawTransaction,
+        secondary_signer_addresses: Vec<AccountAddress>,
+        fee_payer_address: AccountAddress,
+    },
+}
+
+/// The asset, recipient, and amount of a payment transfer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transfer {
+    /// A coin type (`0x1::aptos_coin::AptosCoin`) or a fungible asset's
+    /// metadata address.
+    pub asset: String,
+    pub recipient: AccountAddress,
+    pub amount: u64,
+}
+
+/// Why a transfer doesn't satisfy payment requirements, naming the
+/// requirements field (`asset`, `payTo`, or `amount`) it disagrees with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub field: &'static str,
+    pub reason: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.reason)
+    }
+}
+
+impl std::error::Error for Mismatch {}
+
+impl Transfer {
+    /// Checks this transfer against payment requirements' `asset`, `payTo`,
+    /// and `amount`, comparing addresses by value so `0x1` matches its
+    /// padded form.
+    pub fn check(&self, asset: &str, pay_to: &str, amount: &str) -> Result<(), Mismatch> {
+        let mismatch = |field, reason: String| Mismatch { field, reason };
+
+        let expected_asset = if asset.contains("::") {
+            asset.parse::<StructTag>().map(|tag| tag.to_string())
+        } else {
+            asset
+                .parse::<AccountAddress>()
+                .map(|address| address.to_string())
+        };
+        let expected_asset = expected_asset.map_err(|e| mismatch("asset", e.to_string()))?;
+        if expected_asset != self.asset {
+            return Err(mismatch(
+                "asset",
+                format!("transaction transfers {}, expected {}", self.asset, asset),
+            ));
+        }
+
+        let expected_pay_to = pay_to
+            .parse::<AccountAddress>()
+            .map_err(|e| mismatch("payTo", e.to_string()))?;
+        if expected_pay_to != self.recipient {
+            return Err(mismatch(
+                "payTo",
+                format!("transaction pays {}, expected {}", self.recipient, pay_to),
+            ));
+        }
+
+        if amount.parse::<u64>().ok() != Some(self.amount) {
+            return Err(mismatch(
+                "amount",
+                format!("transaction transfers {}, expected {}", self.amount, amount),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl TransactionPayload {
+    /// The entry function call paying `amount` of `asset` to `recipient`:
+    /// `0x1::aptos_account::transfer_coins<CoinType>` for a coin type, or
+    /// `0x1::primary_fungible_store::transfer` for a fungible asset
+    /// address.
+    pub fn transfer(asset: &str, recipient: AccountAddress, amount: u64) -> Result<Self> {
+        let amount = bcs::to_bytes(&amount)?;
+        let recipient = bcs::to_bytes(&recipient)?;
+
+        let entry_function = if asset.contains("::") {
+            EntryFunction {
+                module: framework_module("aptos_account"),
+                function: "transfer_coins".to_string(),
+                ty_args: vec![TypeTag::Struct(Box::new(asset.parse()?))],
+                args: vec![recipient, amount],
+            }
+        } else {
+            let metadata: AccountAddress = asset.parse()?;
+            EntryFunction {
+                module: framework_module("primary_fungible_store"),
+                function: "transfer".to_string(),
+                ty_args: vec![TypeTag::Struct(Box::new(StructTag {
+                    address: FRAMEWORK_ADDRESS,
+                    module: "fungible_asset".to_string(),
+                    name: "Metadata".to_string(),
+                    type_args: Vec::new(),
+                }))],
+                args: vec![bcs::to_bytes(&metadata)?, recipient, amount],
+            }
+        };
+        Ok(TransactionPayload::EntryFunction(entry_function))
+    }
+
+    /// Reads back a payload built by [`transfer`](Self::transfer); `None` for
+    /// any other call.
+    pub fn as_transfer(&self) -> Option<Transfer> {
+        let TransactionPayload::EntryFunction(call) = self else {
+            return None;
+        };
+        if call.module.address != FRAMEWORK_ADDRESS {
+            return None;
+        }
+
+        match (
+            call.module.name.as_str(),
+            call.function.as_str(),
+            &call.ty_args[..],
+            &call.args[..],
+        ) {
+            ("aptos_account", "transfer_coins", [TypeTag::Struct(coin)], [recipient, amount]) => {
+                Some(Transfer {
+                    asset: coin.to_string(),
+                    recipient: bcs::from_bytes(recipient).ok()?,
+                    amount: bcs::from_bytes(amount).ok()?,
+                })
+            }
+            ("primary_fungible_store", "transfer", [_], [metadata, recipient, amount]) => {
+                let metadata: AccountAddress = bcs::from_bytes(metadata).ok()?;
+                Some(Transfer {
+                    asset: metadata.to_string(),
+                    recipient: bcs::from_bytes(recipient).ok()?,
+                    amount: bcs::from_bytes(amount).ok()?,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+fn framework_module(name: &str) -> ModuleId {
+    ModuleId {
+        address: FRAMEWORK_ADDRESS,
+        name: name.to_string(),
+    }
+}
+
+/// Encodes the bytes a payment payload carries as its `transaction`: the BCS
+/// `RawTransaction`, or for a sponsored payment the
+/// `RawTransactionWithData::MultiAgentWithFeePayer` naming `fee_payer`.
+pub fn encode_payment(
+    raw_txn: &RawTransaction,
+    fee_payer: Option<AccountAddress>,
+) -> Result<Vec<u8>> {
+    let bytes = match fee_payer {
+        None => bcs::to_bytes(raw_txn),
+        Some(fee_payer_address) => bcs::to_bytes(&RawTransactionWithData::MultiAgentWithFeePayer {
+            raw_txn: raw_txn.clone(),
+            secondary_signer_addresses: Vec::new(),
+            fee_payer_address,
+        }),
+    };
+    bytes.context("Failed to BCS-encode the payment transaction")
+}
+
+/// Decodes [`encode_payment`]'s bytes. `sponsored` says which form to expect,
+/// since the payload records it (`feePayerAddress`) and the bytes alone are
+/// ambiguous.
+pub fn decode_payment(bytes: &[u8], sponsored: bool) -> Result<RawTransaction> {
+    if !sponsored {
+        return bcs::from_bytes(bytes).context("transaction is not a BCS-encoded RawTransaction");
+    }
+    match bcs::from_bytes(bytes) {
+        Ok(RawTransactionWithData::MultiAgentWithFeePayer { raw_txn, .. }) => Ok(raw_txn),
+        _ => anyhow::bail!("transaction is not a BCS-encoded fee-payer RawTransaction"),
+    }
+}
+
+/// The message a payment's signers sign: the domain salt followed by
+/// [`encode_payment`]'s bytes. Sponsored transactions are signed as
+/// `RawTransactionWithData` naming the fee payer, so the sender's signature
+/// only holds with that fee payer.
+pub fn signing_message(
+    raw_txn: &RawTransaction,
+    fee_payer: Option<AccountAddress>,
+) -> Result<Vec<u8>> {
+    let mut message = match fee_payer {
+        None => RAW_TRANSACTION_SALT.to_vec(),
+        Some(_) => Sha3_256::digest(RAW_TRANSACTION_WITH_DATA_DOMAIN).to_vec(),
+    };
+    message.extend(encode_payment(raw_txn, fee_payer)?);
+    Ok(message)
+}
+
+/// Signs a payment as its sender, or as the fee payer of a sponsored one.
+pub fn sign_payment(
+    raw_txn: &RawTransaction,
+    fee_payer: Option<AccountAddress>,
+    key: &SigningKey,
+) -> Result<AccountAuthenticator> {
+    let signature = key.sign(&signing_message(raw_txn, fee_payer)?);
+    Ok(AccountAuthenticator::Ed25519 {
+        public_key: key.verifying_key().to_bytes().to_vec(),
+        signature: signature.to_bytes().to_vec(),
+    })
+}
+
+/// Checks that `authenticator` is the sender's signature over the payment:
+/// its key must be the sender account's, and its signature must hold over
+/// [`signing_message`].
+pub fn verify_sender(
+    raw_txn: &RawTransaction,
+    fee_payer: Option<AccountAddress>,
+    authenticator: &AccountAuthenticator,
+) -> Result<()> {
+    let AccountAuthenticator::Ed25519 {
+        public_key,
+        signature,
+    } = authenticator;
+
+    if AccountAddress::from_ed25519_public_key(public_key) != raw_txn.sender {
+        anyhow::bail!(
+            "sender authenticator's public key is not the key of sender {}",
+            raw_txn.sender
+        );
+    }
+    let public_key: [u8; 32] = public_key[..]
+        .try_into()
+        .context("sender public key is not 32 bytes")?;
+    let public_key =
+        VerifyingKey::from_bytes(&public_key).context("sender public key is not a valid key")?;
+    let signature = Signature::from_slice(signature).context("sender signature is not 64 bytes")?;
+
+    public_key
+        .verify(&signing_message(raw_txn, fee_payer)?, &signature)
+        .context("sender signature does not match the transaction")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `0x1::aptos_account::transfer(0xb0b, 1000)` from `0xa11ce`.
+    fn transfer_transaction() -> RawTransaction {
+        let recipient: AccountAddress = "0xb0b".parse().unwrap();
+        RawTransaction {
+            sender: "0xa11ce".parse().unwrap(),
+            sequence_number: 7,
+            payload: TransactionPayload::EntryFunction(EntryFunction {
+                module: framework_module("aptos_account"),
+                function: "transfer".to_string(),
+                ty_args: Vec::new(),
+                args: vec![
+                    bcs::to_bytes(&recipient).unwrap(),
+                    bcs::to_bytes(&1000u64).unwrap(),
+                ],
+            }),
+            max_gas_amount: 2000,
+            gas_unit_price: 100,
+            expiration_timestamp_secs: 1_700_000_000,
+            chain_id: 2,
+        }
+    }
+
+    /// Assembled by hand from Aptos's `RawTransaction` layout: sender,
+    /// sequence number (u64 LE), payload tag 2 (`EntryFunction`), module
+    /// address and ULEB128-prefixed names, no type arguments, two
+    /// length-prefixed arguments, then max gas, gas unit price, expiration
+    /// (u64 LE each) and the chain id.
+    const TRANSFER_TRANSACTION_BCS: &str = concat!(
+        "00000000000000000000000000000000000000000000000000000000000a11ce",
+        "0700000000000000",
+        "02",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+        "0d6170746f735f6163636f756e74",
+        "087472616e73666572",
+        "00",
+        "02",
+        "20",
+        "0000000000000000000000000000000000000000000000000000000000000b0b",
+        "08",
+        "e803000000000000",
+        "d007000000000000",
+        "6400000000000000",
+        "00f1536500000000",
+        "02",
+    );
+
+    #[test]
+    fn sender_signatures_verify_only_for_their_transaction() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let mut transaction = transfer_transaction();
+        transaction.sender =
+            AccountAddress::from_ed25519_public_key(key.verifying_key().as_bytes());
+        let fee_payer: AccountAddress = "0xfee".parse().unwrap();
+
+        for fee_payer in [None, Some(fee_payer)] {
+            let authenticator = sign_payment(&transaction, fee_payer, &key).unwrap();
+            verify_sender(&transaction, fee_payer, &authenticator).unwrap();
+
+            let mut tampered = transaction.clone();
+            tampered.sequence_number += 1;
+            assert!(verify_sender(&tampered, fee_payer, &authenticator).is_err());
+        }
+
+        // Signed as self-funded, presented as sponsored.
+        let authenticator = sign_payment(&transaction, None, &key).unwrap();
+        assert!(verify_sender(&transaction, Some(fee_payer), &authenticator).is_err());
+
+        // Someone else's key over the same transaction.
+        let other = sign_payment(&transaction, None, &SigningKey::from_bytes(&[8u8; 32])).unwrap();
+        assert!(verify_sender(&transaction, None, &other).is_err());
+    }
+
+    #[test]
+    fn account_authenticator_matches_aptos_layout() {
+        let authenticator = AccountAuthenticator::Ed25519 {
+            public_key: vec![1; 32],
+            signature: vec![2; 64],
+        };
+        let bytes = bcs::to_bytes(&authenticator).unwrap();
+
+        // Variant tag 0, then the length-prefixed key and signature.
+        assert_eq!(bytes.len(), 1 + 1 + 32 + 1 + 64);
+        assert_eq!((bytes[0], bytes[1], bytes[34]), (0, 32, 64));
+    }
+
+    #[test]
+    fn fee_payer_transaction_matches_aptos_layout() {
+        let transaction = transfer_transaction();
+        let fee_payer: AccountAddress = "0xfee".parse().unwrap();
+        let signer = |byte| AccountAuthenticator::Ed25519 {
+            public_key: vec![byte; 32],
+            signature: vec![byte; 64],
+        };
+        let signed =
+            SignedTransaction::payment(transaction, signer(1), Some((fee_payer, signer(2))));
+        let bytes = bcs::to_bytes(&signed).unwrap();
+
+        // The raw transaction, variant tag 3 (`FeePayer`), the sender, no
+        // secondary signers, then the fee payer's address and signature.
+        let raw_len = TRANSFER_TRANSACTION_BCS.len() / 2;
+        let authenticator = 1 + 1 + 32 + 1 + 64;
+        let rest = &bytes[raw_len..];
+        assert_eq!(rest.len(), 1 + authenticator + 2 + 32 + authenticator);
+        assert_eq!((rest[0], rest[1]), (3, 0));
+        assert_eq!(&rest[1 + authenticator..3 + authenticator], &[0, 0]);
+        assert_eq!(
+            &rest[3 + authenticator..35 + authenticator],
+            &fee_payer.0[..]
+        );
+    }
+
+    #[test]
+    fn signing_salt_is_the_hashed_domain() {
+        assert_eq!(
+            Sha3_256::digest(b"APTOS::RawTransaction").as_slice(),
+            RAW_TRANSACTION_SALT
+        );
+    }
+
+    #[test]
+    fn raw_transaction_matches_reference_encoding() {
+        let bytes = bcs::to_bytes(&transfer_transaction()).unwrap();
+        assert_eq!(hex::encode(bytes), TRANSFER_TRANSACTION_BCS);
+    }
+
+    #[test]
+    fn self_funded_payment_round_trips() {
+        let transaction = transfer_transaction();
+        let bytes = encode_payment(&transaction, None).unwrap();
+
+        assert_eq!(hex::encode(&bytes), TRANSFER_TRANSACTION_BCS);
+        assert_eq!(decode_payment(&bytes, false).unwrap(), transaction);
+        assert!(decode_payment(&bytes, true).is_err());
+    }
+
+    #[test]
+    fn fee_payer_payment_round_trips() {
+        let transaction = transfer_transaction();
+        let fee_payer: AccountAddress = "0xfee".parse().unwrap();
+        let bytes = encode_payment(&transaction, Some(fee_payer)).unwrap();
+
+        // Variant tag 1 (`MultiAgentWithFeePayer`), the raw transaction, no
+        // secondary signers, then the fee payer.
+        let expected = format!(
+            "01{}00{}",
+            TRANSFER_TRANSACTION_BCS,
+            hex::encode(fee_payer.0)
+        );
+        assert_eq!(hex::encode(&bytes), expected);
+        assert_eq!(decode_payment(&bytes, true).unwrap(), transaction);
+    }
+
+    #[test]
+    fn transfer_payload_reads_back() {
+        let recipient: AccountAddress = "0xb0b".parse().unwrap();
+        let payload =
+            TransactionPayload::transfer("0x1::aptos_coin::AptosCoin", recipient, 1000).unwrap();
+
+        assert_eq!(
+            payload.as_transfer(),
+            Some(Transfer {
+                asset: "0x0000000000000000000000000000000000000000000000000000000000000001::aptos_coin::AptosCoin".to_string(),
+                recipient,
+                amount: 1000,
+            })
+        );
+    }
+}
@@ -0,0 +1,195 @@
+use anyhow::Result;
+use colored::Colorize;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::Confirm;
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+/// How commands print their results (`--format`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Colored progress lines and an aligned summary table.
+    #[default]
+    Table,
+    /// A single JSON object on stdout; progress lines are suppressed.
+    Json,
+    /// Like `table`, without colors, for logs and pipes.
+    Plain,
+}
+
+static FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// Sets the output format for the rest of the process. Only the first call
+/// has an effect.
+pub fn set_format(format: OutputFormat) {
+    let _ = FORMAT.set(format);
+}
+
+pub fn format() -> OutputFormat {
+    FORMAT.get().copied().unwrap_or_default()
+}
+
+/// Whether `--format json` is active, also selected by the deprecated
+/// per-command `--json` flags.
+pub fn is_json() -> bool {
+    format() == OutputFormat::Json
+}
+
+/// Decides once, for the whole process, whether output is colored, so every
+/// `colored` value and `dialoguer` prompt agrees. Colors are off for
+/// `--no-color`, `--format plain|json`, and a non-empty `NO_COLOR`; otherwise
+/// `CLICOLOR_FORCE` turns them on, `CLICOLOR=0` turns them off, and by
+/// default they follow whether stdout is a terminal. Call after
+/// [`set_format`].
+pub fn set_color(no_color: bool) {
+    let env_set = |name: &str| std::env::var_os(name).is_some_and(|value| !value.is_empty());
+    let env_is_zero = |name: &str| std::env::var_os(name).is_some_and(|value| value == "0");
+
+    let enabled = if no_color || format() != OutputFormat::Table || env_set("NO_COLOR") {
+        false
+    } else if env_set("CLICOLOR_FORCE") && !env_is_zero("CLICOLOR_FORCE") {
+        true
+    } else if env_is_zero("CLICOLOR") {
+        false
+    } else {
+        std::io::stdout().is_terminal()
+    };
+
+    colored::control::set_override(enabled);
+    dialoguer::console::set_colors_enabled(enabled);
+    dialoguer::console::set_colors_enabled_stderr(enabled);
+}
+
+/// Prints a command's opening line, e.g. `Creating wallet...`.
+pub fn header(msg: &str) {
+    if !is_json() {
+        println!("{}", msg.cyan());
+    }
+}
+
+/// Prints an empty separator line, except in JSON output.
+pub fn blank_line() {
+    if !is_json() {
+        println!();
+    }
+}
+
+/// Prints a numbered progress step, e.g. `  Step 2: Saving wallet...`.
+pub fn step(n: usize, msg: &str) {
+    if !is_json() {
+        println!("{}", format!("  Step {}: {}...", n, msg).dimmed());
+    }
+}
+
+/// Prints a completed sub-task under the current step.
+pub fn success(msg: &str) {
+    if !is_json() {
+        println!("{}", format!("  ✓ {}", msg).green().dimmed());
+    }
+}
+
+/// Prints a non-fatal problem the user should know about. With `--format
+/// json` it goes to stderr so stdout stays parseable.
+pub fn warn(msg: &str) {
+    if is_json() {
+        eprintln!("⚠ {}", msg);
+    } else {
+        println!("{}", format!("  ⚠ {}", msg).yellow().dimmed());
+    }
+}
+
+/// Prints a neutral note, such as a step that was skipped.
+pub fn info(msg: &str) {
+    if !is_json() {
+        println!("{}", format!("  ℹ {}", msg).dimmed());
+    }
+}
+
+/// Prints a line of a running server's log, such as a request it served.
+/// With `--format json` it goes to stderr so stdout holds only the JSON
+/// result.
+pub fn log(line: impl std::fmt::Display) {
+    if is_json() {
+        eprintln!("{}", line);
+    } else {
+        println!("{}", line);
+    }
+}
+
+/// Prints the headline result of a command.
+pub fn done(msg: &str) {
+    if !is_json() {
+        println!("{}", format!("✓ {}", msg).green().bold());
+    }
+}
+
+/// Prints a value as pretty JSON on stdout.
+pub fn print_json(value: &impl serde::Serialize) -> anyhow::Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Prints the closing key/value table every command ends with. Keys are
+/// aligned; values are printed as given so callers can color them. With
+/// `--format json`, prints the pairs as one JSON object instead, keyed by
+/// the snake_cased labels.
+pub fn summary(title: &str, pairs: &[(&str, String)]) {
+    if is_json() {
+        let object: serde_json::Map<String, serde_json::Value> = pairs
+            .iter()
+            .map(|(key, value)| {
+                (
+                    key.to_lowercase().replace(' ', "_"),
+                    serde_json::Value::String(value.clone()),
+                )
+            })
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::Value::Object(object)).unwrap_or_default()
+        );
+        return;
+    }
+
+    let width = pairs.iter().map(|(key, _)| key.len()).max().unwrap_or(0) + 1;
+
+    println!();
+    println!("{}", title.cyan().bold());
+    for (key, value) in pairs {
+        println!("  {:<width$} {}", format!("{}:", key), value, width = width);
+    }
+}
+
+/// Guards an action that moves real funds: describes it on stderr (keeping
+/// `--json` output clean) and asks before going ahead. `yes` skips the
+/// prompt; without a terminal there is no one to ask, so `--yes` is required.
+pub fn confirm_mainnet(action: &str, details: &[(&str, String)], yes: bool) -> Result<()> {
+    if yes {
+        return Ok(());
+    }
+
+    eprintln!(
+        "{}",
+        format!("⚠ {} on mainnet (real funds)", action)
+            .yellow()
+            .bold()
+    );
+    for (key, value) in details {
+        eprintln!("  {}: {}", key, value);
+    }
+
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!("Refusing to act on mainnet without confirmation; pass --yes to proceed");
+    }
+
+    let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Continue on mainnet?")
+        .default(false)
+        .interact()?;
+
+    if !confirmed {
+        anyhow::bail!("Aborted; nothing was sent to mainnet");
+    }
+
+    Ok(())
+}
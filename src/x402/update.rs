@@ -0,0 +1,246 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::x402::config;
+use crate::x402::ui;
+
+/// The version of this binary.
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Where the latest published version is looked up: the crates.io API for
+/// this crate.
+pub const DEFAULT_VERSION_URL: &str = "https://crates.io/api/v1/crates/x402-cli";
+
+/// Environment variable pointing the version check at another URL.
+pub const X402_VERSION_URL_ENV: &str = "X402_VERSION_CHECK_URL";
+
+/// Environment variable that turns off the automatic daily check, like
+/// `--no-update-check`.
+pub const X402_NO_UPDATE_CHECK_ENV: &str = "X402_NO_UPDATE_CHECK";
+
+pub const CHANGELOG_URL: &str = "https://github.com/sambhuyadav/x402-Developer-CLI#changelog";
+
+/// File in the x402 directory recording the last automatic check.
+const CACHE_FILE_NAME: &str = "update-check.json";
+const CHECK_INTERVAL_SECS: i64 = 24 * 60 * 60;
+const REQUEST_TIMEOUT_SECS: u64 = 3;
+/// How long a finished command waits for an automatic check still in
+/// flight before giving up on it.
+const NOTIFY_WAIT_MS: u64 = 500;
+
+#[derive(Serialize, Deserialize)]
+struct Cache {
+    checked_at: i64,
+    latest: String,
+}
+
+/// The outcome of comparing this binary against the latest release.
+#[derive(Serialize)]
+pub struct VersionStatus {
+    pub current: String,
+    pub latest: String,
+    pub update_available: bool,
+    pub changelog: String,
+}
+
+impl VersionStatus {
+    fn new(latest: String) -> Self {
+        VersionStatus {
+            update_available: is_newer(&latest, CURRENT_VERSION),
+            current: CURRENT_VERSION.to_string(),
+            latest,
+            changelog: CHANGELOG_URL.to_string(),
+        }
+    }
+}
+
+fn version_url() -> String {
+    std::env::var(X402_VERSION_URL_ENV)
+        .ok()
+        .filter(|url| !url.is_empty())
+        .unwrap_or_else(|| DEFAULT_VERSION_URL.to_string())
+}
+
+/// Asks the version URL for the latest published version. Accepts the
+/// crates.io crate response (`crate.max_stable_version`, else
+/// `crate.max_version`) or a plain `{ "version": "..." }`.
+pub async fn fetch_latest_version() -> Result<String> {
+    let url = version_url();
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        // crates.io rejects requests without a user agent.
+        .user_agent(format!("x402-cli/{}", CURRENT_VERSION))
+        .build()?;
+
+    let body: Value = client
+        .get(&url)
+        .send()
+        .await
+        .with_context(|| format!("Failed to reach {}", url))?
+        .error_for_status()
+        .with_context(|| format!("Version lookup at {} failed", url))?
+        .json()
+        .await
+        .with_context(|| format!("Unexpected version response from {}", url))?;
+
+    let latest = [
+        &body["crate"]["max_stable_version"],
+        &body["crate"]["max_version"],
+        &body["version"],
+    ]
+    .into_iter()
+    .find_map(Value::as_str)
+    .map(str::to_string);
+    latest.with_context(|| format!("No version found in the response from {}", url))
+}
+
+/// Whether `latest` is a higher `major.minor.patch` than `current`.
+/// Anything after a `-` or `+` is ignored.
+fn is_newer(latest: &str, current: &str) -> bool {
+    fn parts(version: &str) -> Vec<u64> {
+        version
+            .trim_start_matches('v')
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    }
+    parts(latest) > parts(current)
+}
+
+/// `x402 version-check`: always asks the version URL, ignoring the daily
+/// cache. An unreachable URL is a warning, not a failure.
+pub async fn run() -> Result<()> {
+    let latest = match fetch_latest_version().await {
+        Ok(latest) => latest,
+        Err(e) => {
+            if ui::is_json() {
+                return ui::print_json(&serde_json::json!({
+                    "current": CURRENT_VERSION,
+                    "latest": null,
+                    "error": format!("{:#}", e),
+                }));
+            }
+            ui::warn(&format!("Couldn't check for updates: {:#}", e));
+            println!("  Current version: {}", CURRENT_VERSION);
+            return Ok(());
+        }
+    };
+    let _ = write_cache(&latest);
+
+    let status = VersionStatus::new(latest);
+    if ui::is_json() {
+        return ui::print_json(&status);
+    }
+
+    if status.update_available {
+        ui::warn(&format!(
+            "x402-cli {} is available (you have {})",
+            status.latest, status.current
+        ));
+        println!("  Update with: cargo install x402-cli");
+    } else {
+        ui::done(&format!("x402-cli {} is up to date", status.current));
+    }
+    println!("  Changelog: {}", status.changelog.cyan());
+
+    Ok(())
+}
+
+fn cache_file() -> Result<PathBuf> {
+    Ok(config::x402_dir()?.join(CACHE_FILE_NAME))
+}
+
+fn read_cache() -> Option<Cache> {
+    let data = fs::read_to_string(cache_file().ok()?).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+fn write_cache(latest: &str) -> Result<()> {
+    let path = cache_file()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let cache = Cache {
+        checked_at: chrono::Utc::now().timestamp(),
+        latest: latest.to_string(),
+    };
+    fs::write(&path, serde_json::to_string(&cache)?)?;
+    Ok(())
+}
+
+/// An automatic check started alongside a command: the cached latest
+/// version, and a refresh in flight when the cache was over a day old.
+pub struct DailyCheck {
+    cached: Option<String>,
+    refresh: Option<tokio::task::JoinHandle<Option<String>>>,
+}
+
+/// Starts the once-a-day check, unless it is turned off by
+/// `--no-update-check` (`disabled`) or `X402_NO_UPDATE_CHECK`, or stderr
+/// isn't a terminal (scripts and CI). A stale cache is refreshed in the
+/// background while the command runs.
+pub fn start_daily_check(disabled: bool) -> Option<DailyCheck> {
+    let env_disabled = std::env::var_os(X402_NO_UPDATE_CHECK_ENV).is_some_and(|v| !v.is_empty());
+    if disabled || env_disabled || !std::io::stderr().is_terminal() {
+        return None;
+    }
+
+    let cache = read_cache();
+    let fresh = cache.as_ref().is_some_and(|cache| {
+        chrono::Utc::now().timestamp() - cache.checked_at < CHECK_INTERVAL_SECS
+    });
+    let cached = cache.map(|cache| cache.latest);
+    // A failed lookup is recorded too, keeping the last known version, so
+    // an offline machine retries daily rather than on every command.
+    let refresh = (!fresh).then(|| {
+        let known = cached
+            .clone()
+            .unwrap_or_else(|| CURRENT_VERSION.to_string());
+        tokio::spawn(async move {
+            let latest = fetch_latest_version().await;
+            let _ = write_cache(latest.as_deref().unwrap_or(&known));
+            latest.ok()
+        })
+    });
+
+    Some(DailyCheck { cached, refresh })
+}
+
+impl DailyCheck {
+    /// Prints a one-line notice on stderr when a newer version is known.
+    /// Waits briefly for a refresh still in flight; any failure is silent.
+    pub async fn notify(self) {
+        let mut latest = self.cached;
+        if let Some(refresh) = self.refresh {
+            if let Ok(Ok(Some(fetched))) =
+                tokio::time::timeout(Duration::from_millis(NOTIFY_WAIT_MS), refresh).await
+            {
+                latest = Some(fetched);
+            }
+        }
+
+        let Some(latest) = latest else {
+            return;
+        };
+        if is_newer(&latest, CURRENT_VERSION) {
+            eprintln!();
+            eprintln!(
+                "{}",
+                format!(
+                    "  A new x402-cli is available: {} → {}. Run `x402 version-check` for details, or set {}=1 to stop these notices",
+                    CURRENT_VERSION, latest, X402_NO_UPDATE_CHECK_ENV
+                )
+                .yellow()
+            );
+        }
+    }
+}
@@ -1,22 +1,279 @@
 use anyhow::{Context, Result};
 use colored::Colorize;
-use ed25519_dalek::{SigningKey, VerifyingKey};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use zeroize::Zeroizing;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::x402::error::X402Error;
+use crate::x402::ui;
+
+/// Octas requested from the faucet (1 APT).
+pub const FAUCET_AMOUNT: u64 = 100_000_000;
+/// How long `fund_from_faucet` waits for the funds to show up on-chain.
+const FAUCET_CONFIRM_TIMEOUT_SECS: u64 = 30;
+/// Retries after a failed faucet request when `--fund-retries` isn't given.
+pub const DEFAULT_FAUCET_RETRIES: u32 = 3;
+/// First backoff between faucet attempts; doubles after each retry.
+const FAUCET_BACKOFF_MS: u64 = 1000;
+/// Longest backoff between faucet attempts.
+const FAUCET_MAX_BACKOFF_MS: u64 = 16_000;
+/// A rate limit asking to wait longer than this fails right away instead of
+/// blocking the command.
+const FAUCET_MAX_RETRY_WAIT_SECS: u64 = 60;
+const FAUCET_POLL_INTERVAL_MS: u64 = 1000;
+/// How long `transfer` waits for a submitted transaction to commit.
+const TRANSFER_CONFIRM_TIMEOUT_SECS: u64 = 30;
+/// APT, the coin `transfer` sends.
+const APTOS_COIN: &str = "0x1::aptos_coin::AptosCoin";
+
+/// A saved wallet. The private key and seed phrase are zeroed when the
+/// wallet is dropped and left out of `Debug`; read them through
+/// [`private_key`](Self::private_key) and [`seed_phrase`](Self::seed_phrase)
+/// only where the plaintext is needed.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Wallet {
     pub address: String,
-    pub private_key: String,
+    private_key: Zeroizing<String>,
     pub network: String,
-    pub seed_phrase: String,
+    seed_phrase: Zeroizing<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    /// Write the wallet file as single-line JSON. Set by `--compact`, and
+    /// kept from the file a wallet was loaded from so rewrites keep its
+    /// layout.
+    #[serde(skip)]
+    pub compact: bool,
+}
+
+/// Whether `value` is a well-formed Aptos account address: `0x` followed by
+/// exactly 64 hex digits, the form wallet files are named by.
+pub fn is_valid_aptos_address(value: &str) -> bool {
+    value
+        .strip_prefix("0x")
+        .is_some_and(|hex| hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Whether `value` is a Move address as written in type names and asset
+/// identifiers: `0x` followed by 1 to 64 hex digits, so `0x1` is accepted.
+pub fn is_valid_move_address(value: &str) -> bool {
+    value.strip_prefix("0x").is_some_and(|hex| {
+        !hex.is_empty() && hex.len() <= 64 && hex.chars().all(|c| c.is_ascii_hexdigit())
+    })
+}
+
+/// Parses an address argument such as `--pay-to`, rejecting malformed ones
+/// before they reach a config file or payment requirements.
+pub fn parse_address(value: &str) -> std::result::Result<String, String> {
+    if is_valid_aptos_address(value) {
+        Ok(value.to_string())
+    } else {
+        Err(X402Error::InvalidAddress(value.to_string()).to_string())
+    }
+}
+
+/// Parses a hex-encoded (optionally `0x`-prefixed) 32-byte Ed25519 public key.
+pub fn parse_public_key(value: &str) -> Result<VerifyingKey> {
+    let bytes: [u8; 32] = hex::decode(value.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .with_context(|| format!("'{}' is not a 32-byte hex public key", value))?;
+
+    VerifyingKey::from_bytes(&bytes)
+        .map_err(|_| anyhow::anyhow!("'{}' is not a valid Ed25519 public key", value))
+}
+
+/// Parses a hex-encoded (optionally `0x`-prefixed) 64-byte Ed25519 signature,
+/// as printed by `wallet sign`.
+pub fn parse_signature(value: &str) -> Result<Signature> {
+    let bytes: [u8; 64] = hex::decode(value.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .with_context(|| format!("'{}' is not a 64-byte hex signature", value))?;
+
+    Ok(Signature::from_bytes(&bytes))
+}
+
+/// Whether `signature` over `message` was made by `public_key`'s private key.
+/// Uses strict verification, which also rejects malleable signatures.
+pub fn verify_signature(public_key: &VerifyingKey, message: &[u8], signature: &Signature) -> bool {
+    public_key.verify_strict(message, signature).is_ok()
+}
+
+/// Submits a signed transaction to `fullnode` and returns its hash. With
+/// `wait`, polls until the transaction commits and fails if it didn't
+/// succeed.
+pub async fn submit_transaction(
+    client: &reqwest::Client,
+    fullnode: &str,
+    signed: &crate::x402::tx::SignedTransaction,
+    wait: bool,
+) -> Result<String> {
+    let response = client
+        .post(format!("{}/transactions", fullnode))
+        .header(
+            reqwest::header::CONTENT_TYPE,
+            crate::x402::tx::SIGNED_TRANSACTION_CONTENT_TYPE,
+        )
+        .body(bcs::to_bytes(signed).context("Failed to BCS-encode the signed transaction")?)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .context("Failed to submit the transaction")?;
+    let status = response.status();
+    let body: serde_json::Value = response.json().await.unwrap_or_default();
+    if !status.is_success() {
+        anyhow::bail!(
+            "Fullnode rejected the transaction ({}): {}",
+            status,
+            body["message"].as_str().unwrap_or("no reason given")
+        );
+    }
+    let hash = body["hash"]
+        .as_str()
+        .context("Fullnode accepted the transaction but returned no transaction hash")?
+        .to_string();
+
+    if !wait {
+        return Ok(hash);
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(TRANSFER_CONFIRM_TIMEOUT_SECS);
+    loop {
+        let committed: Option<serde_json::Value> = match client
+            .get(format!("{}/transactions/by_hash/{}", fullnode, hash))
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await
+        {
+            Ok(response) if response.status().is_success() => response.json().await.ok(),
+            _ => None,
+        };
+        if let Some(committed) = committed.filter(|t| t["type"] != "pending_transaction") {
+            if committed["success"].as_bool() == Some(true) {
+                return Ok(hash);
+            }
+            anyhow::bail!(
+                "Transaction {} failed on-chain: {}",
+                hash,
+                committed["vm_status"].as_str().unwrap_or("unknown status")
+            );
+        }
+
+        if Instant::now() >= deadline {
+            anyhow::bail!(
+                "Transaction {} was submitted but hasn't committed after {}s",
+                hash,
+                TRANSFER_CONFIRM_TIMEOUT_SECS
+            );
+        }
+        tokio::time::sleep(Duration::from_millis(FAUCET_POLL_INTERVAL_MS)).await;
+    }
+}
+
+/// The faucet's explanation for a failure: the `message` and any
+/// `rejection_reasons` from its JSON error body, else the raw body.
+fn faucet_message(body: &str) -> String {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(body) else {
+        return body.trim().to_string();
+    };
+
+    let mut parts: Vec<String> = Vec::new();
+    if let Some(message) = value.get("message").and_then(|m| m.as_str()) {
+        parts.push(message.to_string());
+    }
+    if let Some(reasons) = value.get("rejection_reasons").and_then(|r| r.as_array()) {
+        parts.extend(
+            reasons
+                .iter()
+                .filter_map(|reason| reason.get("reason").and_then(|r| r.as_str()))
+                .map(str::to_string),
+        );
+    }
+
+    if parts.is_empty() {
+        body.trim().to_string()
+    } else {
+        parts.join(": ")
+    }
+}
+
+/// Finds a wait such as "try again in 30 seconds" or "retry after 5
+/// minutes" in a faucet rate-limit message.
+fn parse_wait_hint(body: &str) -> Option<Duration> {
+    let lower = body.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    words.windows(3).find_map(|window| {
+        if !matches!(window[0], "in" | "after") {
+            return None;
+        }
+        let amount: u64 = window[1].parse().ok()?;
+        let unit = window[2].trim_end_matches('s');
+        let secs = match unit {
+            "second" | "sec" | "" => amount,
+            "minute" | "min" => amount.saturating_mul(60),
+            "hour" | "hr" | "h" => amount.saturating_mul(3600),
+            _ => return None,
+        };
+        Some(Duration::from_secs(secs))
+    })
+}
+
+/// Renders a wait as `45s`, `3m 20s`, or `2h 5m`.
+fn format_wait(delay: Duration) -> String {
+    let secs = delay.as_secs();
+    if secs == 0 {
+        format!("{}ms", delay.as_millis())
+    } else if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m {}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h {}m", secs / 3600, secs % 3600 / 60)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FaucetOutcome {
+    /// The faucet accepted the request. `balance` is the confirmed on-chain
+    /// balance in octas, or `None` when confirmation was skipped.
+    Funded {
+        balance: Option<u64>,
+    },
+    /// The faucet accepted the request but the funds did not appear before
+    /// the confirmation timeout.
+    Unconfirmed,
+    Skipped,
+    Failed(String),
+    /// Another wallet sent `amount` octas (`--fund-from`) in `transaction`.
+    /// `confirmed` is false when waiting for it to commit was skipped.
+    Transferred {
+        amount: u64,
+        transaction: String,
+        confirmed: bool,
+    },
+}
+
+/// How `fund_from_faucet` requests funds.
+#[derive(Debug, Clone, Copy)]
+pub struct FaucetOptions {
+    /// Poll the balance until the funds are visible on-chain.
+    pub wait: bool,
+    /// Retries after an unreachable faucet, a 429, or a 5xx.
+    pub retries: u32,
+    /// Don't report retries and rate limits (e.g. for JSON output).
+    pub quiet: bool,
 }
 
 impl Wallet {
     pub async fn create(network: &str) -> Result<Self> {
-        println!("{}", "Creating wallet...".cyan());
-
         let seed_phrase = Self::generate_seed_phrase();
         let (private_key, address) = Self::derive_keys(&seed_phrase);
 
@@ -25,173 +282,550 @@ impl Wallet {
             private_key,
             network: network.to_string(),
             seed_phrase,
+            alias: None,
+            compact: false,
         };
 
-        println!("{}", "✓ Wallet created successfully".green().bold());
-
         Ok(wallet)
     }
 
-    pub fn save_to_file(&self) -> Result<()> {
-        let mut wallets_dir = dirs::home_dir().context("Failed to determine home directory")?;
+    pub fn wallets_dir() -> Result<PathBuf> {
+        Ok(crate::x402::config::x402_dir()?.join("wallets"))
+    }
 
-        wallets_dir.push(".x402");
-        wallets_dir.push("wallets");
+    pub fn save_to_file(&self) -> Result<PathBuf> {
+        self.save_to_dir(&Self::wallets_dir()?)
+    }
 
-        fs::create_dir_all(&wallets_dir)
-            .with_context(|| format!("Failed to create wallets directory"))?;
+    /// Writes the wallet to `<dir>/<address>.json`, creating `dir` if needed:
+    /// pretty-printed, or on one line if [`compact`](Self::compact).
+    pub fn save_to_dir(&self, wallets_dir: &Path) -> Result<PathBuf> {
+        fs::create_dir_all(wallets_dir).with_context(|| {
+            format!(
+                "Failed to create wallets directory: {}",
+                wallets_dir.display()
+            )
+        })?;
 
         let wallet_file = wallets_dir.join(format!("{}.json", self.address));
 
-        let wallet_data =
-            serde_json::to_string_pretty(self).context("Failed to serialize wallet data")?;
+        let wallet_data = if self.compact {
+            serde_json::to_string(self)
+        } else {
+            serde_json::to_string_pretty(self)
+        }
+        .context("Failed to serialize wallet data")?;
 
         fs::write(&wallet_file, wallet_data)
             .with_context(|| format!("Failed to save wallet file: {}", wallet_file.display()))?;
 
-        let display = wallet_file.display();
-        println!(
-            "{}",
-            format!("  ✓ Wallet saved to {}", display).cyan().dimmed()
-        );
+        Ok(wallet_file)
+    }
 
-        Ok(())
+    /// Replaces this saved wallet with a fresh keypair that keeps its alias
+    /// and network. The old file is renamed to `<address>.json.old` rather
+    /// than deleted; `load_all` only reads `.json` files, so the archived
+    /// wallet no longer claims the alias. Returns the new wallet, its file,
+    /// and the archive path.
+    pub async fn rotate(&self) -> Result<(Self, PathBuf, PathBuf)> {
+        let mut rotated = Self::create(&self.network).await?;
+        rotated.alias = self.alias.clone();
+        rotated.compact = self.compact;
+
+        let wallets_dir = Self::wallets_dir()?;
+        let old_file = wallets_dir.join(format!("{}.json", self.address));
+        let archive = wallets_dir.join(format!("{}.json.old", self.address));
+
+        fs::rename(&old_file, &archive)
+            .with_context(|| format!("Failed to archive wallet file: {}", old_file.display()))?;
+
+        match rotated.save_to_file() {
+            Ok(wallet_file) => Ok((rotated, wallet_file, archive)),
+            Err(e) => {
+                // Put the old wallet back so a failed rotation loses nothing.
+                let _ = fs::rename(&archive, &old_file);
+                Err(e)
+            }
+        }
     }
 
     pub fn import(private_key: &str, network: &str) -> Result<Self> {
-        println!("{}", "Importing wallet from private key...".cyan());
-
         let private_key_clean = private_key.trim_start_matches("0x");
-        let private_key_bytes = hex::decode(private_key_clean)
-            .map_err(|e| anyhow::anyhow!("Failed to decode private key hex: {}", e))?;
+        let signing_key = Self::parse_signing_key(private_key_clean)?;
+        let public_key = signing_key.verifying_key();
+        let address = Self::derive_address_from_public_key(public_key.as_bytes());
 
-        if private_key_bytes.len() < 32 {
-            return Err(anyhow::anyhow!("Private key must be at least 32 bytes"));
-        }
+        let wallet = Wallet {
+            address,
+            private_key: Zeroizing::new(private_key_clean.to_string()),
+            network: network.to_string(),
+            seed_phrase: Zeroizing::default(),
+            alias: None,
+            compact: false,
+        };
 
-        let mut key_bytes = [0u8; 32];
-        key_bytes.copy_from_slice(&private_key_bytes[..32]);
+        ui::success("Derived keys from private key");
 
-        let signing_key = SigningKey::from_bytes(&key_bytes);
-        let public_key = signing_key.verifying_key();
-        let public_key_bytes = public_key.as_bytes();
-        let address = hex::encode(&public_key_bytes[1..]);
-        let address = format!("0x{}", address);
+        Ok(wallet)
+    }
+
+    pub fn import_seed_phrase(seed_phrase: &str, network: &str) -> Result<Self> {
+        let seed_phrase = Self::normalize_seed_phrase(seed_phrase)?;
+        let (private_key, address) = Self::derive_keys(&seed_phrase);
 
         let wallet = Wallet {
             address,
-            private_key: private_key_clean.to_string(),
+            private_key,
             network: network.to_string(),
-            seed_phrase: String::new(),
+            seed_phrase,
+            alias: None,
+            compact: false,
         };
 
-        println!("{}", "✓ Wallet imported successfully".green().bold());
+        ui::success("Derived keys from seed phrase");
 
         Ok(wallet)
     }
 
-    pub fn load_from_address(address: &str) -> Result<Self> {
-        let mut wallets_dir = dirs::home_dir().context("Failed to determine home directory")?;
-        wallets_dir.push(".x402");
-        wallets_dir.push("wallets");
+    /// The private key as hex, for signing or showing on request.
+    pub fn private_key(&self) -> &str {
+        &self.private_key
+    }
 
-        let wallet_file = wallets_dir.join(format!("{}.json", address));
+    /// The seed phrase, empty for a wallet imported from a private key.
+    pub fn seed_phrase(&self) -> &str {
+        &self.seed_phrase
+    }
 
-        if !wallet_file.exists() {
-            anyhow::bail!("Wallet file not found: {}", wallet_file.display());
+    pub fn signing_key(&self) -> Result<SigningKey> {
+        Self::parse_signing_key(&self.private_key)
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Result<(Signature, VerifyingKey)> {
+        let signing_key = self.signing_key()?;
+        Ok((signing_key.sign(message), signing_key.verifying_key()))
+    }
+
+    pub fn verifying_key(&self) -> Result<VerifyingKey> {
+        Ok(self.signing_key()?.verifying_key())
+    }
+
+    /// Parses a hex Ed25519 private key: the 32-byte seed, or the 64-byte
+    /// `seed || public key` form some tools export, whose public key half
+    /// must match the seed.
+    fn parse_signing_key(private_key: &str) -> Result<SigningKey> {
+        let private_key_bytes = hex::decode(private_key.trim_start_matches("0x"))
+            .map(Zeroizing::new)
+            .map_err(|e| anyhow::anyhow!("Failed to decode private key hex: {}", e))?;
+
+        let (seed, public_key) = match private_key_bytes.len() {
+            32 => (&private_key_bytes[..], None),
+            64 => (&private_key_bytes[..32], Some(&private_key_bytes[32..])),
+            len => anyhow::bail!(
+                "Private key must be 32 bytes (64 hex characters), or 64 bytes of seed and \
+                 public key; got {} bytes",
+                len
+            ),
+        };
+
+        let mut key_bytes = Zeroizing::new([0u8; 32]);
+        key_bytes.copy_from_slice(seed);
+        let signing_key = SigningKey::from_bytes(&key_bytes);
+
+        if let Some(public_key) = public_key {
+            if public_key != signing_key.verifying_key().as_bytes() {
+                anyhow::bail!("The private key's public key half doesn't match its seed");
+            }
+        }
+
+        Ok(signing_key)
+    }
+
+    /// Loads a saved wallet by address or alias. Anything starting with `0x`
+    /// is treated as an address (aliases can't start with it) and must be
+    /// well-formed.
+    pub fn load(handle: &str) -> Result<Self> {
+        if handle.starts_with("0x") {
+            return Self::load_from_address(handle);
+        }
+
+        Self::find_by_alias(handle)?
+            .ok_or_else(|| X402Error::WalletNotFound(handle.to_string()).into())
+    }
+
+    pub fn find_by_alias(alias: &str) -> Result<Option<Self>> {
+        Ok(Self::load_all()?
+            .into_iter()
+            .find(|wallet| wallet.alias.as_deref() == Some(alias)))
+    }
+
+    pub fn load_all() -> Result<Vec<Self>> {
+        let wallets_dir = Self::wallets_dir()?;
+        if !wallets_dir.exists() {
+            return Ok(Vec::new());
         }
 
-        let wallet_data = fs::read_to_string(&wallet_file)
-            .with_context(|| format!("Failed to read wallet file: {}", wallet_file.display()))?;
+        let mut wallets = Vec::new();
+        for entry in fs::read_dir(&wallets_dir).context("Failed to read wallets directory")? {
+            let path = entry.context("Failed to read wallets directory")?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
 
-        let wallet: Wallet = serde_json::from_str(&wallet_data)
-            .context("Failed to parse wallet data")?;
+            if let Some(wallet) = Self::read_file_or_skip(&path)? {
+                wallets.push(wallet);
+            }
+        }
 
+        Ok(wallets)
+    }
+
+    /// Reads a wallet file. Invalid JSON is an `X402Error::CorruptWalletFile`
+    /// naming the file, rather than a bare parse error.
+    fn read_file(path: &Path) -> Result<Self> {
+        let wallet_data = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read wallet file: {}", path.display()))?;
+
+        let mut wallet: Self =
+            serde_json::from_str(&wallet_data).map_err(|e| X402Error::CorruptWalletFile {
+                path: path.display().to_string(),
+                reason: e.to_string(),
+            })?;
+        wallet.compact = !wallet_data.trim().contains('\n');
         Ok(wallet)
     }
 
+    /// Like [`read_file`](Self::read_file), but warns about a corrupt file
+    /// and returns `None`, so one bad file doesn't hide every other wallet.
+    /// The warning goes to stderr, keeping JSON output on stdout clean.
+    fn read_file_or_skip(path: &Path) -> Result<Option<Self>> {
+        match Self::read_file(path) {
+            Ok(wallet) => Ok(Some(wallet)),
+            Err(e) if e.downcast_ref::<X402Error>().is_some() => {
+                eprintln!("{}", format!("  ⚠ Skipping: {}", e).yellow());
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn set_alias(&mut self, alias: &str) -> Result<()> {
+        let alias = alias.trim();
+        if alias.is_empty() {
+            anyhow::bail!("Alias must not be empty");
+        }
+        if alias.starts_with("0x") {
+            anyhow::bail!("Alias must not start with 0x (it would be confused with an address)");
+        }
+
+        if let Some(existing) = Self::find_by_alias(alias)? {
+            if existing.address != self.address {
+                anyhow::bail!("Alias '{}' is already used by {}", alias, existing.address);
+            }
+        }
+
+        self.alias = Some(alias.to_string());
+        Ok(())
+    }
+
+    pub fn load_from_address(address: &str) -> Result<Self> {
+        if !is_valid_aptos_address(address) {
+            return Err(X402Error::InvalidAddress(address.to_string()).into());
+        }
+
+        let wallets_dir = Self::wallets_dir()?;
+
+        let wallet_file = wallets_dir.join(format!("{}.json", address));
+
+        if !wallet_file.exists() {
+            return Err(X402Error::WalletNotFound(address.to_string()).into());
+        }
+
+        Self::read_file(&wallet_file)
+    }
+
+    /// Returns the wallet set with `x402 wallet default`, falling back to the
+    /// first saved wallet.
     pub fn find_default() -> Result<Self> {
-        let mut wallets_dir = dirs::home_dir().context("Failed to determine home directory")?;
-        wallets_dir.push(".x402");
-        wallets_dir.push("wallets");
+        if let Some(default_wallet) = crate::x402::config::UserConfig::load()?.default_wallet {
+            return Self::load(&default_wallet)
+                .with_context(|| format!("Failed to load default wallet {}", default_wallet));
+        }
+
+        let wallets_dir = Self::wallets_dir()?;
 
         if !wallets_dir.exists() {
-            anyhow::bail!("No wallets directory found. Create a wallet first using `x402 wallet create`");
+            anyhow::bail!("No wallet specified and no wallets directory found. Pass --wallet <address> or create a wallet first using `x402 wallet create`");
         }
 
+        // Skip archived `<address>.json.old` files left by `wallet rotate`.
         let entries: Vec<_> = fs::read_dir(&wallets_dir)
             .context("Failed to read wallets directory")?
             .filter_map(|e| e.ok())
+            .filter(|e| e.path().extension().and_then(|ext| ext.to_str()) == Some("json"))
             .collect();
 
         if entries.is_empty() {
-            anyhow::bail!("No wallets found. Create a wallet first using `x402 wallet create`");
+            anyhow::bail!("No wallet specified and no saved wallets found. Pass --wallet <address> or create a wallet first using `x402 wallet create`");
         }
 
-        let first_wallet = entries
-            .first()
-            .context("No wallets found")?;
+        // The first wallet that parses; corrupt files are reported and skipped.
+        for entry in &entries {
+            if let Some(wallet) = Self::read_file_or_skip(&entry.path())? {
+                return Ok(wallet);
+            }
+        }
 
-        let wallet_file = first_wallet.path();
-        let wallet_data = fs::read_to_string(&wallet_file)
-            .with_context(|| format!("Failed to read wallet file: {}", wallet_file.display()))?;
+        anyhow::bail!("No readable wallets found; every saved wallet file is corrupt. Pass --wallet <address> or create a wallet first using `x402 wallet create`")
+    }
 
-        let wallet: Wallet = serde_json::from_str(&wallet_data)
-            .context("Failed to parse wallet data")?;
+    /// Requests funds from the network's faucet (in the chain registry;
+    /// networks without one are skipped). With `options.wait`, polls the account balance until
+    /// the funds are visible on-chain, since the faucet settles
+    /// asynchronously.
+    ///
+    /// Unreachable faucets, 429s, and 5xx responses are retried up to
+    /// `options.retries` times with exponential backoff (or the faucet's
+    /// requested delay); any other failure, or running out of retries, is an
+    /// `X402Error::FaucetUnavailable`.
+    pub async fn fund_from_faucet(&self, options: FaucetOptions) -> Result<FaucetOutcome> {
+        let FaucetOptions {
+            wait,
+            retries,
+            quiet,
+        } = options;
+        let Some(faucet_url) = crate::x402::chains::registry().faucet_url(&self.network) else {
+            return Ok(FaucetOutcome::Skipped);
+        };
 
-        Ok(wallet)
+        let client = reqwest::Client::new();
+        let starting_balance = if wait {
+            self.balance(&client).await.unwrap_or(0)
+        } else {
+            0
+        };
+
+        let request_body = serde_json::json!({
+            "address": self.address,
+            "amount": FAUCET_AMOUNT
+        });
+
+        let mut attempt = 0;
+        loop {
+            let (reason, requested_wait) = match client
+                .post(faucet_url)
+                .header("Content-Type", "application/json")
+                .json(&request_body)
+                .send()
+                .await
+            {
+                Ok(response) if response.status().is_success() => break,
+                Ok(response) => {
+                    let status = response.status();
+                    let retry_after = response
+                        .headers()
+                        .get(reqwest::header::RETRY_AFTER)
+                        .and_then(|value| value.to_str().ok())
+                        .and_then(|value| value.trim().parse::<u64>().ok())
+                        .map(Duration::from_secs);
+                    let body = response.text().await.unwrap_or_default();
+                    let reason = format!("{} - {}", status, faucet_message(&body));
+
+                    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                        let requested_wait = retry_after.or_else(|| parse_wait_hint(&body));
+                        if let Some(delay) = requested_wait {
+                            if delay.as_secs() > FAUCET_MAX_RETRY_WAIT_SECS {
+                                return Err(X402Error::FaucetUnavailable(format!(
+                                    "rate-limited, try again in {} ({})",
+                                    format_wait(delay),
+                                    reason
+                                ))
+                                .into());
+                            }
+                            if !quiet {
+                                ui::warn(&format!(
+                                    "Faucet rate-limited, try again in {}",
+                                    format_wait(delay)
+                                ));
+                            }
+                        }
+                        (reason, requested_wait)
+                    } else if status.is_server_error() {
+                        (reason, None)
+                    } else {
+                        return Err(X402Error::FaucetUnavailable(reason).into());
+                    }
+                }
+                Err(e) => (format!("could not reach the faucet: {}", e), None),
+            };
+
+            if attempt >= retries {
+                let tries = if retries == 0 {
+                    String::new()
+                } else {
+                    format!(" (gave up after {} attempts)", retries + 1)
+                };
+                return Err(X402Error::FaucetUnavailable(format!("{}{}", reason, tries)).into());
+            }
+            attempt += 1;
+
+            let backoff = Duration::from_millis(
+                (FAUCET_BACKOFF_MS << (attempt - 1).min(16)).min(FAUCET_MAX_BACKOFF_MS),
+            );
+            let delay = requested_wait.unwrap_or(backoff);
+            if !quiet {
+                ui::info(&format!(
+                    "Faucet request failed ({}); retry {}/{} in {}",
+                    reason,
+                    attempt,
+                    retries,
+                    format_wait(delay)
+                ));
+            }
+            tokio::time::sleep(delay).await;
+        }
+
+        if !wait {
+            return Ok(FaucetOutcome::Funded { balance: None });
+        }
+
+        let target = starting_balance.saturating_add(FAUCET_AMOUNT);
+        let deadline = Instant::now() + Duration::from_secs(FAUCET_CONFIRM_TIMEOUT_SECS);
+
+        loop {
+            if let Ok(balance) = self.balance(&client).await {
+                if balance >= target {
+                    return Ok(FaucetOutcome::Funded {
+                        balance: Some(balance),
+                    });
+                }
+            }
+
+            if Instant::now() >= deadline {
+                return Ok(FaucetOutcome::Unconfirmed);
+            }
+
+            tokio::time::sleep(Duration::from_millis(FAUCET_POLL_INTERVAL_MS)).await;
+        }
     }
 
-    pub async fn fund_from_faucet(&self) -> Result<()> {
-        if self.network != "testnet" {
-            println!(
-                "{}",
-                "  ℹ Skipping faucet funding (not on testnet)"
-                    .yellow()
-                    .dimmed()
+    /// Fails if the saved address isn't the private key's account. Wallets
+    /// saved before addresses were derived from the key's SHA3-256 name an
+    /// account the key can't sign for, which a fullnode would reject with an
+    /// opaque authentication error.
+    pub fn check_key_address(&self) -> Result<()> {
+        use crate::x402::tx::AccountAddress;
+
+        let key_address = Self::derive_address_from_public_key(self.verifying_key()?.as_bytes());
+        if key_address.parse::<AccountAddress>()? != self.address.parse::<AccountAddress>()? {
+            anyhow::bail!(
+                "Wallet {} doesn't match its private key, whose account is {}; \
+                 re-import the key with `x402 wallet import-private-key` to send from it",
+                self.address,
+                key_address
             );
-            return Ok(());
         }
+        Ok(())
+    }
 
-        let faucet_url = "https://faucet.testnet.aptoslabs.com";
+    /// Sends `amount` octas of APT to `recipient` in a transaction signed with
+    /// this wallet's key, and returns its hash. With `wait`, polls the
+    /// fullnode until the transaction commits and fails if it didn't succeed.
+    pub async fn transfer(&self, recipient: &str, amount: u64, wait: bool) -> Result<String> {
+        use crate::x402::test;
+        use crate::x402::tx::{self, RawTransaction, TransactionPayload};
 
+        self.check_key_address()?;
+        let signing_key = self.signing_key()?;
+        let sender: tx::AccountAddress = self.address.parse()?;
+
+        let fullnode = crate::x402::config::fullnode_url(&self.network)
+            .with_context(|| format!("No fullnode known for network '{}'", self.network))?;
         let client = reqwest::Client::new();
-        let request_body = serde_json::json!({
-            "address": self.address,
-            "amount": 100_000_000
-        });
 
+        let balance = self.balance(&client).await?;
+        if balance < amount {
+            anyhow::bail!(
+                "{} holds {} octas, not enough to send {}",
+                self.address,
+                balance,
+                amount
+            );
+        }
+
+        let gas_unit_price = test::estimate_gas_unit_price(&client, &self.network)
+            .await
+            .unwrap_or(test::DEFAULT_GAS_UNIT_PRICE);
+        let raw_txn = RawTransaction {
+            sender,
+            sequence_number: self.sequence_number(&client, &fullnode).await?,
+            payload: TransactionPayload::transfer(APTOS_COIN, recipient.parse()?, amount)?,
+            max_gas_amount: test::DEFAULT_MAX_GAS,
+            gas_unit_price,
+            expiration_timestamp_secs: chrono::Utc::now().timestamp() as u64
+                + test::DEFAULT_EXPIRATION_SECS,
+            chain_id: test::resolve_chain_id(&client, &self.network).await?,
+        };
+        let signed = raw_txn.sign(&signing_key)?;
+
+        submit_transaction(&client, &fullnode, &signed, wait).await
+    }
+
+    /// The account's next sequence number, from the fullnode.
+    pub async fn sequence_number(&self, client: &reqwest::Client, fullnode: &str) -> Result<u64> {
         let response = client
-            .post(faucet_url)
-            .header("Content-Type", "application/json")
-            .json(&request_body)
+            .get(format!("{}/accounts/{}", fullnode, self.address))
+            .timeout(Duration::from_secs(5))
             .send()
             .await
-            .context("Failed to contact faucet")?;
-
-        if response.status().is_success() {
-            let _output = response.text().await.unwrap_or_default();
-            println!(
-                "{}",
-                format!("  ✓ Funded with 1 APT from faucet")
-                    .green()
-                    .dimmed()
-            );
-        } else {
-            let status = response.status();
-            let error = response.text().await.unwrap_or_default();
-            println!(
-                "{}",
-                format!("  ⚠ Faucet request failed: {} - {}", status, error)
-                    .yellow()
-                    .dimmed()
-            );
+            .context("Failed to query the account")?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            anyhow::bail!("Account {} doesn't exist on-chain yet", self.address);
         }
+        let account: serde_json::Value = response
+            .error_for_status()
+            .context("Account query failed")?
+            .json()
+            .await
+            .context("Failed to read the account")?;
+        account["sequence_number"]
+            .as_str()
+            .and_then(|n| n.parse().ok())
+            .with_context(|| format!("Unexpected account response: {}", account))
+    }
 
-        Ok(())
+    /// Queries the account's APT balance, in octas, from the network's
+    /// fullnode. An account that does not exist on-chain yet has balance 0.
+    pub async fn balance(&self, client: &reqwest::Client) -> Result<u64> {
+        let fullnode = crate::x402::config::fullnode_url(&self.network)
+            .with_context(|| format!("No fullnode known for network '{}'", self.network))?;
+
+        let response = client
+            .get(format!(
+                "{}/accounts/{}/balance/0x1::aptos_coin::AptosCoin",
+                fullnode, self.address
+            ))
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await
+            .context("Failed to query account balance")?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(0);
+        }
+
+        if !response.status().is_success() {
+            anyhow::bail!("Balance query failed with status {}", response.status());
+        }
+
+        let body = response.text().await.context("Failed to read balance")?;
+        body.trim()
+            .trim_matches('"')
+            .parse()
+            .with_context(|| format!("Unexpected balance response: {}", body))
     }
 
-    fn generate_seed_phrase() -> String {
+    fn bip39_words() -> &'static [&'static str] {
         const WORDS: &[&str] = &[
             "abandon",
             "ability",
@@ -2246,38 +2880,73 @@ impl Wallet {
             "zoo",
         ];
 
+        WORDS
+    }
+
+    fn generate_seed_phrase() -> Zeroizing<String> {
+        let words = Self::bip39_words();
+
         use rand::Rng;
         let mut rng = rand::thread_rng();
         let mut phrase = Vec::new();
         for _ in 0..12 {
-            phrase.push(WORDS[rng.gen_range(0..WORDS.len())]);
+            phrase.push(words[rng.gen_range(0..words.len())]);
         }
-        phrase.join(" ")
+        Zeroizing::new(phrase.join(" "))
     }
 
-    fn derive_keys(seed: &str) -> (String, String) {
+    /// Normalizes a seed phrase and checks its length and words against the
+    /// wordlist used by `create`.
+    fn normalize_seed_phrase(seed_phrase: &str) -> Result<Zeroizing<String>> {
+        let words: Zeroizing<Vec<String>> = seed_phrase
+            .split_whitespace()
+            .map(|word| word.to_lowercase())
+            .collect::<Vec<_>>()
+            .into();
+
+        if !matches!(words.len(), 12 | 15 | 18 | 21 | 24) {
+            anyhow::bail!(
+                "Seed phrase must have 12, 15, 18, 21 or 24 words (got {})",
+                words.len()
+            );
+        }
+
+        let wordlist = Self::bip39_words();
+        let unknown: Vec<&str> = words
+            .iter()
+            .filter(|word| !wordlist.contains(&word.as_str()))
+            .map(|word| word.as_str())
+            .collect();
+        if !unknown.is_empty() {
+            anyhow::bail!(
+                "Seed phrase contains unknown word(s): {}",
+                unknown.join(", ")
+            );
+        }
+
+        Ok(Zeroizing::new(words.join(" ")))
+    }
+
+    fn derive_keys(seed: &str) -> (Zeroizing<String>, String) {
         let mut hasher = Sha256::new();
         hasher.update(seed.as_bytes());
         let hash_result = hasher.finalize();
 
-        let mut key_bytes = [0u8; 32];
+        let mut key_bytes = Zeroizing::new([0u8; 32]);
         key_bytes.copy_from_slice(&hash_result[..32]);
 
         let signing_key = SigningKey::from_bytes(&key_bytes);
         let verifying_key: VerifyingKey = signing_key.verifying_key();
 
-        let private_key_hex = hex::encode(signing_key.to_bytes());
-        let address = hex::encode(&verifying_key.as_bytes()[1..]);
-
-        let formatted_address = format!("0x{}", address);
-        let formatted_private_key = format!("0x{}", private_key_hex);
+        let private_key_hex = Zeroizing::new(hex::encode(signing_key.to_bytes()));
+        let formatted_address = Self::derive_address_from_public_key(verifying_key.as_bytes());
+        let formatted_private_key = Zeroizing::new(format!("0x{}", *private_key_hex));
 
         (formatted_private_key, formatted_address)
     }
 
     fn derive_address_from_public_key(public_key_bytes: &[u8]) -> String {
-        let address = hex::encode(&public_key_bytes[1..]);
-        format!("0x{}", address)
+        crate::x402::tx::AccountAddress::from_ed25519_public_key(public_key_bytes).to_string()
     }
 }
 
@@ -2286,10 +2955,95 @@ impl Default for Wallet {
         Wallet {
             address: "0x0000000000000000000000000000000000000000000000000000000000000000"
                 .to_string(),
-            private_key: "0x0000000000000000000000000000000000000000000000000000000000000000"
-                .to_string(),
+            private_key: Zeroizing::new(
+                "0x0000000000000000000000000000000000000000000000000000000000000000".to_string(),
+            ),
             network: "testnet".to_string(),
-            seed_phrase: String::new(),
+            seed_phrase: Zeroizing::default(),
+            alias: None,
+            compact: false,
         }
     }
 }
+
+impl std::fmt::Debug for Wallet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Wallet")
+            .field("address", &self.address)
+            .field("private_key", &"<redacted>")
+            .field("network", &self.network)
+            .field("seed_phrase", &"<redacted>")
+            .field("alias", &self.alias)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_derives_the_aptos_address() {
+        // Key bytes 0x00..0x1f; the address is SHA3-256(public key || 0x00),
+        // computed independently with Python's hashlib and cryptography.
+        let private_key = "0x000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+        let wallet = Wallet::import(private_key, "testnet").unwrap();
+
+        assert_eq!(
+            hex::encode(wallet.verifying_key().unwrap().as_bytes()),
+            "03a107bff3ce10be1d70dd18e74bc09967e4d6309ba50d5f1ddc8664125531b8"
+        );
+        assert_eq!(
+            wallet.address,
+            "0xdeb6bc1848ba9de44f8ebd4009dd666fe46ac921246500c18476dd1b96f1cb3a"
+        );
+    }
+
+    #[test]
+    fn check_key_address_rejects_legacy_addresses() {
+        let mut wallet = Wallet::import(&"ab".repeat(32), "testnet").unwrap();
+        wallet.check_key_address().unwrap();
+
+        // The old derivation: the public key without its first byte.
+        let public_key = wallet.verifying_key().unwrap();
+        wallet.address = format!("0x{}", hex::encode(&public_key.as_bytes()[1..]));
+        assert!(wallet.check_key_address().is_err());
+    }
+
+    #[test]
+    fn private_keys_must_be_32_bytes_or_a_matching_keypair() {
+        let seed = "ab".repeat(32);
+        let public_key = hex::encode(
+            Wallet::import(&seed, "testnet")
+                .unwrap()
+                .verifying_key()
+                .unwrap()
+                .as_bytes(),
+        );
+
+        assert!(Wallet::import(&format!("{}{}", seed, public_key), "testnet").is_ok());
+        assert!(Wallet::import(&format!("{}{}", seed, "cd".repeat(32)), "testnet").is_err());
+        assert!(Wallet::import(&format!("{}00", seed), "testnet").is_err());
+        assert!(Wallet::import(&"ab".repeat(31), "testnet").is_err());
+        assert!(Wallet::import("not hex", "testnet").is_err());
+    }
+
+    #[test]
+    fn account_addresses_need_all_64_digits() {
+        assert!(is_valid_aptos_address(&format!("0x{}", "0a".repeat(32))));
+        assert!(!is_valid_aptos_address("0x1"));
+        assert!(!is_valid_aptos_address(&"0a".repeat(32)));
+        assert!(!is_valid_aptos_address(&format!("0x{}", "zz".repeat(32))));
+
+        assert!(is_valid_move_address("0x1"));
+        assert!(!is_valid_move_address("0x"));
+        assert!(!is_valid_move_address(&format!("0x{}", "0".repeat(65))));
+    }
+
+    #[test]
+    fn addresses_are_full_length() {
+        let wallet = Wallet::import(&"ab".repeat(32), "testnet").unwrap();
+        assert_eq!(wallet.address.len(), 66);
+        assert!(is_valid_aptos_address(&wallet.address));
+    }
+}